@@ -0,0 +1,573 @@
+//! Derive macros for `sword-ai`.
+//!
+//! Use them through the re-exports of `sword-ai`, e.g.
+//! `sword_ai::openapi::ApiSchema`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit,
+    LitStr, Meta, Token, Type,
+};
+
+/// Derives `sword_ai::openapi::ApiSchema`, describing a type with the JSON
+/// Schema of its serde representation.
+///
+/// Doc comments become descriptions, and these serde attributes are
+/// followed: `rename`, `rename_all`, `default`, `skip`, `skip_serializing`,
+/// `skip_deserializing`, `skip_serializing_if`, `flatten`, `transparent`,
+/// `tag`, `content` and `untagged`. Fields and variants that are skipped in
+/// either direction are left out of the schema.
+#[proc_macro_derive(ApiSchema)]
+pub fn derive_api_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let container = Serde::parse(&input.attrs)?;
+    if let Some(rule) = &container.rename_all {
+        check_rule(rule, &input.ident)?;
+    }
+    let description = docs(&input.attrs);
+    let ident = &input.ident;
+
+    let (body, named) = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(_) if !container.transparent => {
+                (object(&data.fields, &container, description, None)?, true)
+            }
+            Fields::Unnamed(fields) if fields.unnamed.len() > 1 && !container.transparent => {
+                (tuple(&data.fields), true)
+            }
+            Fields::Unit => (quote!(__private::json!({ "type": "null" })), true),
+            fields => {
+                let mut kept = fields.iter().filter(|f| {
+                    !Serde::parse(&f.attrs)
+                        .is_ok_and(|s| s.skip || (s.skip_serializing && s.skip_deserializing))
+                });
+                let (Some(field), None) = (kept.next(), kept.next()) else {
+                    return Err(syn::Error::new_spanned(
+                        ident,
+                        "ApiSchema: a transparent struct needs exactly one field",
+                    ));
+                };
+                let ty = &field.ty;
+                (quote!(components.schema::<#ty>()), false)
+            }
+        },
+        Data::Enum(data) => (enumeration(data, &container, description, ident)?, true),
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "ApiSchema cannot be derived for unions",
+            ))
+        }
+    };
+
+    let name = match (&container.rename, named && input.generics.params.is_empty()) {
+        (_, false) => quote!(::std::option::Option::None),
+        (Some(name), true) => quote!(::std::option::Option::Some(#name.into())),
+        (None, true) => {
+            let name = ident.unraw().to_string();
+            quote!(::std::option::Option::Some(#name.into()))
+        }
+    };
+
+    for param in input.generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::sword_ai::openapi::ApiSchema));
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sword_ai::openapi::ApiSchema for #ident #ty_generics #where_clause {
+            fn name() -> ::std::option::Option<::std::borrow::Cow<'static, str>> {
+                #name
+            }
+
+            fn schema(
+                components: &mut ::sword_ai::openapi::Components,
+            ) -> ::sword_ai::openapi::__private::Value {
+                use ::sword_ai::openapi::__private;
+                #body
+            }
+        }
+    })
+}
+
+/// An object with the named `fields`, and the `tag` property of an
+/// internally tagged enum variant.
+fn object(
+    fields: &Fields,
+    container: &Serde,
+    description: Option<String>,
+    tag: Option<(&str, &str)>,
+) -> syn::Result<TokenStream2> {
+    let mut properties = Vec::new();
+    if let Some((tag, variant)) = tag {
+        properties.push(quote! {
+            properties.insert(#tag.into(), __private::json!({ "const": #variant }));
+            required.push(#tag.into());
+        });
+    }
+    for field in fields {
+        let serde = Serde::parse(&field.attrs)?;
+        let ty = &field.ty;
+        if serde.skipped() {
+            continue;
+        }
+        if serde.flatten {
+            properties.push(quote!(flattened.push(components.schema::<#ty>());));
+            continue;
+        }
+        let ident = field
+            .ident
+            .as_ref()
+            .expect("named field")
+            .unraw()
+            .to_string();
+        let name = match (&serde.rename, &container.rename_all) {
+            (Some(name), _) => name.clone(),
+            (None, Some(rule)) => rename_field(rule, &ident),
+            (None, None) => ident,
+        };
+        let description = option(docs(&field.attrs));
+        properties.push(quote! {
+            properties.insert(
+                #name.into(),
+                __private::describe(components.schema::<#ty>(), #description),
+            );
+        });
+        if !(serde.default || container.default || serde.skip_serializing_if) {
+            properties.push(quote! {
+                if !<#ty as ::sword_ai::openapi::ApiSchema>::OPTIONAL {
+                    required.push(#name.into());
+                }
+            });
+        }
+    }
+    let description = option(description);
+    Ok(quote! {{
+        #[allow(unused_mut)]
+        let mut properties = __private::Map::new();
+        #[allow(unused_mut)]
+        let mut required = ::std::vec::Vec::<__private::Value>::new();
+        #[allow(unused_mut)]
+        let mut flattened = ::std::vec::Vec::<__private::Value>::new();
+        #(#properties)*
+        __private::object(properties, required, flattened, #description)
+    }})
+}
+
+/// A fixed-length array of the unnamed `fields`.
+fn tuple(fields: &Fields) -> TokenStream2 {
+    let types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+    let len = types.len();
+    quote! {{
+        let items: ::std::vec::Vec<__private::Value> =
+            ::std::vec![#(components.schema::<#types>()),*];
+        __private::json!({
+            "type": "array",
+            "prefixItems": items,
+            "minItems": #len,
+            "maxItems": #len,
+        })
+    }}
+}
+
+fn enumeration(
+    data: &syn::DataEnum,
+    container: &Serde,
+    description: Option<String>,
+    ident: &syn::Ident,
+) -> syn::Result<TokenStream2> {
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let serde = Serde::parse(&variant.attrs)?;
+        if serde.skipped() {
+            continue;
+        }
+        if let Some(rule) = &serde.rename_all {
+            check_rule(rule, &variant.ident)?;
+        }
+        let name = match (&serde.rename, &container.rename_all) {
+            (Some(name), _) => name.clone(),
+            (None, Some(rule)) => rename_variant(rule, &variant.ident.unraw().to_string()),
+            (None, None) => variant.ident.unraw().to_string(),
+        };
+        variants.push((variant, serde, name));
+    }
+    let description = option(description);
+
+    let unit_only = variants
+        .iter()
+        .all(|(variant, _, _)| matches!(variant.fields, Fields::Unit));
+    if unit_only && !container.untagged && container.tag.is_none() {
+        let names = variants.iter().map(|(_, _, name)| name);
+        return Ok(quote! {
+            __private::describe(
+                __private::json!({ "type": "string", "enum": [#(#names),*] }),
+                #description,
+            )
+        });
+    }
+
+    let mut schemas = Vec::new();
+    for (variant, serde, name) in &variants {
+        let fields_container = Serde {
+            rename_all: serde.rename_all.clone(),
+            ..Serde::default()
+        };
+        let content = match &variant.fields {
+            Fields::Unit => None,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                Some(quote!(components.schema::<#ty>()))
+            }
+            Fields::Unnamed(_) => Some(tuple(&variant.fields)),
+            Fields::Named(_) => Some(object(&variant.fields, &fields_container, None, None)?),
+        };
+        let schema = match (&container.tag, &container.content, container.untagged) {
+            (_, _, true) => content.unwrap_or(quote!(__private::json!({ "type": "null" }))),
+            (Some(tag), None, false) => match &variant.fields {
+                Fields::Named(_) => {
+                    object(&variant.fields, &fields_container, None, Some((tag, name)))?
+                }
+                Fields::Unit => quote! {
+                    __private::json!({
+                        "type": "object",
+                        "properties": { #tag: { "const": #name } },
+                        "required": [#tag],
+                    })
+                },
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {{
+                    let content = #content;
+                    __private::json!({
+                        "allOf": [
+                            {
+                                "type": "object",
+                                "properties": { #tag: { "const": #name } },
+                                "required": [#tag],
+                            },
+                            content,
+                        ]
+                    })
+                }},
+                Fields::Unnamed(_) => {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "ApiSchema: internally tagged enums cannot have tuple variants",
+                    ))
+                }
+            },
+            (Some(tag), Some(field), false) => match content {
+                Some(content) => quote! {{
+                    let content = #content;
+                    __private::json!({
+                        "type": "object",
+                        "properties": { #tag: { "const": #name }, #field: content },
+                        "required": [#tag, #field],
+                    })
+                }},
+                None => quote! {
+                    __private::json!({
+                        "type": "object",
+                        "properties": { #tag: { "const": #name } },
+                        "required": [#tag],
+                    })
+                },
+            },
+            (None, _, false) => match content {
+                Some(content) => quote! {{
+                    let content = #content;
+                    __private::json!({
+                        "type": "object",
+                        "properties": { #name: content },
+                        "required": [#name],
+                        "additionalProperties": false,
+                    })
+                }},
+                None => quote!(__private::json!({ "type": "string", "enum": [#name] })),
+            },
+        };
+        let variant_description = option(docs(&variant.attrs));
+        schemas.push(quote!(__private::describe(#schema, #variant_description)));
+    }
+    if schemas.is_empty() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "ApiSchema: the enum has no variants",
+        ));
+    }
+    Ok(quote! {
+        __private::describe(__private::json!({ "oneOf": [#(#schemas),*] }), #description)
+    })
+}
+
+/// The serde attributes that change the shape of a type.
+#[derive(Default)]
+struct Serde {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    tag: Option<String>,
+    content: Option<String>,
+    default: bool,
+    skip: bool,
+    skip_serializing: bool,
+    skip_deserializing: bool,
+    skip_serializing_if: bool,
+    flatten: bool,
+    transparent: bool,
+    untagged: bool,
+}
+
+impl Serde {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut serde = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                let name = meta
+                    .path
+                    .get_ident()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                match name.as_str() {
+                    "rename" if meta.input.peek(Token![=]) => serde.rename = Some(string(&meta)?),
+                    "rename_all" if meta.input.peek(Token![=]) => {
+                        serde.rename_all = Some(string(&meta)?)
+                    }
+                    "tag" => serde.tag = Some(string(&meta)?),
+                    "content" => serde.content = Some(string(&meta)?),
+                    "skip" => serde.skip = true,
+                    "flatten" => serde.flatten = true,
+                    "transparent" => serde.transparent = true,
+                    "untagged" => serde.untagged = true,
+                    "default" => {
+                        serde.default = true;
+                        skip_value(&meta)?;
+                    }
+                    "skip_serializing" => serde.skip_serializing = true,
+                    "skip_deserializing" => serde.skip_deserializing = true,
+                    "skip_serializing_if" => {
+                        serde.skip_serializing_if = true;
+                        skip_value(&meta)?;
+                    }
+                    _ => skip_value(&meta)?,
+                }
+                Ok(())
+            })?;
+        }
+        Ok(serde)
+    }
+
+    /// Whether the field or variant is absent from the serialized or the
+    /// deserialized form, so one schema cannot describe it.
+    fn skipped(&self) -> bool {
+        self.skip || self.skip_serializing || self.skip_deserializing
+    }
+}
+
+fn string(meta: &ParseNestedMeta) -> syn::Result<String> {
+    Ok(meta.value()?.parse::<LitStr>()?.value())
+}
+
+/// Consumes the value of an attribute this derive does not use.
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream2>()?;
+    }
+    Ok(())
+}
+
+/// The doc comment, without the leading space of each line.
+fn docs(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(line),
+                    ..
+                }) => Some(line.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let text = lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn option(text: Option<String>) -> TokenStream2 {
+    match text {
+        Some(text) => quote!(::std::option::Option::Some(#text)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+const RULES: [&str; 8] = [
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+fn check_rule(rule: &str, ident: &syn::Ident) -> syn::Result<()> {
+    if RULES.contains(&rule) {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            ident,
+            format!("ApiSchema: unknown rename_all rule '{}'", rule),
+        ))
+    }
+}
+
+/// Applies a `rename_all` rule to a snake_case field name, as serde does.
+fn rename_field(rule: &str, field: &str) -> String {
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(field),
+        "camelCase" => lower_first(&pascal(field)),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
+
+/// Applies a `rename_all` rule to a PascalCase variant name, as serde does.
+fn rename_variant(rule: &str, variant: &str) -> String {
+    match rule {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "camelCase" => lower_first(variant),
+        "snake_case" => snake(variant),
+        "SCREAMING_SNAKE_CASE" => snake(variant).to_ascii_uppercase(),
+        "kebab-case" => snake(variant).replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake(variant).to_ascii_uppercase().replace('_', "-"),
+        _ => variant.to_string(),
+    }
+}
+
+fn pascal(field: &str) -> String {
+    let mut out = String::new();
+    let mut capitalize = true;
+    for c in field.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            out.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+fn snake(variant: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in variant.char_indices() {
+        if i > 0 && c.is_uppercase() {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_unions_are_rejected() {
+        let input = parse_quote! {
+            union Bits {
+                int: u32,
+                float: f32,
+            }
+        };
+        assert_eq!(error(input), "ApiSchema cannot be derived for unions");
+    }
+
+    #[test]
+    fn test_unknown_rename_rule() {
+        let input = parse_quote! {
+            #[serde(rename_all = "Title Case")]
+            struct Order {
+                id: i64,
+            }
+        };
+        assert_eq!(
+            error(input),
+            "ApiSchema: unknown rename_all rule 'Title Case'"
+        );
+
+        let input = parse_quote! {
+            enum Shape {
+                #[serde(rename_all = "camel")]
+                Rect { top_left: u32 },
+            }
+        };
+        assert_eq!(error(input), "ApiSchema: unknown rename_all rule 'camel'");
+    }
+
+    #[test]
+    fn test_invalid_shapes() {
+        let input = parse_quote! {
+            #[serde(transparent)]
+            struct Pair {
+                a: u32,
+                b: u32,
+            }
+        };
+        assert_eq!(
+            error(input),
+            "ApiSchema: a transparent struct needs exactly one field"
+        );
+
+        let input = parse_quote! {
+            #[serde(tag = "kind")]
+            enum Point {
+                Xy(u32, u32),
+            }
+        };
+        assert_eq!(
+            error(input),
+            "ApiSchema: internally tagged enums cannot have tuple variants"
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! Application builder module.
+//!
+//! Provides [`SwordApp`], a builder for configuring and running a SwordAI
+//! server. It is the single entry point behind [`crate::server::run`] and
+//! [`crate::server::run_with_migrator`].
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use sword_ai::SwordApp;
+//! use tower_http::trace::TraceLayer;
+//!
+//! SwordApp::builder()
+//!     .migrator::<Migrator>()
+//!     .on_startup(|ctx| async move { warm_cache(ctx.db.reader()).await })
+//!     .router(build_router)
+//!     .layer(TraceLayer::new_for_http())
+//!     .serve()
+//!     .await?;
+//! ```
+
+use crate::config::{AppConfig, ConfigError, Profile};
+#[cfg(feature = "database")]
+use crate::db;
+use crate::health::{self, HealthCheck};
+use crate::lifecycle::Hooks;
+use crate::metrics;
+use crate::middleware;
+use crate::openapi::{self, OpenApi};
+use crate::pagination::PaginationConfig;
+use crate::rate_limit::RateLimit;
+use crate::server::{self, FrameworkContext};
+use crate::shutdown::Shutdown;
+use axum::extract::Request;
+use axum::response::IntoResponse;
+use axum::routing::Route;
+use axum::Router;
+#[cfg(feature = "database")]
+use sea_orm::DatabaseConnection;
+#[cfg(feature = "database")]
+use sea_orm_migration::MigratorTrait;
+use serde::de::DeserializeOwned;
+use std::convert::Infallible;
+use std::future::Future;
+#[cfg(feature = "database")]
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::{Layer, Service};
+
+#[cfg(feature = "database")]
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+#[cfg(feature = "database")]
+type MigrateFn =
+    for<'a> fn(&'a DatabaseConnection, &'a AppConfig) -> BoxFuture<'a, anyhow::Result<()>>;
+type SectionCheck = fn(&AppConfig, &str) -> Result<(), ConfigError>;
+type RouterFactory = Box<dyn FnOnce(&FrameworkContext) -> Router + Send>;
+type LayerFn = Box<dyn FnOnce(Router) -> Router + Send>;
+type RateLimitFn = Box<dyn FnOnce(RateLimit) -> RateLimit + Send>;
+
+/// Entry point for building a SwordAI application.
+///
+/// See [`SwordApp::builder`].
+pub struct SwordApp;
+
+impl SwordApp {
+    /// Creates a new [`SwordAppBuilder`] with default settings.
+    pub fn builder() -> SwordAppBuilder {
+        SwordAppBuilder::default()
+    }
+}
+
+/// Builder for configuring and running a SwordAI server.
+///
+/// Created with [`SwordApp::builder`] and consumed by [`SwordAppBuilder::serve`].
+#[derive(Default)]
+pub struct SwordAppBuilder {
+    config: Option<AppConfig>,
+    sections: Vec<(String, SectionCheck)>,
+    #[cfg(feature = "database")]
+    migrate: Option<MigrateFn>,
+    router: Option<RouterFactory>,
+    layers: Vec<LayerFn>,
+    hooks: Hooks,
+    health_checks: Vec<Arc<dyn HealthCheck>>,
+    openapi: Option<OpenApi>,
+    rate_limit: Option<RateLimitFn>,
+    listener: Option<TcpListener>,
+}
+
+impl SwordAppBuilder {
+    /// Uses the given configuration instead of loading it with
+    /// [`AppConfig::from_env`].
+    pub fn config(mut self, config: AppConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Serves on an already bound `listener` instead of binding
+    /// `HOST:PORT`.
+    pub fn listener(mut self, listener: TcpListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Validates the application config section `name` as `T` at boot.
+    ///
+    /// The server does not start if the section cannot be deserialized.
+    /// Read it in handlers or hooks with
+    /// [`AppConfig::section`](crate::AppConfig::section).
+    pub fn config_section<T: DeserializeOwned>(mut self, name: &str) -> Self {
+        self.sections.push((name.to_string(), |config, name| {
+            config.section::<T>(name).map(drop)
+        }));
+        self
+    }
+
+    /// Runs pending migrations from `M` before the server starts, or only
+    /// checks that there are none with `DB_MIGRATIONS=check`.
+    ///
+    /// On Postgres, concurrent instances are serialized with an advisory
+    /// lock; see [`crate::db`]. Requires the `database` feature.
+    #[cfg(feature = "database")]
+    pub fn migrator<M: MigratorTrait + 'static>(mut self) -> Self {
+        self.migrate = Some(|db, config| Box::pin(db::run_migrations::<M>(db, config)));
+        self
+    }
+
+    /// Registers an async hook that runs after migrations and before the
+    /// router is built.
+    ///
+    /// Hooks run in registration order. If a hook returns an error the
+    /// server does not start and [`SwordAppBuilder::serve`] returns it.
+    pub fn on_startup<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.hooks.add_startup(hook);
+        self
+    }
+
+    /// Registers an async hook that runs during shutdown, once in-flight
+    /// requests and background tasks have finished.
+    ///
+    /// Hooks run in registration order, each limited to
+    /// `APP_SHUTDOWN_HOOK_TIMEOUT` seconds. Errors and timeouts are logged.
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.hooks.add_shutdown(hook, None);
+        self
+    }
+
+    /// Like [`SwordAppBuilder::on_shutdown`], with an explicit time budget.
+    pub fn on_shutdown_with_timeout<F, Fut>(mut self, timeout: Duration, hook: F) -> Self
+    where
+        F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.hooks.add_shutdown(hook, Some(timeout));
+        self
+    }
+
+    /// Adds a dependency check to the readiness endpoint.
+    ///
+    /// The database pool is always checked; see [`crate::health`].
+    pub fn health_check<C: HealthCheck>(mut self, check: C) -> Self {
+        self.health_checks.push(Arc::new(check));
+        self
+    }
+
+    /// Serves `doc` at `OPENAPI_PATH`, with a docs page at
+    /// `OPENAPI_DOCS_PATH`. See [`crate::openapi`].
+    pub fn openapi(mut self, doc: OpenApi) -> Self {
+        self.openapi = Some(doc);
+        self
+    }
+
+    /// Adjusts the global rate limit set by the `RATE_LIMIT_*` settings,
+    /// e.g. with per-route quotas. See [`crate::rate_limit`].
+    pub fn rate_limit<F>(mut self, configure: F) -> Self
+    where
+        F: FnOnce(RateLimit) -> RateLimit + Send + 'static,
+    {
+        self.rate_limit = Some(Box::new(configure));
+        self
+    }
+
+    /// Sets the function that builds the application [`Router`].
+    ///
+    /// Defaults to an empty router when not set.
+    pub fn router<F>(mut self, build_router: F) -> Self
+    where
+        F: FnOnce(&FrameworkContext) -> Router + Send + 'static,
+    {
+        self.router = Some(Box::new(build_router));
+        self
+    }
+
+    /// Applies a middleware layer to the whole application.
+    ///
+    /// Layers wrap the router in registration order, so the last layer
+    /// added is the outermost, as with [`Router::layer`].
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.layers
+            .push(Box::new(move |router| router.layer(layer)));
+        self
+    }
+
+    /// Connects to the database, runs migrations and startup hooks, builds
+    /// the router and serves it until a shutdown signal is received.
+    ///
+    /// Without the `database` feature no connection is made and the
+    /// context carries no database handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if configuration cannot be loaded or a registered
+    /// config section is invalid, the database is
+    /// unreachable, a migration or startup hook fails or the server cannot
+    /// bind its address.
+    pub async fn serve(mut self) -> anyhow::Result<()> {
+        let config = match self.config {
+            Some(config) => config,
+            None => AppConfig::from_env()?,
+        };
+        let section_errors: Vec<String> = self
+            .sections
+            .iter()
+            .filter_map(|(name, check)| check(&config, name).err())
+            .map(|e| e.to_string())
+            .collect();
+        if !section_errors.is_empty() {
+            anyhow::bail!(section_errors.join("\n"));
+        }
+        #[cfg(feature = "database")]
+        let db = if config.db_start_degraded {
+            db::Db::connect_lazy(&config).await?
+        } else {
+            db::Db::connect(&config).await?
+        };
+
+        #[cfg(feature = "database")]
+        if !config.db_start_degraded {
+            if let Some(migrate) = self.migrate.take() {
+                migrate(db.writer(), &config).await?;
+            }
+        }
+
+        let ctx = FrameworkContext {
+            config,
+            #[cfg(feature = "database")]
+            db,
+            shutdown: Shutdown::new(),
+        };
+
+        #[cfg(feature = "database")]
+        if ctx.config.db_start_degraded {
+            connect_in_background(&ctx, self.migrate.take());
+        }
+        #[cfg(feature = "database")]
+        ctx.db.monitor(
+            &ctx.shutdown,
+            Duration::from_secs(ctx.config.db_replica_check_interval),
+        );
+
+        if let Err(e) = self.hooks.run_startup(&ctx).await {
+            ctx.shutdown.trigger();
+            #[cfg(feature = "database")]
+            ctx.db.close().await;
+            return Err(e);
+        }
+
+        let mut app = match self.router {
+            Some(build_router) => build_router(&ctx),
+            None => Router::new(),
+        };
+        app = app.merge(health::router(&ctx, self.health_checks));
+        app = app.merge(metrics::router(&ctx));
+        app = app.merge(openapi::router(&ctx, self.openapi.as_ref()));
+        #[cfg(feature = "database")]
+        {
+            app = app.layer(axum::middleware::from_fn_with_state(
+                ctx.db.clone(),
+                db::transaction_scope,
+            ));
+        }
+        #[cfg(feature = "database")]
+        if ctx.db.replica_count() > 0 && ctx.config.db_read_your_writes {
+            app = app.layer(axum::middleware::from_fn(db::request_scope));
+        }
+        if ctx.config.pagination_cursor_secret.expose().is_empty()
+            && ctx.config.profile == Profile::Prod
+        {
+            tracing::warn!(
+                "PAGINATION_CURSOR_SECRET is not set: pagination cursors are signed with a random key and will not survive a restart"
+            );
+        }
+        app = app.layer(axum::Extension(PaginationConfig::from_config(&ctx.config)));
+        let mut rate_limit = RateLimit::from_config(&ctx.config);
+        if let Some(configure) = self.rate_limit {
+            rate_limit = configure(rate_limit);
+        }
+        app = middleware::apply(app, &ctx.config, rate_limit);
+        app = app.layer(axum::middleware::from_fn(crate::tracing::trace_scope));
+        for layer in self.layers {
+            app = layer(app);
+        }
+
+        let listener = match self.listener {
+            Some(listener) => listener,
+            None => TcpListener::bind(ctx.config.bind_address()).await?,
+        };
+        server::serve(ctx, listener, app, self.hooks).await
+    }
+}
+
+/// Waits for the database in the background (`DB_START_DEGRADED`), then
+/// runs migrations and marks the database ready. Retries until shutdown,
+/// ignoring `DB_CONNECT_MAX_ATTEMPTS`.
+#[cfg(feature = "database")]
+fn connect_in_background(ctx: &FrameworkContext, migrate: Option<MigrateFn>) {
+    let db = ctx.db.clone();
+    let config = ctx.config.clone();
+    let policy = db::RetryPolicy::from_config(&ctx.config);
+    let shutdown = ctx.shutdown.clone();
+    tracing::warn!("Starting degraded: waiting for the database in the background");
+
+    ctx.shutdown.spawn(async move {
+        let conn = db.writer().clone();
+        let mut attempt = 1u32;
+        while let Err(e) = conn.ping().await {
+            let delay = policy.delay(attempt);
+            tracing::warn!(
+                "Database connection attempt {} failed: {}; retrying in {}ms",
+                attempt,
+                e,
+                delay.as_millis()
+            );
+            tokio::select! {
+                _ = shutdown.triggered() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            attempt = attempt.saturating_add(1);
+        }
+
+        if let Some(migrate) = migrate {
+            if let Err(e) = migrate(&conn, &config).await {
+                tracing::error!("Migrations failed, the database stays unavailable: {:#}", e);
+                return;
+            }
+        }
+        db.set_ready(true);
+        tracing::info!("Database is up, leaving degraded mode");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use axum::response::Response;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::oneshot;
+
+    fn test_config() -> AppConfig {
+        AppConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            #[cfg(feature = "database")]
+            database_url: if cfg!(feature = "postgres") {
+                "postgres://localhost/test"
+            } else {
+                "sqlite::memory:"
+            }
+            .into(),
+            // Start degraded so that no database has to be running.
+            #[cfg(feature = "database")]
+            db_start_degraded: true,
+            #[cfg(feature = "database")]
+            db_connect_timeout: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Sends `GET path` with `headers` and returns the raw response.
+    async fn get_raw(addr: std::net::SocketAddr, path: &str, headers: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n{headers}Connection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (started, shutdown) = oneshot::channel();
+
+        let server = tokio::spawn(
+            SwordApp::builder()
+                .config(test_config())
+                .listener(listener)
+                .on_startup(move |ctx| async move {
+                    started.send(ctx.shutdown.clone()).ok();
+                    Ok(())
+                })
+                .router(|_| Router::new().route("/hello", get(|| async { "hello" })))
+                // Outside the framework stack: the request id set here is
+                // the one `trace_scope` picks up and echoes.
+                .layer(axum::middleware::map_request(
+                    |mut req: Request| async move {
+                        req.headers_mut()
+                            .insert("x-request-id", HeaderValue::from_static("outer-layer"));
+                        req
+                    },
+                ))
+                .layer(axum::middleware::map_response(
+                    |mut res: Response| async move {
+                        res.headers_mut()
+                            .insert("x-outer", HeaderValue::from_static("1"));
+                        res
+                    },
+                ))
+                .serve(),
+        );
+        let shutdown = shutdown.await.unwrap();
+
+        let response = get_raw(addr, "/livez", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let response = get_raw(addr, "/hello", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+        assert!(
+            response.contains("x-request-id: outer-layer\r\n"),
+            "{response}"
+        );
+        assert!(response.contains("x-outer: 1\r\n"), "{response}");
+
+        shutdown.trigger();
+        server.await.unwrap().unwrap();
+    }
+
+    #[cfg(not(feature = "database"))]
+    #[tokio::test]
+    async fn test_serve_without_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut loader = crate::config::ConfigLoader::new().dir(dir.path());
+        loader.env = Some(Default::default());
+        let config = loader.load().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (started, shutdown) = oneshot::channel();
+
+        let server = tokio::spawn(
+            SwordApp::builder()
+                .config(config)
+                .listener(listener)
+                .on_startup(move |ctx| async move {
+                    started.send(ctx.shutdown.clone()).ok();
+                    Ok(())
+                })
+                .serve(),
+        );
+        let shutdown = shutdown.await.unwrap();
+
+        let response = get_raw(addr, "/readyz", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.contains("database"), "{response}");
+
+        shutdown.trigger();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_startup_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (started, shutdown) = oneshot::channel();
+        let err = SwordApp::builder()
+            .config(test_config())
+            .listener(listener)
+            .on_startup(move |ctx| async move {
+                started.send(ctx.shutdown.clone()).ok();
+                Ok(())
+            })
+            .on_startup(|_| async { anyhow::bail!("cache unavailable") })
+            .serve()
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "startup hook 2 of 2 failed");
+        assert_eq!(err.root_cause().to_string(), "cache unavailable");
+        assert!(shutdown.await.unwrap().is_triggered());
+    }
+}
@@ -0,0 +1,66 @@
+//! Configuration errors.
+
+use super::Source;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Error returned when configuration cannot be loaded.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// A config file exists but could not be read or parsed.
+    #[error("failed to load {}: {message}", path.display())]
+    File {
+        /// Path of the file.
+        path: PathBuf,
+        /// Underlying I/O or TOML error.
+        message: String,
+    },
+    /// One or more settings are missing or invalid. Every problem found is
+    /// listed, not just the first one.
+    #[error("invalid configuration ({} problem{}):{}", .0.len(), if .0.len() == 1 { "" } else { "s" }, ConfigIssue::list(.0))]
+    Invalid(Vec<ConfigIssue>),
+    /// An application section could not be deserialized.
+    #[error("invalid config section [{name}]: {message}")]
+    Section {
+        /// Name of the section.
+        name: String,
+        /// Underlying deserialization error.
+        message: String,
+    },
+}
+
+/// A single invalid or missing setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// Environment variable name of the setting, e.g. `DB_MAX_CONNECTIONS`.
+    pub var: String,
+    /// The rejected value, or `None` when the setting is missing. Secret
+    /// values are replaced with `<redacted>`.
+    pub value: Option<String>,
+    /// Where the rejected value came from.
+    pub source: Source,
+    /// What was expected, e.g. `expected an integer between 1 and 10000`.
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn list(issues: &[ConfigIssue]) -> String {
+        issues
+            .iter()
+            .map(|issue| format!("\n  - {}", issue))
+            .collect()
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.var)?;
+        if let Some(value) = &self.value {
+            write!(f, "={:?}", value)?;
+        }
+        if self.source != Source::Default {
+            write!(f, " (from {})", self.source)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
@@ -0,0 +1,879 @@
+//! Layered configuration loading.
+
+use super::secret::{read_secret_file, SecretProvider};
+use super::validate::{self, RawValues};
+use super::{settings, AppConfig, ConfigError, ConfigIssue, ConfigSections};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Deployment profile, selected with `SWORD_ENV`.
+///
+/// Determines which `sword.{profile}.toml` file is layered on top of
+/// `sword.toml`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Local development (`dev`).
+    #[default]
+    Dev,
+    /// Automated tests (`test`).
+    Test,
+    /// Production (`prod`).
+    Prod,
+}
+
+impl Profile {
+    /// Returns the profile name used in `SWORD_ENV` and file names.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Test => "test",
+            Profile::Prod => "prod",
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Profile {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dev" => Ok(Profile::Dev),
+            "test" => Ok(Profile::Test),
+            "prod" => Ok(Profile::Prod),
+            other => Err(ConfigError::Invalid(vec![ConfigIssue {
+                var: "SWORD_ENV".to_string(),
+                value: Some(other.to_string()),
+                source: Source::Env("SWORD_ENV".to_string()),
+                message: "expected one of dev, test, prod".to_string(),
+            }])),
+        }
+    }
+}
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Built-in default.
+    Default,
+    /// A TOML config file.
+    File(PathBuf),
+    /// An environment variable, by name.
+    Env(String),
+    /// A file named by a `*_FILE` environment variable, e.g.
+    /// `DATABASE_URL_FILE`.
+    SecretFile(PathBuf),
+    /// A [`SecretProvider`], by name.
+    Provider(String),
+    /// A `--set key=value` command-line override.
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => f.write_str("default"),
+            Source::File(path) => write!(f, "file {}", path.display()),
+            Source::Env(var) => write!(f, "env {}", var),
+            Source::SecretFile(path) => write!(f, "secret file {}", path.display()),
+            Source::Provider(name) => write!(f, "secret provider {}", name),
+            Source::Cli => f.write_str("cli --set"),
+        }
+    }
+}
+
+/// Origin of every value in an [`AppConfig`], keyed by setting name.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    sources: BTreeMap<String, Source>,
+}
+
+impl ConfigSources {
+    /// Returns where `key` was loaded from, or [`Source::Default`] if no
+    /// layer set it.
+    pub fn get(&self, key: &str) -> &Source {
+        self.sources.get(key).unwrap_or(&Source::Default)
+    }
+
+    /// Iterates over the keys set by a file, environment variable or CLI
+    /// override.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Source)> {
+        self.sources.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+/// Builder that loads an [`AppConfig`] from layered sources.
+///
+/// See the [module documentation](crate::config) for the layer order.
+#[derive(Default)]
+pub struct ConfigLoader {
+    dir: Option<PathBuf>,
+    profile: Option<Profile>,
+    overrides: Vec<(String, String)>,
+    /// Replaces the process environment, in tests.
+    pub(crate) env: Option<HashMap<String, String>>,
+    secrets: Option<Arc<dyn SecretProvider>>,
+}
+
+impl fmt::Debug for ConfigLoader {
+    // The environment may hold credentials, so it is not shown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigLoader")
+            .field("dir", &self.dir)
+            .field("profile", &self.profile)
+            .field("secrets", &self.secrets.as_ref().map(|p| p.name()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConfigLoader {
+    /// Creates a loader that reads from `SWORD_CONFIG_DIR` and the process
+    /// environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads config files from `dir` instead of `SWORD_CONFIG_DIR`.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Uses `profile` instead of reading `SWORD_ENV`.
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Looks up secret settings such as `DATABASE_URL` in `provider`.
+    ///
+    /// Values found there take precedence over config files and
+    /// environment variables, but not over `--set` overrides.
+    pub fn secret_provider(mut self, provider: impl SecretProvider + 'static) -> Self {
+        self.secrets = Some(Arc::new(provider));
+        self
+    }
+
+    /// Overrides a single setting. Accepts either the key (`port`), the
+    /// environment variable name (`APP_PORT`) or a section key
+    /// (`payments.api_key`).
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Collects `--set key=value` and `--set=key=value` overrides from
+    /// command-line arguments, ignoring everything else.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = args.into_iter().map(Into::into);
+        while let Some(arg) = args.next() {
+            let pair = match arg.strip_prefix("--set=") {
+                Some(pair) => Some(pair.to_string()),
+                None if arg == "--set" => args.next(),
+                None => None,
+            };
+            if let Some((key, value)) = pair.as_deref().and_then(|p| p.split_once('=')) {
+                self.overrides.push((key.to_string(), value.to_string()));
+            }
+        }
+        self
+    }
+
+    /// Loads and validates the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::File`] if a config file cannot be read or
+    /// parsed, and [`ConfigError::Invalid`] listing every problem found
+    /// otherwise: an unknown `SWORD_ENV` profile or `--set` key, a value of
+    /// the wrong type or out of range, an unreadable `*_FILE` secret, a
+    /// failing secret provider, a missing `DATABASE_URL` (with the
+    /// `database` feature) or inconsistent settings.
+    pub fn load(self) -> Result<AppConfig, ConfigError> {
+        let mut issues = Vec::new();
+        let env = self.env.unwrap_or_else(|| {
+            std::env::vars_os()
+                .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+                .collect()
+        });
+        let var = |name: &str| env.get(name).cloned();
+
+        let profile = match self.profile {
+            Some(profile) => profile,
+            None => match var("SWORD_ENV").map(|name| name.parse()) {
+                Some(Ok(profile)) => profile,
+                Some(Err(ConfigError::Invalid(found))) => {
+                    issues.extend(found);
+                    Profile::default()
+                }
+                Some(Err(e)) => return Err(e),
+                None => Profile::default(),
+            },
+        };
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => var("SWORD_CONFIG_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(".")),
+        };
+
+        let mut layers = Layers {
+            sections: ConfigSections::with_env(env.clone()),
+            ..Layers::default()
+        };
+
+        layers.file(&dir.join("sword.toml"))?;
+        layers.file(&dir.join(format!("sword.{}.toml", profile)))?;
+
+        for setting in settings() {
+            let file_var = format!("{}_FILE", setting.var);
+            match (var(setting.var), var(&file_var)) {
+                (Some(_), Some(_)) => issues.push(ConfigIssue {
+                    var: setting.var.to_string(),
+                    value: None,
+                    source: Source::Env(file_var),
+                    message: format!(
+                        "set either {} or {}_FILE, not both",
+                        setting.var, setting.var
+                    ),
+                }),
+                (Some(value), None) => {
+                    layers.set(setting.key, value, Source::Env(setting.var.to_string()))
+                }
+                (None, Some(path)) => match read_secret_file(Path::new(&path)) {
+                    Ok(value) => layers.set(setting.key, value, Source::SecretFile(path.into())),
+                    Err(e) => issues.push(ConfigIssue {
+                        var: file_var.clone(),
+                        value: Some(path),
+                        source: Source::Env(file_var),
+                        message: format!("cannot read secret file: {}", e),
+                    }),
+                },
+                (None, None) => {}
+            }
+        }
+
+        if let Some(provider) = &self.secrets {
+            for setting in settings().filter(|s| s.secret) {
+                match provider.get(setting.var) {
+                    Ok(Some(value)) => layers.set(
+                        setting.key,
+                        value,
+                        Source::Provider(provider.name().to_string()),
+                    ),
+                    Ok(None) => {}
+                    Err(e) => issues.push(ConfigIssue {
+                        var: setting.var.to_string(),
+                        value: None,
+                        source: Source::Provider(provider.name().to_string()),
+                        message: format!("secret lookup failed: {:#}", e),
+                    }),
+                }
+            }
+        }
+
+        for (name, value) in &self.overrides {
+            if let Some((section, key)) = name.split_once('.') {
+                if ConfigSections::is_reserved(section) {
+                    issues.push(ConfigIssue {
+                        var: name.clone(),
+                        value: None,
+                        source: Source::Cli,
+                        message: "section name is reserved for framework settings".to_string(),
+                    });
+                } else {
+                    layers.sections.set_override(section, key, value.clone());
+                }
+                continue;
+            }
+            match resolve_key(name) {
+                Some(key) => layers.set(key, value.clone(), Source::Cli),
+                None => issues.push(ConfigIssue {
+                    var: name.clone(),
+                    value: None,
+                    source: Source::Cli,
+                    message: "unknown configuration key".to_string(),
+                }),
+            }
+        }
+
+        issues.extend(validate::validate(&RawValues {
+            values: &layers.values,
+            sources: &layers.sources,
+        }));
+        if !issues.is_empty() {
+            return Err(ConfigError::Invalid(issues));
+        }
+
+        let mut config: AppConfig = envy::from_iter(layers.values).map_err(|e| {
+            ConfigError::Invalid(vec![ConfigIssue {
+                var: "config".to_string(),
+                value: None,
+                source: Source::Default,
+                message: e.to_string(),
+            }])
+        })?;
+        config.profile = profile;
+        config.sources = ConfigSources {
+            sources: layers.sources,
+        };
+        config.sections = layers.sections;
+
+        for (key, source) in config.sources.iter() {
+            tracing::debug!("Config {} loaded from {}", key, source);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Maps a key or environment variable name to its [`AppConfig`] key.
+fn resolve_key(name: &str) -> Option<&'static str> {
+    settings()
+        .find(|s| s.key.eq_ignore_ascii_case(name) || s.var.eq_ignore_ascii_case(name))
+        .map(|s| s.key)
+}
+
+/// Values merged from every layer, with the source of each.
+#[derive(Default)]
+struct Layers {
+    values: BTreeMap<String, String>,
+    sources: BTreeMap<String, Source>,
+    sections: ConfigSections,
+}
+
+impl Layers {
+    fn set(&mut self, key: &str, value: String, source: Source) {
+        self.values.insert(key.to_string(), value);
+        self.sources.insert(key.to_string(), source);
+    }
+
+    /// Merges a TOML file if it exists.
+    fn file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let file_error = |message: String| ConfigError::File {
+            path: path.to_path_buf(),
+            message,
+        };
+        let content = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        let table: toml::Table = toml::from_str(&content).map_err(|e| file_error(e.to_string()))?;
+
+        for (key, value) in table {
+            if let toml::Value::Table(section) = value {
+                if ConfigSections::is_reserved(&key) {
+                    return Err(file_error(format!(
+                        "[{}] is reserved for framework settings",
+                        key
+                    )));
+                }
+                self.section(&key, section, path);
+                continue;
+            }
+            if resolve_key(&key) != Some(key.as_str()) {
+                tracing::warn!("Ignoring unknown key '{}' in {}", key, path.display());
+                continue;
+            }
+            let value = scalar_to_string(&value).ok_or_else(|| {
+                file_error(format!(
+                    "'{}' must be a string, number, boolean or array",
+                    key
+                ))
+            })?;
+            self.set(&key, value, Source::File(path.to_path_buf()));
+        }
+        Ok(())
+    }
+}
+
+impl Layers {
+    /// Merges a `[section]` table from a config file.
+    fn section(&mut self, name: &str, table: toml::Table, path: &Path) {
+        for (key, value) in table {
+            match scalar_to_string(&value) {
+                Some(value) => self.sections.set_from_file(name, &key, value),
+                None => tracing::warn!(
+                    "Ignoring nested table '{}.{}' in {}",
+                    name,
+                    key,
+                    path.display()
+                ),
+            }
+        }
+    }
+}
+
+/// Converts a TOML value into the string form used by environment variables.
+/// Arrays become comma-separated lists.
+fn scalar_to_string(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Datetime(d) => Some(d.to_string()),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(scalar_to_string)
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        toml::Value::Table(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Secret;
+    use crate::openapi::DocsUi;
+    use crate::rate_limit::ClientKey;
+
+    /// A `DATABASE_URL` for a backend that is compiled in.
+    const TEST_DATABASE_URL: &str = if cfg!(feature = "postgres") {
+        "postgres://localhost/test"
+    } else {
+        "sqlite::memory:"
+    };
+
+    fn loader(dir: &Path, env: &[(&str, &str)]) -> ConfigLoader {
+        let mut env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        env.entry("DATABASE_URL".to_string())
+            .or_insert_with(|| TEST_DATABASE_URL.to_string());
+        ConfigLoader {
+            env: Some(env),
+            ..ConfigLoader::new()
+        }
+        .dir(dir)
+    }
+
+    #[test]
+    fn test_defaults_without_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(dir.path(), &[]).load().unwrap();
+
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.profile, Profile::Dev);
+        assert_eq!(config.sources.get("port"), &Source::Default);
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("sword.toml"),
+            "host = \"10.0.0.1\"\nport = 4000\nhealth_check_timeout = 3\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("sword.prod.toml"), "port = 5000\n").unwrap();
+
+        let config = loader(
+            dir.path(),
+            &[("SWORD_ENV", "prod"), ("HEALTH_CHECK_TIMEOUT", "7")],
+        )
+        .args(["app", "--set", "host=127.0.0.1"])
+        .load()
+        .unwrap();
+
+        assert_eq!(config.profile, Profile::Prod);
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 5000);
+        assert_eq!(config.health_check_timeout, 7);
+        assert_eq!(config.sources.get("host"), &Source::Cli);
+        assert_eq!(
+            config.sources.get("port"),
+            &Source::File(dir.path().join("sword.prod.toml"))
+        );
+        assert_eq!(
+            config.sources.get("health_check_timeout"),
+            &Source::Env("HEALTH_CHECK_TIMEOUT".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_accepts_env_var_names() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(dir.path(), &[])
+            .set("APP_PORT", "8081")
+            .load()
+            .unwrap();
+
+        assert_eq!(config.port, 8081);
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Payments {
+        api_key: String,
+        max_retries: u32,
+        sandbox: bool,
+    }
+
+    #[test]
+    fn test_section_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("sword.toml"),
+            "[payments]\napi_key = \"file\"\nmax_retries = 3\nsandbox = true\n",
+        )
+        .unwrap();
+
+        let config = loader(dir.path(), &[("PAYMENTS_MAX_RETRIES", "5")])
+            .set("payments.sandbox", "false")
+            .load()
+            .unwrap();
+        let payments: Payments = config.section("payments").unwrap();
+
+        assert_eq!(payments.api_key, "file");
+        assert_eq!(payments.max_retries, 5);
+        assert!(!payments.sandbox);
+        assert_eq!(config.sections.names().collect::<Vec<_>>(), ["payments"]);
+    }
+
+    #[test]
+    fn test_section_missing_field_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(dir.path(), &[("PAYMENTS_API_KEY", "env")])
+            .load()
+            .unwrap();
+        let err = config.section::<Payments>("payments").unwrap_err();
+
+        assert!(format!("{:#}", err).contains("[payments]"));
+    }
+
+    #[test]
+    fn test_plain_file_fields_in_sections() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(dir.path(), &[("LOGGING_OUTPUT_FILE", "/var/log/app.log")])
+            .load()
+            .unwrap();
+
+        #[derive(serde::Deserialize)]
+        struct Logging {
+            output_file: String,
+        }
+        let logging: Logging = config.section("logging").unwrap();
+        assert_eq!(logging.output_file, "/var/log/app.log");
+    }
+
+    #[test]
+    fn test_reserved_section_names() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(dir.path(), &[("APP_PORT", "4000")]).load().unwrap();
+        let err = config
+            .section::<HashMap<String, String>>("app")
+            .unwrap_err();
+        assert!(err.to_string().contains("reserved"));
+
+        let err = loader(dir.path(), &[])
+            .set("db.pool", "1")
+            .load()
+            .unwrap_err();
+        assert!(err.to_string().contains("db.pool"));
+
+        std::fs::write(
+            dir.path().join("sword.toml"),
+            "[app]
+name = \"notes\"\n",
+        )
+        .unwrap();
+        let err = loader(dir.path(), &[]).load().unwrap_err();
+        assert!(matches!(err, ConfigError::File { .. }));
+    }
+
+    #[test]
+    fn test_unknown_profile_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = loader(dir.path(), &[("SWORD_ENV", "staging")])
+            .load()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("SWORD_ENV"));
+    }
+
+    #[cfg(feature = "database")]
+    #[test]
+    fn test_database_url_is_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader = ConfigLoader {
+            env: Some(HashMap::new()),
+            ..ConfigLoader::new()
+        }
+        .dir(dir.path());
+
+        let err = loader.load().unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration (1 problem):\n  - DATABASE_URL: must be set"
+        );
+    }
+
+    #[test]
+    fn test_all_problems_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("sword.toml"), "health_check_timeout = 0\n").unwrap();
+
+        let err = loader(
+            dir.path(),
+            &[("APP_PORT", "http"), ("HEALTH_LIVENESS_PATH", "livez")],
+        )
+        .set("no_such_key", "1")
+        .load()
+        .unwrap_err();
+
+        let ConfigError::Invalid(issues) = err else {
+            panic!("expected ConfigError::Invalid");
+        };
+        let vars: Vec<&str> = issues.iter().map(|i| i.var.as_str()).collect();
+        assert_eq!(
+            vars,
+            [
+                "no_such_key",
+                "APP_PORT",
+                "HEALTH_LIVENESS_PATH",
+                "HEALTH_CHECK_TIMEOUT"
+            ]
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "APP_PORT=\"http\" (from env APP_PORT): expected an integer between 1 and 65535"
+        );
+        assert_eq!(
+            issues[3].source,
+            Source::File(dir.path().join("sword.toml"))
+        );
+    }
+
+    #[cfg(feature = "database")]
+    #[test]
+    fn test_min_connections_above_max_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = loader(
+            dir.path(),
+            &[("DB_MIN_CONNECTIONS", "20"), ("DB_MAX_CONNECTIONS", "10")],
+        )
+        .load()
+        .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("DB_MIN_CONNECTIONS=\"20\" (from env DB_MIN_CONNECTIONS): must not exceed DB_MAX_CONNECTIONS (10)"));
+    }
+
+    #[test]
+    fn test_cross_field_rules_run_alongside_other_issues() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = loader(
+            dir.path(),
+            &[
+                ("APP_PORT", "http"),
+                ("CORS_ALLOWED_ORIGINS", "*"),
+                ("CORS_ALLOW_CREDENTIALS", "true"),
+                // Not compared against the default: the maximum is invalid.
+                ("PAGINATION_DEFAULT_SIZE", "500"),
+                ("PAGINATION_MAX_SIZE", "lots"),
+            ],
+        )
+        .load()
+        .unwrap_err();
+        let ConfigError::Invalid(issues) = err else {
+            panic!("expected ConfigError::Invalid");
+        };
+        let vars: Vec<&str> = issues.iter().map(|i| i.var.as_str()).collect();
+        assert_eq!(
+            vars,
+            ["APP_PORT", "PAGINATION_MAX_SIZE", "CORS_ALLOWED_ORIGINS"]
+        );
+    }
+
+    #[test]
+    fn test_cors_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("sword.toml"),
+            "cors_allowed_origins = [\"https://a.example.com\", \"http://localhost:5173\"]\n",
+        )
+        .unwrap();
+
+        let config = loader(dir.path(), &[("CORS_ALLOWED_METHODS", "GET,POST")])
+            .load()
+            .unwrap();
+        assert_eq!(
+            config.cors_allowed_origins,
+            ["https://a.example.com", "http://localhost:5173"]
+        );
+        assert_eq!(config.cors_allowed_methods, ["GET", "POST"]);
+
+        let err = loader(
+            dir.path(),
+            &[
+                ("CORS_ALLOWED_ORIGINS", "*"),
+                ("CORS_ALLOW_CREDENTIALS", "true"),
+            ],
+        )
+        .load()
+        .unwrap_err();
+        assert!(err.to_string().contains(
+            "CORS_ALLOWED_ORIGINS=\"*\" (from env CORS_ALLOWED_ORIGINS): must not contain * when CORS_ALLOW_CREDENTIALS is true"
+        ));
+    }
+
+    #[test]
+    fn test_choices_are_exact() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = loader(
+            dir.path(),
+            &[("OPENAPI_DOCS", "redoc"), ("RATE_LIMIT_KEY", "api_key")],
+        )
+        .load()
+        .unwrap();
+        assert_eq!(config.openapi_docs, DocsUi::Redoc);
+        assert_eq!(config.rate_limit_key, ClientKey::ApiKey);
+
+        let err = loader(
+            dir.path(),
+            &[
+                ("OPENAPI_DOCS", " off"),
+                ("RATE_LIMIT_ALGORITHM", "Sliding_Window"),
+            ],
+        )
+        .load()
+        .unwrap_err();
+        let ConfigError::Invalid(issues) = err else {
+            panic!("expected ConfigError::Invalid");
+        };
+        let vars: Vec<&str> = issues.iter().map(|i| i.var.as_str()).collect();
+        assert_eq!(vars, ["OPENAPI_DOCS", "RATE_LIMIT_ALGORITHM"]);
+    }
+
+    #[test]
+    fn test_swagger_docs_follow_the_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = loader(dir.path(), &[("OPENAPI_DOCS", "swagger")]).load();
+
+        #[cfg(feature = "swagger-ui")]
+        assert_eq!(result.unwrap().openapi_docs, DocsUi::Swagger);
+        #[cfg(not(feature = "swagger-ui"))]
+        assert!(result.unwrap_err().to_string().contains(
+            "OPENAPI_DOCS=\"swagger\" (from env OPENAPI_DOCS): swagger requires the `swagger-ui` feature of sword-ai"
+        ));
+    }
+
+    #[cfg(feature = "database")]
+    #[test]
+    fn test_secret_values_are_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = loader(dir.path(), &[("DATABASE_URL", "hunter2")])
+            .load()
+            .unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains("DATABASE_URL=\"<redacted>\""));
+        assert!(!message.contains("hunter2"));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_secrets_from_file_and_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let url_file = dir.path().join("url");
+        std::fs::write(&url_file, "postgres://file/app\n").unwrap();
+        let env = HashMap::from([(
+            "DATABASE_URL_FILE".to_string(),
+            url_file.display().to_string(),
+        )]);
+
+        let config = ConfigLoader {
+            env: Some(env.clone()),
+            ..ConfigLoader::new()
+        }
+        .dir(dir.path())
+        .load()
+        .unwrap();
+
+        assert_eq!(config.database_url.expose(), "postgres://file/app");
+        assert_eq!(
+            config.sources.get("database_url"),
+            &Source::SecretFile(url_file)
+        );
+        assert!(!format!("{:?}", config).contains("postgres://file"));
+
+        let secrets = tempfile::tempdir().unwrap();
+        std::fs::write(secrets.path().join("DATABASE_URL"), "postgres://vault/app").unwrap();
+
+        let config = ConfigLoader {
+            env: Some(env),
+            ..ConfigLoader::new()
+        }
+        .dir(dir.path())
+        .secret_provider(crate::config::FileSecretProvider::new(secrets.path()))
+        .load()
+        .unwrap();
+
+        assert_eq!(config.database_url.expose(), "postgres://vault/app");
+        assert_eq!(
+            config.sources.get("database_url"),
+            &Source::Provider("files".to_string())
+        );
+    }
+
+    #[test]
+    fn test_file_variants_are_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("api_key");
+        std::fs::write(&key_file, "sk_live_123\n").unwrap();
+
+        let config = loader(
+            dir.path(),
+            &[
+                ("APP_HOST", "127.0.0.1"),
+                ("APP_HOST_FILE", "/nonexistent"),
+                ("PAYMENTS_API_KEY_FILE", key_file.to_str().unwrap()),
+            ],
+        );
+        let err = config.load().unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("set either APP_HOST or APP_HOST_FILE, not both"));
+
+        let config = loader(
+            dir.path(),
+            &[("PAYMENTS_API_KEY_FILE", key_file.to_str().unwrap())],
+        )
+        .load()
+        .unwrap();
+
+        #[derive(serde::Deserialize)]
+        struct Payments {
+            api_key: Secret<String>,
+        }
+        let payments: Payments = config.section("payments").unwrap();
+        assert_eq!(payments.api_key.expose(), "sk_live_123");
+    }
+}
@@ -0,0 +1,826 @@
+//! Application configuration module.
+//!
+//! Provides [`AppConfig`] and the [`ConfigLoader`] that builds it from
+//! layered sources. Later layers override earlier ones:
+//!
+//! 1. Built-in defaults
+//! 2. `sword.toml`
+//! 3. `sword.{profile}.toml`, where the profile comes from `SWORD_ENV`
+//!    (`dev`, `test` or `prod`; default `dev`)
+//! 4. Environment variables
+//! 5. CLI overrides (`--set key=value`)
+//!
+//! Files are read from `SWORD_CONFIG_DIR` (default: the current directory)
+//! and are optional. The origin of every value is recorded in
+//! [`AppConfig::sources`].
+//!
+//! Loading validates every setting and reports all problems at once in a
+//! [`ConfigError`], naming the variable, the rejected value (redacted for
+//! secrets such as `DATABASE_URL`), where it came from and what was
+//! expected:
+//!
+//! ```text
+//! invalid configuration (2 problems):
+//!   - APP_PORT="http" (from env APP_PORT): expected an integer between 1 and 65535
+//!   - DATABASE_URL: must be set
+//! ```
+//!
+//! The `DATABASE_URL` and `DB_*` settings only exist when the `database`
+//! feature is enabled.
+//!
+//! ## Secrets
+//!
+//! Secret settings are held in a [`Secret`], whose `Debug` and `Display`
+//! output is `[REDACTED]`. Any environment variable can instead be read
+//! from a file by setting `<VAR>_FILE` to its path, e.g.
+//! `DATABASE_URL_FILE=/run/secrets/database_url`; trailing newlines are
+//! removed. Setting both `<VAR>` and `<VAR>_FILE` is an error. In
+//! [application sections](#application-sections) this applies only to
+//! fields declared as `Secret`.
+//!
+//! Secret settings can also come from an external store by registering a
+//! [`SecretProvider`] with [`ConfigLoader::secret_provider`].
+//! [`FileSecretProvider`] reads one file per secret from a directory:
+//!
+//! ```rust,ignore
+//! use sword_ai::config::{ConfigLoader, FileSecretProvider};
+//!
+//! let config = ConfigLoader::new()
+//!     .secret_provider(FileSecretProvider::new("/run/secrets"))
+//!     .load()?;
+//! ```
+//!
+//! ## Settings
+//!
+//! | Key | Environment Variable | Description | Default |
+//! |-----|----------------------|-------------|---------|
+//! | `database_url` | `DATABASE_URL` | Database connection URL (`postgres://`, `mysql://` or `sqlite:`) | **Required** with the `database` feature |
+//! | `host` | `APP_HOST` | Server bind host | `0.0.0.0` |
+//! | `port` | `APP_PORT` | Server bind port (1-65535) | `3000` |
+//! | `db_max_connections` | `DB_MAX_CONNECTIONS` | Maximum database connections (1-10000) | `100` (SQLite: `8`) |
+//! | `db_min_connections` | `DB_MIN_CONNECTIONS` | Minimum database connections, at most `db_max_connections` | `5` (SQLite: `1`) |
+//! | `db_connect_timeout` | `DB_CONNECT_TIMEOUT` | Connection timeout in seconds | `8` |
+//! | `db_idle_timeout` | `DB_IDLE_TIMEOUT` | Idle connection timeout in seconds | `600` |
+//! | `db_max_lifetime` | `DB_MAX_LIFETIME` | Maximum connection lifetime in seconds | `1800` |
+//! | `db_connect_max_attempts` | `DB_CONNECT_MAX_ATTEMPTS` | Connection attempts at startup before giving up | `5` |
+//! | `db_connect_initial_delay_ms` | `DB_CONNECT_INITIAL_DELAY_MS` | Delay after the first failed attempt, doubled after each one, in milliseconds | `500` |
+//! | `db_connect_max_delay_ms` | `DB_CONNECT_MAX_DELAY_MS` | Maximum delay between attempts, in milliseconds | `10000` |
+//! | `db_connect_jitter` | `DB_CONNECT_JITTER` | Randomize retry delays | `true` |
+//! | `db_start_degraded` | `DB_START_DEGRADED` | Start without waiting for the database; readiness fails until it is up | `false` |
+//! | `database_replica_urls` | `DATABASE_REPLICA_URLS` | Comma-separated read replica URLs, same backend as `database_url` | none |
+//! | `db_replica_max_connections` | `DB_REPLICA_MAX_CONNECTIONS` | Maximum connections per replica | as `db_max_connections` |
+//! | `db_replica_min_connections` | `DB_REPLICA_MIN_CONNECTIONS` | Minimum connections per replica | as `db_min_connections` |
+//! | `db_replica_check_interval` | `DB_REPLICA_CHECK_INTERVAL` | Seconds between replica health checks | `5` |
+//! | `db_read_your_writes` | `DB_READ_YOUR_WRITES` | Pin a request's reads to the primary after it writes | `true` |
+//! | `db_log_statements` | `DB_LOG_STATEMENTS` | Log executed SQL statements | `true` |
+//! | `db_log_level` | `DB_LOG_LEVEL` | Statement log level (`off`, `error`, `warn`, `info`, `debug`, `trace`) | `debug` |
+//! | `db_slow_query_threshold_ms` | `DB_SLOW_QUERY_THRESHOLD_MS` | Log and count queries at least this slow, in milliseconds (`0` disables) | `1000` |
+//! | `db_migrations` | `DB_MIGRATIONS` | `apply` pending migrations at boot, or `check` and refuse to start if any | `apply` |
+//! | `db_migration_lock_timeout` | `DB_MIGRATION_LOCK_TIMEOUT` | Seconds to wait for another instance's migrations on Postgres (`0` skips) | `300` |
+//! | `shutdown_drain_timeout` | `APP_SHUTDOWN_DRAIN_TIMEOUT` | Time to drain in-flight requests on shutdown, in seconds | `30` |
+//! | `shutdown_tasks_timeout` | `APP_SHUTDOWN_TASKS_TIMEOUT` | Time to wait for background tasks on shutdown, in seconds | `10` |
+//! | `shutdown_hook_timeout` | `APP_SHUTDOWN_HOOK_TIMEOUT` | Default time budget for each shutdown hook, in seconds | `5` |
+//! | `health_liveness_path` | `HEALTH_LIVENESS_PATH` | Liveness endpoint path | `/livez` |
+//! | `health_readiness_path` | `HEALTH_READINESS_PATH` | Readiness endpoint path | `/readyz` |
+//! | `health_check_timeout` | `HEALTH_CHECK_TIMEOUT` | Timeout for each readiness check, in seconds | `2` |
+//! | `metrics_enabled` | `METRICS_ENABLED` | Serve the metrics endpoint | `false` |
+//! | `metrics_path` | `METRICS_PATH` | Metrics endpoint path | `/metrics` |
+//! | `openapi_enabled` | `OPENAPI_ENABLED` | Serve the OpenAPI document and docs page | `true` |
+//! | `openapi_path` | `OPENAPI_PATH` | OpenAPI document path | `/openapi.json` |
+//! | `openapi_docs` | `OPENAPI_DOCS` | Docs page UI (`swagger` with the `swagger-ui` feature, `redoc` or `off`) | `off` |
+//! | `openapi_docs_path` | `OPENAPI_DOCS_PATH` | Docs page path | `/docs` |
+//! | `http_request_id` | `HTTP_REQUEST_ID` | Propagate `x-request-id`, generating one when missing | `true` |
+//! | `http_request_timeout` | `HTTP_REQUEST_TIMEOUT` | Time to handle a request before a `503`, in seconds (`0` disables) | `30` |
+//! | `http_body_limit` | `HTTP_BODY_LIMIT` | Largest request body, in bytes (`0` disables) | `2097152` |
+//! | `http_compression` | `HTTP_COMPRESSION` | Compress responses with gzip or brotli | `true` |
+//! | `http_catch_panic` | `HTTP_CATCH_PANIC` | Turn handler panics into `500` responses | `true` |
+//! | `cors_allowed_origins` | `CORS_ALLOWED_ORIGINS` | Comma-separated origins allowed by CORS, or `*` (empty disables CORS) | none |
+//! | `cors_allowed_methods` | `CORS_ALLOWED_METHODS` | Comma-separated methods allowed by CORS, or `*` | `GET,POST,PUT,PATCH,DELETE` |
+//! | `cors_allowed_headers` | `CORS_ALLOWED_HEADERS` | Comma-separated request headers allowed by CORS, or `*` | `content-type,authorization` |
+//! | `cors_allow_credentials` | `CORS_ALLOW_CREDENTIALS` | Allow credentialed CORS requests | `false` |
+//! | `cors_max_age` | `CORS_MAX_AGE` | Time browsers may cache a preflight response, in seconds | `600` |
+//! | `http_concurrency_limit` | `HTTP_CONCURRENCY_LIMIT` | Requests handled at once (`0` disables) | `0` |
+//! | `http_concurrency_queue` | `HTTP_CONCURRENCY_QUEUE` | Requests waiting for a slot before new ones get a `503` | `100` |
+//! | `rate_limit_requests` | `RATE_LIMIT_REQUESTS` | Requests allowed per client and period (`0` disables) | `0` |
+//! | `rate_limit_period` | `RATE_LIMIT_PERIOD` | Rate limit period, in seconds | `60` |
+//! | `rate_limit_algorithm` | `RATE_LIMIT_ALGORITHM` | `token_bucket` or `sliding_window` | `token_bucket` |
+//! | `rate_limit_key` | `RATE_LIMIT_KEY` | Client key: `ip`, `api_key` or `user` | `ip` |
+//! | `rate_limit_trusted_proxies` | `RATE_LIMIT_TRUSTED_PROXIES` | Comma-separated proxy addresses or CIDR ranges trusted for `X-Forwarded-For` | none |
+//! | `pagination_default_size` | `PAGINATION_DEFAULT_SIZE` | Items per page when `per_page` is not given | `20` |
+//! | `pagination_max_size` | `PAGINATION_MAX_SIZE` | Largest `per_page` a client may ask for | `100` |
+//! | `pagination_cursor_secret` | `PAGINATION_CURSOR_SECRET` | Key signing pagination cursors | random at startup |
+//!
+//! ## Example
+//!
+//! ```toml
+//! # sword.toml
+//! port = 8080
+//! db_max_connections = 20
+//! ```
+//!
+//! ```rust,ignore
+//! use sword_ai::{config::ConfigLoader, AppConfig};
+//!
+//! // Files and environment only
+//! let config = AppConfig::from_env()?;
+//!
+//! // With `--set key=value` overrides from the command line
+//! let config = ConfigLoader::new().args(std::env::args()).load()?;
+//! println!("Binding to {} (port from {})", config.bind_address(), config.sources.get("port"));
+//! ```
+//!
+//! ## Application Sections
+//!
+//! Application-specific settings live in their own TOML table and are
+//! deserialized into any `serde` type with [`AppConfig::section`]:
+//!
+//! ```toml
+//! # sword.toml
+//! [payments]
+//! api_url = "https://api.stripe.com"
+//! max_retries = 3
+//! ```
+//!
+//! ```rust,ignore
+//! #[derive(Deserialize)]
+//! struct PaymentsConfig {
+//!     api_url: String,
+//!     api_key: String, // from PAYMENTS_API_KEY
+//!     max_retries: u32,
+//! }
+//!
+//! let payments: PaymentsConfig = ctx.config.section("payments")?;
+//! ```
+//!
+//! Section names sharing an environment variable prefix with framework
+//! settings, such as `app` (`APP_PORT`) and `db` (`DB_LOG_LEVEL`), are
+//! reserved.
+
+mod error;
+mod loader;
+mod secret;
+mod section;
+mod validate;
+
+pub use error::{ConfigError, ConfigIssue};
+pub use loader::{ConfigLoader, ConfigSources, Profile, Source};
+pub use secret::{FileSecretProvider, Secret, SecretProvider};
+pub use section::ConfigSections;
+
+use validate::{Rule, Setting};
+
+#[cfg(feature = "database")]
+use crate::db::{LogLevel, MigrationMode};
+use crate::openapi::DocsUi;
+use crate::rate_limit::{Algorithm, ClientKey};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Framework settings, in documentation order.
+const SETTINGS: &[Setting] = &[
+    setting("host", "APP_HOST", Rule::Text),
+    setting("port", "APP_PORT", Rule::Int { min: 1, max: 65535 }),
+    setting(
+        "shutdown_drain_timeout",
+        "APP_SHUTDOWN_DRAIN_TIMEOUT",
+        Rule::Int { min: 0, max: 3600 },
+    ),
+    setting(
+        "shutdown_tasks_timeout",
+        "APP_SHUTDOWN_TASKS_TIMEOUT",
+        Rule::Int { min: 0, max: 3600 },
+    ),
+    setting(
+        "shutdown_hook_timeout",
+        "APP_SHUTDOWN_HOOK_TIMEOUT",
+        Rule::Int { min: 1, max: 3600 },
+    ),
+    setting("health_liveness_path", "HEALTH_LIVENESS_PATH", Rule::Path),
+    setting("health_readiness_path", "HEALTH_READINESS_PATH", Rule::Path),
+    setting(
+        "health_check_timeout",
+        "HEALTH_CHECK_TIMEOUT",
+        Rule::Int { min: 1, max: 300 },
+    ),
+    setting("metrics_enabled", "METRICS_ENABLED", Rule::Bool),
+    setting("metrics_path", "METRICS_PATH", Rule::Path),
+    setting("openapi_enabled", "OPENAPI_ENABLED", Rule::Bool),
+    setting("openapi_path", "OPENAPI_PATH", Rule::Path),
+    setting("openapi_docs", "OPENAPI_DOCS", Rule::DocsUi),
+    setting("openapi_docs_path", "OPENAPI_DOCS_PATH", Rule::Path),
+    setting("http_request_id", "HTTP_REQUEST_ID", Rule::Bool),
+    setting(
+        "http_request_timeout",
+        "HTTP_REQUEST_TIMEOUT",
+        Rule::Int { min: 0, max: 3600 },
+    ),
+    setting(
+        "http_body_limit",
+        "HTTP_BODY_LIMIT",
+        Rule::Int {
+            min: 0,
+            max: 1 << 40,
+        },
+    ),
+    setting("http_compression", "HTTP_COMPRESSION", Rule::Bool),
+    setting("http_catch_panic", "HTTP_CATCH_PANIC", Rule::Bool),
+    setting(
+        "cors_allowed_origins",
+        "CORS_ALLOWED_ORIGINS",
+        Rule::Origins,
+    ),
+    setting("cors_allowed_methods", "CORS_ALLOWED_METHODS", Rule::Tokens),
+    setting("cors_allowed_headers", "CORS_ALLOWED_HEADERS", Rule::Tokens),
+    setting(
+        "cors_allow_credentials",
+        "CORS_ALLOW_CREDENTIALS",
+        Rule::Bool,
+    ),
+    setting(
+        "cors_max_age",
+        "CORS_MAX_AGE",
+        Rule::Int {
+            min: 0,
+            max: 86_400,
+        },
+    ),
+    setting(
+        "http_concurrency_limit",
+        "HTTP_CONCURRENCY_LIMIT",
+        Rule::Int {
+            min: 0,
+            max: 1_000_000,
+        },
+    ),
+    setting(
+        "http_concurrency_queue",
+        "HTTP_CONCURRENCY_QUEUE",
+        Rule::Int {
+            min: 0,
+            max: 1_000_000,
+        },
+    ),
+    setting(
+        "rate_limit_requests",
+        "RATE_LIMIT_REQUESTS",
+        Rule::Int {
+            min: 0,
+            max: 1_000_000_000,
+        },
+    ),
+    setting(
+        "rate_limit_period",
+        "RATE_LIMIT_PERIOD",
+        Rule::Int {
+            min: 1,
+            max: 86_400,
+        },
+    ),
+    setting(
+        "rate_limit_algorithm",
+        "RATE_LIMIT_ALGORITHM",
+        Rule::OneOf(&["token_bucket", "sliding_window"]),
+    ),
+    setting(
+        "rate_limit_key",
+        "RATE_LIMIT_KEY",
+        Rule::OneOf(&["ip", "api_key", "user"]),
+    ),
+    setting(
+        "rate_limit_trusted_proxies",
+        "RATE_LIMIT_TRUSTED_PROXIES",
+        Rule::IpNets,
+    ),
+    setting(
+        "pagination_default_size",
+        "PAGINATION_DEFAULT_SIZE",
+        Rule::Int {
+            min: 1,
+            max: 10_000,
+        },
+    ),
+    setting(
+        "pagination_max_size",
+        "PAGINATION_MAX_SIZE",
+        Rule::Int {
+            min: 1,
+            max: 10_000,
+        },
+    ),
+    secret(
+        "pagination_cursor_secret",
+        "PAGINATION_CURSOR_SECRET",
+        Rule::Text,
+    ),
+];
+
+/// Database settings, only read when the `database` feature is enabled.
+#[cfg(feature = "database")]
+const DATABASE_SETTINGS: &[Setting] = &[
+    secret("database_url", "DATABASE_URL", Rule::DatabaseUrl),
+    setting(
+        "db_max_connections",
+        "DB_MAX_CONNECTIONS",
+        Rule::Int {
+            min: 1,
+            max: 10_000,
+        },
+    ),
+    setting(
+        "db_min_connections",
+        "DB_MIN_CONNECTIONS",
+        Rule::Int {
+            min: 0,
+            max: 10_000,
+        },
+    ),
+    setting(
+        "db_connect_timeout",
+        "DB_CONNECT_TIMEOUT",
+        Rule::Int { min: 1, max: 3600 },
+    ),
+    setting(
+        "db_idle_timeout",
+        "DB_IDLE_TIMEOUT",
+        Rule::Int {
+            min: 1,
+            max: 86_400,
+        },
+    ),
+    setting(
+        "db_max_lifetime",
+        "DB_MAX_LIFETIME",
+        Rule::Int {
+            min: 1,
+            max: 86_400,
+        },
+    ),
+    setting(
+        "db_connect_max_attempts",
+        "DB_CONNECT_MAX_ATTEMPTS",
+        Rule::Int { min: 1, max: 1000 },
+    ),
+    setting(
+        "db_connect_initial_delay_ms",
+        "DB_CONNECT_INITIAL_DELAY_MS",
+        Rule::Int {
+            min: 1,
+            max: 600_000,
+        },
+    ),
+    setting(
+        "db_connect_max_delay_ms",
+        "DB_CONNECT_MAX_DELAY_MS",
+        Rule::Int {
+            min: 1,
+            max: 600_000,
+        },
+    ),
+    setting("db_connect_jitter", "DB_CONNECT_JITTER", Rule::Bool),
+    setting("db_start_degraded", "DB_START_DEGRADED", Rule::Bool),
+    secret(
+        "database_replica_urls",
+        "DATABASE_REPLICA_URLS",
+        Rule::DatabaseUrls,
+    ),
+    setting(
+        "db_replica_max_connections",
+        "DB_REPLICA_MAX_CONNECTIONS",
+        Rule::Int {
+            min: 1,
+            max: 10_000,
+        },
+    ),
+    setting(
+        "db_replica_min_connections",
+        "DB_REPLICA_MIN_CONNECTIONS",
+        Rule::Int {
+            min: 0,
+            max: 10_000,
+        },
+    ),
+    setting(
+        "db_replica_check_interval",
+        "DB_REPLICA_CHECK_INTERVAL",
+        Rule::Int { min: 1, max: 3600 },
+    ),
+    setting("db_read_your_writes", "DB_READ_YOUR_WRITES", Rule::Bool),
+    setting("db_log_statements", "DB_LOG_STATEMENTS", Rule::Bool),
+    setting(
+        "db_log_level",
+        "DB_LOG_LEVEL",
+        Rule::OneOf(&["off", "error", "warn", "info", "debug", "trace"]),
+    ),
+    setting(
+        "db_slow_query_threshold_ms",
+        "DB_SLOW_QUERY_THRESHOLD_MS",
+        Rule::Int {
+            min: 0,
+            max: 3_600_000,
+        },
+    ),
+    setting(
+        "db_migrations",
+        "DB_MIGRATIONS",
+        Rule::OneOf(&["apply", "check"]),
+    ),
+    setting(
+        "db_migration_lock_timeout",
+        "DB_MIGRATION_LOCK_TIMEOUT",
+        Rule::Int {
+            min: 0,
+            max: 86_400,
+        },
+    ),
+];
+
+const fn setting(key: &'static str, var: &'static str, rule: Rule) -> Setting {
+    Setting {
+        key,
+        var,
+        rule,
+        secret: false,
+    }
+}
+
+const fn secret(key: &'static str, var: &'static str, rule: Rule) -> Setting {
+    Setting {
+        key,
+        var,
+        rule,
+        secret: true,
+    }
+}
+
+/// Returns every setting known to [`AppConfig`].
+pub(crate) fn settings() -> impl Iterator<Item = &'static Setting> {
+    #[cfg(feature = "database")]
+    let database = DATABASE_SETTINGS.iter();
+    #[cfg(not(feature = "database"))]
+    let database = [].iter();
+
+    SETTINGS.iter().chain(database)
+}
+
+/// Application configuration loaded from layered sources.
+///
+/// Use [`AppConfig::from_env`] or a [`ConfigLoader`] to load it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Server bind host (from `APP_HOST`, default: `0.0.0.0`).
+    pub host: String,
+    /// Server bind port (from `APP_PORT`, default: `3000`).
+    pub port: u16,
+    /// Database connection URL (from `DATABASE_URL` or
+    /// `DATABASE_URL_FILE`, required). The scheme selects the backend; see
+    /// [`crate::db`].
+    #[cfg(feature = "database")]
+    pub database_url: Secret<String>,
+    /// Maximum database connections (from `DB_MAX_CONNECTIONS`). `None`
+    /// uses the backend default: `100`, or `8` for SQLite.
+    #[cfg(feature = "database")]
+    pub db_max_connections: Option<u32>,
+    /// Minimum database connections (from `DB_MIN_CONNECTIONS`). `None`
+    /// uses the backend default: `5`, or `1` for SQLite.
+    #[cfg(feature = "database")]
+    pub db_min_connections: Option<u32>,
+    /// Connection timeout in seconds (from `DB_CONNECT_TIMEOUT`, default: `8`).
+    #[cfg(feature = "database")]
+    pub db_connect_timeout: u64,
+    /// Idle connection timeout in seconds (from `DB_IDLE_TIMEOUT`, default: `600`).
+    #[cfg(feature = "database")]
+    pub db_idle_timeout: u64,
+    /// Maximum connection lifetime in seconds (from `DB_MAX_LIFETIME`, default: `1800`).
+    #[cfg(feature = "database")]
+    pub db_max_lifetime: u64,
+    /// Connection attempts at startup before giving up
+    /// (from `DB_CONNECT_MAX_ATTEMPTS`, default: `5`).
+    #[cfg(feature = "database")]
+    pub db_connect_max_attempts: u32,
+    /// Delay after the first failed connection attempt, in milliseconds
+    /// (from `DB_CONNECT_INITIAL_DELAY_MS`, default: `500`). Doubles after
+    /// each further failure.
+    #[cfg(feature = "database")]
+    pub db_connect_initial_delay_ms: u64,
+    /// Maximum delay between connection attempts, in milliseconds
+    /// (from `DB_CONNECT_MAX_DELAY_MS`, default: `10000`).
+    #[cfg(feature = "database")]
+    pub db_connect_max_delay_ms: u64,
+    /// Randomize retry delays (from `DB_CONNECT_JITTER`, default: `true`).
+    #[cfg(feature = "database")]
+    pub db_connect_jitter: bool,
+    /// Start serving without waiting for the database
+    /// (from `DB_START_DEGRADED`, default: `false`). Readiness reports the
+    /// database as down until it is reachable and migrated.
+    #[cfg(feature = "database")]
+    pub db_start_degraded: bool,
+    /// Read replica URLs (from `DATABASE_REPLICA_URLS`, comma-separated,
+    /// default: none). See [`crate::db::Db`].
+    #[cfg(feature = "database")]
+    pub database_replica_urls: Secret<Vec<String>>,
+    /// Maximum connections per replica (from `DB_REPLICA_MAX_CONNECTIONS`).
+    /// `None` uses the backend default.
+    #[cfg(feature = "database")]
+    pub db_replica_max_connections: Option<u32>,
+    /// Minimum connections per replica (from `DB_REPLICA_MIN_CONNECTIONS`).
+    /// `None` uses the backend default.
+    #[cfg(feature = "database")]
+    pub db_replica_min_connections: Option<u32>,
+    /// Seconds between replica health checks
+    /// (from `DB_REPLICA_CHECK_INTERVAL`, default: `5`).
+    #[cfg(feature = "database")]
+    pub db_replica_check_interval: u64,
+    /// Send a request's reads to the primary once it has used the writer
+    /// (from `DB_READ_YOUR_WRITES`, default: `true`).
+    #[cfg(feature = "database")]
+    pub db_read_your_writes: bool,
+    /// Log executed statements (from `DB_LOG_STATEMENTS`, default: `true`).
+    #[cfg(feature = "database")]
+    pub db_log_statements: bool,
+    /// Level statements are logged at: `off`, `error`, `warn`, `info`,
+    /// `debug` or `trace` (from `DB_LOG_LEVEL`, default: `debug`).
+    #[cfg(feature = "database")]
+    pub db_log_level: LogLevel,
+    /// Queries taking at least this long are logged at `WARN` and counted,
+    /// in milliseconds (from `DB_SLOW_QUERY_THRESHOLD_MS`, default: `1000`).
+    /// `0` disables slow-query detection.
+    #[cfg(feature = "database")]
+    pub db_slow_query_threshold_ms: u64,
+    /// What to do with pending migrations at boot: `apply` them or only
+    /// `check` that there are none (from `DB_MIGRATIONS`, default: `apply`).
+    #[cfg(feature = "database")]
+    pub db_migrations: MigrationMode,
+    /// Seconds to wait for another instance that is migrating Postgres
+    /// (from `DB_MIGRATION_LOCK_TIMEOUT`, default: `300`). `0` skips
+    /// migrations instead of waiting.
+    #[cfg(feature = "database")]
+    pub db_migration_lock_timeout: u64,
+    /// Time to drain in-flight requests on shutdown, in seconds
+    /// (from `APP_SHUTDOWN_DRAIN_TIMEOUT`, default: `30`).
+    pub shutdown_drain_timeout: u64,
+    /// Time to wait for background tasks on shutdown, in seconds
+    /// (from `APP_SHUTDOWN_TASKS_TIMEOUT`, default: `10`).
+    pub shutdown_tasks_timeout: u64,
+    /// Default time budget for each shutdown hook, in seconds
+    /// (from `APP_SHUTDOWN_HOOK_TIMEOUT`, default: `5`).
+    pub shutdown_hook_timeout: u64,
+    /// Liveness endpoint path (from `HEALTH_LIVENESS_PATH`, default: `/livez`).
+    pub health_liveness_path: String,
+    /// Readiness endpoint path (from `HEALTH_READINESS_PATH`, default: `/readyz`).
+    pub health_readiness_path: String,
+    /// Timeout for each readiness check, in seconds
+    /// (from `HEALTH_CHECK_TIMEOUT`, default: `2`).
+    pub health_check_timeout: u64,
+    /// Serve metrics (from `METRICS_ENABLED`, default: `false`). See
+    /// [`crate::metrics`].
+    pub metrics_enabled: bool,
+    /// Metrics endpoint path (from `METRICS_PATH`, default: `/metrics`).
+    pub metrics_path: String,
+    /// Serve the OpenAPI document and docs page (from `OPENAPI_ENABLED`,
+    /// default: `true`). See [`crate::openapi`].
+    pub openapi_enabled: bool,
+    /// OpenAPI document path (from `OPENAPI_PATH`, default: `/openapi.json`).
+    pub openapi_path: String,
+    /// Docs page UI: `swagger` (with the `swagger-ui` feature), `redoc` or
+    /// `off` (from `OPENAPI_DOCS`, default: `off`).
+    pub openapi_docs: DocsUi,
+    /// Docs page path (from `OPENAPI_DOCS_PATH`, default: `/docs`).
+    pub openapi_docs_path: String,
+    /// Propagate the `x-request-id` header, generating one when missing
+    /// (from `HTTP_REQUEST_ID`, default: `true`). See [`crate::middleware`].
+    pub http_request_id: bool,
+    /// Time to handle a request before responding `503`, in seconds
+    /// (from `HTTP_REQUEST_TIMEOUT`, default: `30`). `0` disables the timeout.
+    pub http_request_timeout: u64,
+    /// Largest request body, in bytes (from `HTTP_BODY_LIMIT`, default:
+    /// `2097152`). `0` disables the limit.
+    pub http_body_limit: u64,
+    /// Compress responses (from `HTTP_COMPRESSION`, default: `true`).
+    pub http_compression: bool,
+    /// Turn handler panics into `500` responses
+    /// (from `HTTP_CATCH_PANIC`, default: `true`).
+    pub http_catch_panic: bool,
+    /// Origins allowed by CORS, or `*` for any
+    /// (from `CORS_ALLOWED_ORIGINS`, default: none). CORS is disabled when
+    /// empty.
+    pub cors_allowed_origins: Vec<String>,
+    /// Methods allowed by CORS, or `*` for any (from `CORS_ALLOWED_METHODS`,
+    /// default: `GET,POST,PUT,PATCH,DELETE`).
+    pub cors_allowed_methods: Vec<String>,
+    /// Request headers allowed by CORS, or `*` for any
+    /// (from `CORS_ALLOWED_HEADERS`, default: `content-type,authorization`).
+    pub cors_allowed_headers: Vec<String>,
+    /// Allow credentialed CORS requests
+    /// (from `CORS_ALLOW_CREDENTIALS`, default: `false`).
+    pub cors_allow_credentials: bool,
+    /// Time browsers may cache a preflight response, in seconds
+    /// (from `CORS_MAX_AGE`, default: `600`).
+    pub cors_max_age: u64,
+    /// Requests handled at once (from `HTTP_CONCURRENCY_LIMIT`, default:
+    /// `0`). Further requests wait for a slot. `0` disables the limit.
+    pub http_concurrency_limit: u64,
+    /// Requests that may wait for a slot under `HTTP_CONCURRENCY_LIMIT`
+    /// before new ones are shed with a `503`
+    /// (from `HTTP_CONCURRENCY_QUEUE`, default: `100`).
+    pub http_concurrency_queue: u64,
+    /// Requests allowed per client and period (from `RATE_LIMIT_REQUESTS`,
+    /// default: `0`). `0` disables the global rate limit. See
+    /// [`crate::rate_limit`].
+    pub rate_limit_requests: u64,
+    /// Rate limit period, in seconds (from `RATE_LIMIT_PERIOD`, default: `60`).
+    pub rate_limit_period: u64,
+    /// Rate limit algorithm: `token_bucket` or `sliding_window`
+    /// (from `RATE_LIMIT_ALGORITHM`, default: `token_bucket`).
+    pub rate_limit_algorithm: Algorithm,
+    /// What identifies a client: `ip`, `api_key` or `user`
+    /// (from `RATE_LIMIT_KEY`, default: `ip`).
+    pub rate_limit_key: ClientKey,
+    /// Proxy addresses or CIDR ranges whose `X-Forwarded-For` header is
+    /// trusted for the client IP (from `RATE_LIMIT_TRUSTED_PROXIES`,
+    /// default: none).
+    pub rate_limit_trusted_proxies: Vec<String>,
+    /// Items per page when the request does not set `per_page`
+    /// (from `PAGINATION_DEFAULT_SIZE`, default: `20`). See
+    /// [`crate::pagination`].
+    pub pagination_default_size: u64,
+    /// Largest `per_page` a client may ask for
+    /// (from `PAGINATION_MAX_SIZE`, default: `100`).
+    pub pagination_max_size: u64,
+    /// Key signing pagination cursors (from `PAGINATION_CURSOR_SECRET`).
+    /// When empty, a random key is generated at startup.
+    pub pagination_cursor_secret: Secret<String>,
+    /// Active profile (from `SWORD_ENV`, default: `dev`).
+    #[serde(skip)]
+    pub profile: Profile,
+    /// Where each value was loaded from.
+    #[serde(skip)]
+    pub sources: ConfigSources,
+    /// Application-specific sections; see [`AppConfig::section`].
+    #[serde(skip)]
+    pub sections: ConfigSections,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            #[cfg(feature = "database")]
+            database_url: Secret::default(),
+            #[cfg(feature = "database")]
+            db_max_connections: None,
+            #[cfg(feature = "database")]
+            db_min_connections: None,
+            #[cfg(feature = "database")]
+            db_connect_timeout: 8,
+            #[cfg(feature = "database")]
+            db_idle_timeout: 600,
+            #[cfg(feature = "database")]
+            db_max_lifetime: 1800,
+            #[cfg(feature = "database")]
+            db_connect_max_attempts: 5,
+            #[cfg(feature = "database")]
+            db_connect_initial_delay_ms: 500,
+            #[cfg(feature = "database")]
+            db_connect_max_delay_ms: 10_000,
+            #[cfg(feature = "database")]
+            db_connect_jitter: true,
+            #[cfg(feature = "database")]
+            db_start_degraded: false,
+            #[cfg(feature = "database")]
+            database_replica_urls: Secret::default(),
+            #[cfg(feature = "database")]
+            db_replica_max_connections: None,
+            #[cfg(feature = "database")]
+            db_replica_min_connections: None,
+            #[cfg(feature = "database")]
+            db_replica_check_interval: 5,
+            #[cfg(feature = "database")]
+            db_read_your_writes: true,
+            #[cfg(feature = "database")]
+            db_log_statements: true,
+            #[cfg(feature = "database")]
+            db_log_level: LogLevel::Debug,
+            #[cfg(feature = "database")]
+            db_slow_query_threshold_ms: 1000,
+            #[cfg(feature = "database")]
+            db_migrations: MigrationMode::Apply,
+            #[cfg(feature = "database")]
+            db_migration_lock_timeout: 300,
+            shutdown_drain_timeout: 30,
+            shutdown_tasks_timeout: 10,
+            shutdown_hook_timeout: 5,
+            health_liveness_path: "/livez".to_string(),
+            health_readiness_path: "/readyz".to_string(),
+            health_check_timeout: 2,
+            metrics_enabled: false,
+            metrics_path: "/metrics".to_string(),
+            openapi_enabled: true,
+            openapi_path: "/openapi.json".to_string(),
+            openapi_docs: DocsUi::Off,
+            openapi_docs_path: "/docs".to_string(),
+            http_request_id: true,
+            http_request_timeout: 30,
+            http_body_limit: 2 * 1024 * 1024,
+            http_compression: true,
+            http_catch_panic: true,
+            cors_allowed_origins: Vec::new(),
+            cors_allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+            cors_allowed_headers: ["content-type", "authorization"].map(String::from).to_vec(),
+            cors_allow_credentials: false,
+            cors_max_age: 600,
+            http_concurrency_limit: 0,
+            http_concurrency_queue: 100,
+            rate_limit_requests: 0,
+            rate_limit_period: 60,
+            rate_limit_algorithm: Algorithm::TokenBucket,
+            rate_limit_key: ClientKey::Ip,
+            rate_limit_trusted_proxies: Vec::new(),
+            pagination_default_size: 20,
+            pagination_max_size: 100,
+            pagination_cursor_secret: Secret::default(),
+            profile: Profile::default(),
+            sources: ConfigSources::default(),
+            sections: ConfigSections::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Loads configuration from config files and environment variables.
+    ///
+    /// Equivalent to `ConfigLoader::new().load()`; see [`ConfigLoader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError`] if a config file cannot be parsed, or listing
+    /// every missing or invalid setting.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        ConfigLoader::new().load()
+    }
+
+    /// Deserializes the application-specific section `name` into `T`.
+    ///
+    /// See [`ConfigSections`] for where section values are read from.
+    /// Register the section with
+    /// [`SwordAppBuilder::config_section`](crate::SwordAppBuilder::config_section)
+    /// to have it validated at boot.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Section`] if a required field is missing or a
+    /// value has the wrong type.
+    pub fn section<T: DeserializeOwned>(&self, name: &str) -> Result<T, ConfigError> {
+        self.sections.get(name)
+    }
+
+    /// Returns the server bind address in `host:port` format.
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_config() -> AppConfig {
+        AppConfig {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            #[cfg(feature = "database")]
+            database_url: "postgres://localhost/test".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bind_address() {
+        let config = default_config();
+        assert_eq!(config.bind_address(), "127.0.0.1:8080");
+    }
+
+    #[test]
+    fn test_bind_address_default_format() {
+        let mut config = default_config();
+        config.host = "0.0.0.0".to_string();
+        config.port = 3000;
+        assert_eq!(config.bind_address(), "0.0.0.0:3000");
+    }
+
+    #[test]
+    fn test_config_clone() {
+        let config = default_config();
+        let cloned = config.clone();
+        assert_eq!(config.host, cloned.host);
+        assert_eq!(config.port, cloned.port);
+        #[cfg(feature = "database")]
+        assert_eq!(config.database_url, cloned.database_url);
+        #[cfg(feature = "database")]
+        assert_eq!(config.db_max_connections, cloned.db_max_connections);
+    }
+}
@@ -0,0 +1,310 @@
+//! Secret values and secret sources.
+
+use serde::de::value::Error as ProbeError;
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+/// Name `Secret` is deserialized under, so that secret fields can be told
+/// apart from plain ones.
+const SECRET: &str = "Secret";
+
+/// A value that must not appear in logs.
+///
+/// `Debug` and `Display` print `[REDACTED]`; call [`Secret::expose`] to
+/// read the value. `Secret` deliberately does not implement `Serialize`.
+///
+/// ```rust
+/// use sword_ai::config::Secret;
+///
+/// let url = Secret::new("postgres://user:hunter2@db/app".to_string());
+/// assert_eq!(format!("{:?}", url), "Secret([REDACTED])");
+/// assert!(url.expose().contains("hunter2"));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wraps a value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Consumes the wrapper and returns the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(SECRET, SecretVisitor(PhantomData))
+    }
+}
+
+struct SecretVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SecretVisitor<T> {
+    type Value = Secret<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a secret value")
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        T::deserialize(d).map(Secret)
+    }
+
+    // Formats without newtype structs pass the value itself.
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        T::deserialize(v.into_deserializer()).map(Secret)
+    }
+}
+
+/// A store that secret settings (such as `DATABASE_URL`) are looked up in,
+/// e.g. Vault or a cloud secret manager.
+///
+/// Register one with
+/// [`ConfigLoader::secret_provider`](super::ConfigLoader::secret_provider).
+/// Values returned by the provider override config files and environment
+/// variables, but not CLI overrides.
+pub trait SecretProvider: Send + Sync {
+    /// Name shown as the source of values returned by this provider.
+    fn name(&self) -> &str;
+
+    /// Looks up the secret for the environment variable `var`, returning
+    /// `Ok(None)` if the provider has no value for it.
+    fn get(&self, var: &str) -> anyhow::Result<Option<String>>;
+}
+
+/// A [`SecretProvider`] that reads one file per secret from a directory,
+/// as mounted by Docker secrets (`/run/secrets`) or Kubernetes secret
+/// volumes.
+///
+/// The secret for `DATABASE_URL` is read from `DATABASE_URL` or, failing
+/// that, `database_url` in the directory. Trailing newlines are removed.
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    dir: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Creates a provider reading from `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn name(&self) -> &str {
+        "files"
+    }
+
+    fn get(&self, var: &str) -> anyhow::Result<Option<String>> {
+        for name in [var.to_string(), var.to_ascii_lowercase()] {
+            let path = self.dir.join(name);
+            if path.is_file() {
+                return Ok(Some(read_secret_file(&path)?));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads a secret file, removing trailing newlines.
+pub(crate) fn read_secret_file(path: &std::path::Path) -> std::io::Result<String> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Names of the fields of `T` declared as [`Secret`], found by running its
+/// `Deserialize` implementation against a [`Probe`]. Empty unless `T` is a
+/// struct.
+pub(crate) fn secret_fields<T: DeserializeOwned>() -> Vec<&'static str> {
+    let fields = Cell::new(&[][..]);
+    let _ = T::deserialize(Probe {
+        fields: &fields,
+        field: None,
+        secret: &Cell::new(false),
+    });
+    fields
+        .get()
+        .iter()
+        .copied()
+        .filter(|field| {
+            let secret = Cell::new(false);
+            let _ = T::deserialize(Probe {
+                fields: &fields,
+                field: Some(field),
+                secret: &secret,
+            });
+            secret.get()
+        })
+        .collect()
+}
+
+/// A deserializer that records the fields of a struct and, given one of
+/// them, whether its value is a [`Secret`]. Deserialization always fails.
+struct Probe<'a> {
+    fields: &'a Cell<&'static [&'static str]>,
+    field: Option<&'static str>,
+    secret: &'a Cell<bool>,
+}
+
+impl<'de> Deserializer<'de> for Probe<'_> {
+    type Error = ProbeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, ProbeError> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ProbeError> {
+        self.fields.set(fields);
+        match self.field {
+            Some(_) => visitor.visit_map(self),
+            None => Err(de::Error::custom("probed")),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _: V,
+    ) -> Result<V::Value, ProbeError> {
+        self.secret.set(name == SECRET);
+        Err(de::Error::custom("probed"))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+        visitor.visit_some(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Probe<'_> {
+    type Error = ProbeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ProbeError> {
+        self.field
+            .map(|field| seed.deserialize(field.into_deserializer()))
+            .transpose()
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ProbeError> {
+        seed.deserialize(Probe {
+            fields: self.fields,
+            field: None,
+            secret: self.secret,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret::new("hunter2".to_string());
+
+        assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn test_secret_fields() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Payments {
+            api_key: Secret<String>,
+            webhook_secret: Option<Secret<String>>,
+            api_key_file: String,
+            max_retries: u32,
+        }
+
+        assert_eq!(secret_fields::<Payments>(), ["api_key", "webhook_secret"]);
+        assert!(secret_fields::<Secret<String>>().is_empty());
+    }
+
+    #[test]
+    fn test_file_provider_reads_trimmed_value() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("database_url"), "postgres://db/app\n").unwrap();
+        let provider = FileSecretProvider::new(dir.path());
+
+        assert_eq!(
+            provider.get("DATABASE_URL").unwrap().as_deref(),
+            Some("postgres://db/app")
+        );
+        assert_eq!(provider.get("API_KEY").unwrap(), None);
+    }
+}
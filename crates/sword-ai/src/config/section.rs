@@ -0,0 +1,155 @@
+//! User-defined configuration sections.
+
+use super::secret::{read_secret_file, secret_fields};
+use super::ConfigError;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Raw values for application-specific settings, loaded from the same
+/// layers as [`AppConfig`](super::AppConfig).
+///
+/// A section named `payments` is read from:
+///
+/// 1. The `[payments]` table in `sword.toml`
+/// 2. The `[payments]` table in `sword.{profile}.toml`
+/// 3. Environment variables prefixed with `PAYMENTS_` (`PAYMENTS_API_KEY`
+///    sets `api_key`; if `api_key` is a [`Secret`](super::Secret),
+///    `PAYMENTS_API_KEY_FILE` reads it from a file instead)
+/// 4. CLI overrides such as `--set payments.api_key=...`
+///
+/// Sections are flat: each key maps to a single value, and arrays are
+/// passed as comma-separated lists.
+///
+/// Environment variables are read once, when the configuration is loaded,
+/// and each secret file the first time a section needs it. Names whose
+/// variables would clash with framework settings, such as `app` (`APP_*`)
+/// and `db` (`DB_*`), are reserved.
+#[derive(Clone, Default)]
+pub struct ConfigSections {
+    files: BTreeMap<String, BTreeMap<String, String>>,
+    overrides: BTreeMap<String, BTreeMap<String, String>>,
+    env: Arc<HashMap<String, String>>,
+    secret_files: Arc<Mutex<HashMap<PathBuf, String>>>,
+}
+
+impl fmt::Debug for ConfigSections {
+    // Values may hold credentials, so only section names are shown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+impl ConfigSections {
+    pub(crate) fn with_env(env: HashMap<String, String>) -> Self {
+        Self {
+            env: Arc::new(env),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `name` is kept from sections because framework settings use
+    /// its environment variable prefix. `DB_*` is reserved even without the
+    /// `database` feature.
+    pub(crate) fn is_reserved(name: &str) -> bool {
+        let prefix = format!("{}_", name.to_ascii_uppercase());
+        ["sword", "db"].iter().any(|r| r.eq_ignore_ascii_case(name))
+            || super::settings().any(|s| s.var.starts_with(&prefix))
+    }
+
+    pub(crate) fn set_from_file(&mut self, section: &str, key: &str, value: String) {
+        self.files
+            .entry(section.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+
+    pub(crate) fn set_override(&mut self, section: &str, key: &str, value: String) {
+        self.overrides
+            .entry(section.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+
+    /// Names of the sections defined in config files or CLI overrides.
+    ///
+    /// Sections set only through environment variables are not listed.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self
+            .files
+            .keys()
+            .chain(self.overrides.keys())
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names.into_iter()
+    }
+
+    /// Deserializes the section `name` into `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Section`] if `name` is reserved, a required
+    /// field is missing or a value has the wrong type.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, ConfigError> {
+        let section_error = |message: String| ConfigError::Section {
+            name: name.to_string(),
+            message,
+        };
+        if Self::is_reserved(name) {
+            return Err(section_error(
+                "name is reserved for framework settings".to_string(),
+            ));
+        }
+        let mut values = self.files.get(name).cloned().unwrap_or_default();
+
+        let secrets = secret_fields::<T>();
+        let prefix = format!("{}_", name.to_ascii_uppercase());
+        for (var, value) in self.env.iter() {
+            let Some(key) = var.strip_prefix(&prefix) else {
+                continue;
+            };
+            let secret = key
+                .strip_suffix("_FILE")
+                .filter(|key| secrets.contains(&key.to_ascii_lowercase().as_str()));
+            match secret {
+                Some(key) => {
+                    let plain = format!("{}{}", prefix, key);
+                    if self.env.contains_key(&plain) {
+                        return Err(section_error(format!(
+                            "set either {} or {}, not both",
+                            plain, var
+                        )));
+                    }
+                    let content = self.secret_file(Path::new(value)).map_err(|e| {
+                        section_error(format!("cannot read {}={:?}: {}", var, value, e))
+                    })?;
+                    values.insert(key.to_ascii_lowercase(), content);
+                }
+                None => {
+                    values.insert(key.to_ascii_lowercase(), value.clone());
+                }
+            }
+        }
+
+        if let Some(overrides) = self.overrides.get(name) {
+            values.extend(overrides.clone());
+        }
+
+        envy::from_iter(values).map_err(|e| section_error(e.to_string()))
+    }
+
+    /// Reads a secret file, or returns its content from an earlier read.
+    fn secret_file(&self, path: &Path) -> std::io::Result<String> {
+        let mut files = self.secret_files.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(content) = files.get(path) {
+            return Ok(content.clone());
+        }
+        let content = read_secret_file(path)?;
+        files.insert(path.to_path_buf(), content.clone());
+        Ok(content)
+    }
+}
@@ -0,0 +1,355 @@
+//! Per-setting rules and cross-field validation for [`AppConfig`](super::AppConfig).
+
+use super::{ConfigIssue, Source};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A framework setting: its key, environment variable and validation rule.
+pub(crate) struct Setting {
+    /// Field name in [`AppConfig`](super::AppConfig) and key in TOML files.
+    pub(crate) key: &'static str,
+    /// Environment variable the setting is read from.
+    pub(crate) var: &'static str,
+    /// Rule applied to the raw value.
+    pub(crate) rule: Rule,
+    /// Whether the value must be redacted in error messages.
+    pub(crate) secret: bool,
+}
+
+/// Validation rule for a raw setting value.
+pub(crate) enum Rule {
+    /// Any non-empty string.
+    Text,
+    /// A URL path starting with `/`.
+    Path,
+    /// A database URL for a backend that was compiled in.
+    #[cfg(feature = "database")]
+    DatabaseUrl,
+    /// A comma-separated list of [`Rule::DatabaseUrl`]s, possibly empty.
+    #[cfg(feature = "database")]
+    DatabaseUrls,
+    /// `true` or `false`.
+    Bool,
+    /// A comma-separated list of origins (`https://example.com`) or `*`,
+    /// possibly empty.
+    Origins,
+    /// A comma-separated list of HTTP methods or header names, or `*`.
+    Tokens,
+    /// A comma-separated list of IP addresses or CIDR ranges, possibly
+    /// empty.
+    IpNets,
+    /// `swagger` (with the `swagger-ui` feature), `redoc` or `off`.
+    DocsUi,
+    /// Exactly one of the listed values.
+    OneOf(&'static [&'static str]),
+    /// An integer in `min..=max`.
+    Int { min: u64, max: u64 },
+}
+
+impl Rule {
+    fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            Rule::Text if value.trim().is_empty() => Err("must not be empty".to_string()),
+            Rule::Text => Ok(()),
+            Rule::Path if !value.starts_with('/') => {
+                Err("expected a path starting with '/'".to_string())
+            }
+            Rule::Path => Ok(()),
+            #[cfg(feature = "database")]
+            Rule::DatabaseUrl => match crate::db::Backend::from_url(value) {
+                Some(backend) if backend.is_enabled() => Ok(()),
+                Some(backend) => Err(format!(
+                    "{} requires the `{}` feature of sword-ai",
+                    backend,
+                    backend.feature()
+                )),
+                None => {
+                    Err("expected a postgres://, mysql:// or sqlite: connection URL".to_string())
+                }
+            },
+            #[cfg(feature = "database")]
+            Rule::DatabaseUrls => split_list(value).enumerate().try_for_each(|(i, url)| {
+                Rule::DatabaseUrl
+                    .check(url)
+                    .map_err(|e| format!("entry {}: {}", i + 1, e))
+            }),
+            Rule::Bool => match value.trim() {
+                "true" | "false" => Ok(()),
+                _ => Err("expected true or false".to_string()),
+            },
+            Rule::Origins => split_list(value).try_for_each(|origin| match origin {
+                "*" => Ok(()),
+                origin if is_origin(origin) => Ok(()),
+                origin => Err(format!(
+                    "'{}' is not an origin like https://example.com or *",
+                    origin
+                )),
+            }),
+            Rule::Tokens => split_list(value).try_for_each(|token| {
+                if token == "*" || axum::http::HeaderName::from_bytes(token.as_bytes()).is_ok() {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not a valid method or header name", token))
+                }
+            }),
+            Rule::IpNets => split_list(value).try_for_each(|net| {
+                crate::rate_limit::IpNet::parse(net)
+                    .map(|_| ())
+                    .ok_or_else(|| {
+                        format!(
+                            "'{}' is not an IP address or CIDR range like 10.0.0.0/8",
+                            net
+                        )
+                    })
+            }),
+            Rule::DocsUi => match value {
+                "redoc" | "off" => Ok(()),
+                "swagger" if cfg!(feature = "swagger-ui") => Ok(()),
+                "swagger" => {
+                    Err("swagger requires the `swagger-ui` feature of sword-ai".to_string())
+                }
+                _ => Err("expected one of: swagger, redoc, off".to_string()),
+            },
+            Rule::OneOf(values) => {
+                if values.contains(&value) {
+                    Ok(())
+                } else {
+                    Err(format!("expected one of: {}", values.join(", ")))
+                }
+            }
+            Rule::Int { min, max } => match value.trim().parse::<u64>() {
+                Ok(n) if (*min..=*max).contains(&n) => Ok(()),
+                _ => Err(format!("expected an integer between {} and {}", min, max)),
+            },
+        }
+    }
+}
+
+/// Raw values merged from every layer, with the source of each.
+pub(crate) struct RawValues<'a> {
+    pub(crate) values: &'a BTreeMap<String, String>,
+    pub(crate) sources: &'a BTreeMap<String, Source>,
+}
+
+impl RawValues<'_> {
+    fn issue(&self, setting: &Setting, message: impl Into<String>) -> ConfigIssue {
+        let value = self.values.get(setting.key).map(|value| {
+            if setting.secret {
+                "<redacted>".to_string()
+            } else {
+                value.clone()
+            }
+        });
+        ConfigIssue {
+            var: setting.var.to_string(),
+            value,
+            source: self
+                .sources
+                .get(setting.key)
+                .cloned()
+                .unwrap_or(Source::Default),
+            message: message.into(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "database"), allow(dead_code))]
+    fn int(&self, key: &str) -> Option<u64> {
+        self.values.get(key).and_then(|v| v.trim().parse().ok())
+    }
+}
+
+/// Checks every setting and returns all problems found.
+pub(crate) fn validate(raw: &RawValues<'_>) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut invalid = BTreeSet::new();
+
+    for setting in super::settings() {
+        let message = match raw.values.get(setting.key) {
+            Some(value) => setting.rule.check(value).err(),
+            None if is_required(setting.key) => Some("must be set".to_string()),
+            None => None,
+        };
+        if let Some(message) = message {
+            issues.push(raw.issue(setting, message));
+            invalid.insert(setting.key);
+        }
+    }
+
+    cross_field(raw, &invalid, &mut issues);
+
+    issues
+}
+
+fn is_required(key: &str) -> bool {
+    cfg!(feature = "database") && key == "database_url"
+}
+
+/// Rules that relate several settings. Each runs only when the settings
+/// it compares are individually valid, i.e. not in `invalid`.
+fn cross_field(raw: &RawValues<'_>, invalid: &BTreeSet<&str>, issues: &mut Vec<ConfigIssue>) {
+    let defaults = super::AppConfig::default();
+    let valid = |keys: &[&str]| keys.iter().all(|key| !invalid.contains(key));
+    let setting = |key: &str| {
+        super::settings()
+            .find(|s| s.key == key)
+            .expect("cross-field rule refers to a known setting")
+    };
+
+    #[cfg(feature = "database")]
+    {
+        let pool = crate::db::PoolDefaults::for_url(
+            raw.values.get("database_url").map_or("", String::as_str),
+        );
+        let min = raw.int("db_min_connections");
+        let max = raw
+            .int("db_max_connections")
+            .unwrap_or(pool.max_connections.into());
+        if valid(&["database_url", "db_min_connections", "db_max_connections"])
+            && min.is_some_and(|min| min > max)
+        {
+            issues.push(raw.issue(
+                setting("db_min_connections"),
+                format!("must not exceed DB_MAX_CONNECTIONS ({})", max),
+            ));
+        }
+
+        let min = raw.int("db_replica_min_connections");
+        let max = raw
+            .int("db_replica_max_connections")
+            .or(raw.int("db_max_connections"))
+            .unwrap_or(pool.max_connections.into());
+        if valid(&[
+            "database_url",
+            "db_replica_min_connections",
+            "db_replica_max_connections",
+            "db_max_connections",
+        ]) && min.is_some_and(|min| min > max)
+        {
+            issues.push(raw.issue(
+                setting("db_replica_min_connections"),
+                format!("must not exceed DB_REPLICA_MAX_CONNECTIONS ({})", max),
+            ));
+        }
+
+        let initial = raw
+            .int("db_connect_initial_delay_ms")
+            .unwrap_or(defaults.db_connect_initial_delay_ms);
+        let max = raw
+            .int("db_connect_max_delay_ms")
+            .unwrap_or(defaults.db_connect_max_delay_ms);
+        if valid(&["db_connect_initial_delay_ms", "db_connect_max_delay_ms"]) && initial > max {
+            issues.push(raw.issue(
+                setting("db_connect_initial_delay_ms"),
+                format!("must not exceed DB_CONNECT_MAX_DELAY_MS ({})", max),
+            ));
+        }
+
+        let primary = raw
+            .values
+            .get("database_url")
+            .and_then(|url| crate::db::Backend::from_url(url));
+        let replicas = raw.values.get("database_replica_urls");
+        if valid(&["database_url", "database_replica_urls"])
+            && replicas.is_some_and(|urls| {
+                split_list(urls).any(|url| crate::db::Backend::from_url(url) != primary)
+            })
+        {
+            issues.push(raw.issue(
+                setting("database_replica_urls"),
+                "replicas must use the same backend as DATABASE_URL",
+            ));
+        }
+    }
+
+    let liveness = raw
+        .values
+        .get("health_liveness_path")
+        .unwrap_or(&defaults.health_liveness_path);
+    let readiness = raw
+        .values
+        .get("health_readiness_path")
+        .unwrap_or(&defaults.health_readiness_path);
+    if valid(&["health_liveness_path", "health_readiness_path"]) && liveness == readiness {
+        issues.push(raw.issue(
+            setting("health_readiness_path"),
+            "must differ from HEALTH_LIVENESS_PATH",
+        ));
+    }
+    let metrics = raw
+        .values
+        .get("metrics_path")
+        .unwrap_or(&defaults.metrics_path);
+    if valid(&[
+        "metrics_path",
+        "health_liveness_path",
+        "health_readiness_path",
+    ]) && (metrics == liveness || metrics == readiness)
+    {
+        issues.push(raw.issue(
+            setting("metrics_path"),
+            "must differ from the health endpoint paths",
+        ));
+    }
+    let openapi = raw
+        .values
+        .get("openapi_path")
+        .unwrap_or(&defaults.openapi_path);
+    let docs = raw
+        .values
+        .get("openapi_docs_path")
+        .unwrap_or(&defaults.openapi_docs_path);
+    if valid(&["openapi_path", "openapi_docs_path"]) && docs == openapi {
+        issues.push(raw.issue(
+            setting("openapi_docs_path"),
+            "must differ from OPENAPI_PATH",
+        ));
+    }
+    // Browsers refuse wildcards in credentialed CORS responses.
+    if valid(&["cors_allow_credentials"])
+        && raw.values.get("cors_allow_credentials").map(|v| v.trim()) == Some("true")
+    {
+        for key in [
+            "cors_allowed_origins",
+            "cors_allowed_methods",
+            "cors_allowed_headers",
+        ] {
+            if valid(&[key])
+                && raw
+                    .values
+                    .get(key)
+                    .is_some_and(|value| split_list(value).any(|entry| entry == "*"))
+            {
+                issues.push(raw.issue(
+                    setting(key),
+                    "must not contain * when CORS_ALLOW_CREDENTIALS is true",
+                ));
+            }
+        }
+    }
+
+    let default_size = raw
+        .int("pagination_default_size")
+        .unwrap_or(defaults.pagination_default_size);
+    let max_size = raw
+        .int("pagination_max_size")
+        .unwrap_or(defaults.pagination_max_size);
+    if valid(&["pagination_default_size", "pagination_max_size"]) && default_size > max_size {
+        issues.push(raw.issue(
+            setting("pagination_default_size"),
+            format!("must not exceed PAGINATION_MAX_SIZE ({})", max_size),
+        ));
+    }
+}
+
+/// Whether `value` is a `scheme://host[:port]` origin, without a path.
+fn is_origin(value: &str) -> bool {
+    value.split_once("://").is_some_and(|(scheme, host)| {
+        matches!(scheme, "http" | "https")
+            && !host.is_empty()
+            && !host.contains(['/', '?', '#', ' '])
+    })
+}
+
+/// Splits a comma-separated list, trimming entries and skipping empty ones.
+pub(crate) fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
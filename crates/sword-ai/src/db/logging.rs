@@ -0,0 +1,163 @@
+//! Statement logging and slow-query detection.
+
+use crate::config::AppConfig;
+use crate::metrics::{Exemplar, Registry};
+use sea_orm::{ConnectOptions, DatabaseConnection};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::log::LevelFilter;
+
+/// Name of the slow-query counter in the metrics registry.
+const SLOW_QUERIES: &str = "sword_db_slow_queries";
+
+/// Level executed statements are logged at (`DB_LOG_LEVEL`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// Statements are not logged (`off`).
+    Off,
+    /// `error`
+    Error,
+    /// `warn`
+    Warn,
+    /// `info`
+    Info,
+    /// `debug`
+    #[default]
+    Debug,
+    /// `trace`
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Applies `DB_LOG_STATEMENTS` and `DB_LOG_LEVEL` to `opt`.
+///
+/// SQLx's own slow-statement logging is turned off: slow queries are
+/// reported by [`watch_slow_queries`] instead, with redacted SQL.
+pub(crate) fn configure(opt: &mut ConnectOptions, config: &AppConfig) {
+    let level = LevelFilter::from(config.db_log_level);
+    opt.sqlx_logging(config.db_log_statements && level != LevelFilter::Off)
+        .sqlx_logging_level(level)
+        .sqlx_slow_statements_logging_settings(LevelFilter::Off, Duration::ZERO);
+}
+
+/// Logs queries slower than `DB_SLOW_QUERY_THRESHOLD_MS` at `WARN` and
+/// counts them in `sword_db_slow_queries_total`. A threshold of `0`
+/// disables detection.
+///
+/// SeaORM does not time `execute_unprepared`, so such statements are never
+/// reported.
+pub(crate) fn watch_slow_queries(conn: &mut DatabaseConnection, config: &AppConfig) {
+    if config.db_slow_query_threshold_ms == 0 {
+        return;
+    }
+    let threshold = Duration::from_millis(config.db_slow_query_threshold_ms);
+    let counter = Registry::global().counter(
+        SLOW_QUERIES,
+        "Queries slower than DB_SLOW_QUERY_THRESHOLD_MS.",
+    );
+    conn.set_metric_callback(move |info| {
+        if info.elapsed < threshold {
+            return;
+        }
+        tracing::warn!(
+            duration_ms = info.elapsed.as_millis() as u64,
+            failed = info.failed,
+            "Slow query: {}",
+            redact_sql(&info.statement.sql)
+        );
+        match crate::tracing::current_trace_id() {
+            Some(trace_id) => counter.inc_with_exemplar(Exemplar {
+                trace_id,
+                value: info.elapsed.as_secs_f64(),
+            }),
+            None => counter.inc(),
+        }
+    });
+}
+
+/// Replaces string and numeric literals in `sql` with `?`, so that values
+/// inlined into a statement do not reach the logs. Bind placeholders
+/// (`$1`, `?`) and identifiers are kept.
+pub(crate) fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut prev: Option<char> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // Skip to the closing quote; `''` is an escaped quote.
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.next_if_eq(&'\'').is_none() {
+                        break;
+                    }
+                }
+                out.push('?');
+            }
+            '0'..='9' if !prev.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '$') => {
+                while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+                out.push('?');
+            }
+            c => out.push(c),
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_sql() {
+        assert_eq!(
+            redact_sql("SELECT * FROM users WHERE email = 'a@b.c' AND id = $1 LIMIT 10"),
+            "SELECT * FROM users WHERE email = ? AND id = $1 LIMIT ?"
+        );
+        assert_eq!(
+            redact_sql("INSERT INTO t2 (name, score) VALUES ('O''Brien', 3.5)"),
+            "INSERT INTO t2 (name, score) VALUES (?, ?)"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_slow_queries_are_counted() {
+        use sea_orm::{ConnectionTrait, Statement};
+
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            db_slow_query_threshold_ms: 1,
+            ..Default::default()
+        };
+        let conn = super::super::connect_db(&config).await.unwrap();
+        let counter = Registry::global().counter(SLOW_QUERIES, "");
+        let before = counter.get();
+
+        let slow =
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 300000) \
+                    SELECT COUNT(*) FROM n";
+        crate::tracing::with_trace_id(
+            "trace-1".to_string(),
+            conn.query_one(Statement::from_string(conn.get_database_backend(), slow)),
+        )
+        .await
+        .unwrap();
+
+        assert!(counter.get() > before);
+        assert_eq!(counter.exemplar().unwrap().trace_id, "trace-1");
+    }
+}
@@ -0,0 +1,353 @@
+//! Startup migrations and the `migrate` command.
+
+use crate::config::AppConfig;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, Statement,
+    TransactionTrait,
+};
+use sea_orm_migration::MigratorTrait;
+use serde::Deserialize;
+use std::fmt;
+use std::io::Write;
+
+/// Key of the Postgres advisory lock held while migrating.
+const LOCK_KEY: i64 = 0x7377_6f72_645f_6d67;
+
+/// What to do with pending migrations at boot (`DB_MIGRATIONS`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationMode {
+    /// Apply them (`apply`).
+    #[default]
+    Apply,
+    /// Refuse to start if there are any (`check`).
+    Check,
+}
+
+/// Applies or checks the pending migrations of `M`, as set by
+/// `DB_MIGRATIONS`.
+///
+/// On Postgres, migrations are applied in a single transaction holding an
+/// advisory lock, so that only one instance migrates at a time. Instances
+/// that find the lock taken wait up to `DB_MIGRATION_LOCK_TIMEOUT` seconds
+/// for it, then apply whatever is still pending (usually nothing); with a
+/// timeout of `0` they skip migrations instead of waiting.
+///
+/// # Errors
+///
+/// Returns an error if a migration fails, the lock cannot be acquired in
+/// time, or `DB_MIGRATIONS=check` and migrations are pending.
+pub(crate) async fn run_migrations<M: MigratorTrait>(
+    db: &DatabaseConnection,
+    config: &AppConfig,
+) -> anyhow::Result<()> {
+    if config.db_migrations == MigrationMode::Check {
+        return check::<M>(db).await;
+    }
+
+    if db.get_database_backend() != DbBackend::Postgres {
+        tracing::info!("Running database migrations...");
+        M::up(db, None).await?;
+        tracing::info!("Migrations completed successfully");
+        return Ok(());
+    }
+
+    let txn = db.begin().await?;
+    if !lock(&txn, config.db_migration_lock_timeout).await? {
+        tracing::warn!("Another instance is running migrations, skipping");
+        txn.rollback().await?;
+        return Ok(());
+    }
+    let pending = M::get_pending_migrations(&txn).await?.len();
+    if pending == 0 {
+        tracing::info!("No pending migrations");
+    } else {
+        tracing::info!("Running {} database migration(s)...", pending);
+        M::up(&txn, None).await?;
+    }
+    txn.commit().await?;
+    if pending > 0 {
+        tracing::info!("Migrations completed successfully");
+    }
+    Ok(())
+}
+
+/// Fails if `M` has migrations that have not been applied.
+async fn check<M: MigratorTrait>(db: &DatabaseConnection) -> anyhow::Result<()> {
+    let pending = M::get_pending_migrations(db).await?;
+    if pending.is_empty() {
+        tracing::info!("Database schema is up to date");
+        return Ok(());
+    }
+    let names: Vec<&str> = pending.iter().map(|m| m.name()).collect();
+    anyhow::bail!(
+        "{} pending migration(s) and DB_MIGRATIONS=check: {}",
+        names.len(),
+        names.join(", ")
+    )
+}
+
+/// A migration command run against an application's migrator, as invoked
+/// by `sword migrate`.
+///
+/// Generated projects dispatch it from `main` before starting the server,
+/// so that `cargo run -- migrate <command>` works with the project's own
+/// `Migrator` and `DATABASE_URL`:
+///
+/// ```rust,ignore
+/// use sword_ai::db::MigrateCommand;
+///
+/// if let Some(command) = MigrateCommand::from_args(std::env::args())? {
+///     return command.run::<Migrator>(std::io::stdout()).await;
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// Applies pending migrations: all of them, or the given number.
+    Up(Option<u32>),
+    /// Rolls back the given number of applied migrations (default: 1).
+    Down(u32),
+    /// Lists every migration and whether it has been applied.
+    Status,
+    /// Drops every table, then applies all migrations.
+    Fresh,
+    /// Rolls back every applied migration.
+    Reset,
+}
+
+impl MigrateCommand {
+    /// Parses `<program> migrate <command> [n]`.
+    ///
+    /// Returns `Ok(None)` if the first argument is not `migrate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown command or an invalid step count.
+    pub fn from_args<I>(args: I) -> anyhow::Result<Option<Self>>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().skip(1).map(Into::into).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let (command, rest) = match args.as_slice() {
+            ["migrate", command, rest @ ..] => (*command, rest),
+            ["migrate"] => anyhow::bail!("usage: migrate <up|down|status|fresh|reset> [n]"),
+            _ => return Ok(None),
+        };
+        let steps = match rest {
+            [] => None,
+            [n] => Some(n.parse::<u32>().ok().filter(|&n| n > 0).ok_or_else(|| {
+                anyhow::anyhow!("expected a positive number of migrations, got '{}'", n)
+            })?),
+            _ => anyhow::bail!("unexpected arguments after '{}'", rest[0]),
+        };
+        let command = match command {
+            "up" => Self::Up(steps),
+            "down" => Self::Down(steps.unwrap_or(1)),
+            "status" | "fresh" | "reset" if steps.is_some() => {
+                anyhow::bail!("'{}' does not take a number of migrations", command)
+            }
+            "status" => Self::Status,
+            "fresh" => Self::Fresh,
+            "reset" => Self::Reset,
+            other => anyhow::bail!(
+                "unknown migrate command '{}': expected up, down, status, fresh or reset",
+                other
+            ),
+        };
+        Ok(Some(command))
+    }
+
+    /// Loads the configuration, connects to `DATABASE_URL` and runs the
+    /// command, writing its output to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid, the database is
+    /// unreachable, a migration fails or `out` cannot be written.
+    pub async fn run<M: MigratorTrait>(self, mut out: impl Write) -> anyhow::Result<()> {
+        let config = AppConfig::from_env()?;
+        let db = super::connect_db(&config).await?;
+        let result = self.execute::<M>(&db, &mut out).await;
+        db.close().await?;
+        result
+    }
+
+    /// Runs the command on `db`. `status` writes one line per migration to
+    /// `out`; the other commands log their progress instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a migration fails or `out` cannot be written.
+    pub async fn execute<M: MigratorTrait>(
+        self,
+        db: &DatabaseConnection,
+        out: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Up(steps) => M::up(db, steps).await?,
+            Self::Down(steps) => M::down(db, Some(steps)).await?,
+            Self::Fresh => M::fresh(db).await?,
+            Self::Reset => M::reset(db).await?,
+            Self::Status => {
+                M::install(db).await?;
+                for migration in M::get_migration_with_status(db).await? {
+                    writeln!(
+                        out,
+                        "{:<8} {}",
+                        migration.status().to_string(),
+                        migration.name()
+                    )?;
+                }
+                return Ok(());
+            }
+        }
+        tracing::info!("migrate {} completed", self);
+        Ok(())
+    }
+}
+
+impl fmt::Display for MigrateCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Up(None) => f.write_str("up"),
+            Self::Up(Some(n)) => write!(f, "up {}", n),
+            Self::Down(n) => write!(f, "down {}", n),
+            Self::Status => f.write_str("status"),
+            Self::Fresh => f.write_str("fresh"),
+            Self::Reset => f.write_str("reset"),
+        }
+    }
+}
+
+/// Takes the migration lock for the rest of `txn`, waiting up to `timeout`
+/// seconds. With a timeout of `0`, returns `false` at once if the lock is
+/// held elsewhere.
+async fn lock(txn: &DatabaseTransaction, timeout: u64) -> anyhow::Result<bool> {
+    let backend = txn.get_database_backend();
+    if timeout == 0 {
+        let row = txn
+            .query_one(Statement::from_sql_and_values(
+                backend,
+                "SELECT pg_try_advisory_xact_lock($1) AS locked",
+                [LOCK_KEY.into()],
+            ))
+            .await?;
+        return Ok(match row {
+            Some(row) => row.try_get("", "locked")?,
+            None => false,
+        });
+    }
+
+    txn.execute_unprepared(&format!("SET LOCAL lock_timeout = '{}s'", timeout))
+        .await?;
+    tracing::debug!("Waiting for the migration lock");
+    txn.execute(Statement::from_sql_and_values(
+        backend,
+        "SELECT pg_advisory_xact_lock($1)",
+        [LOCK_KEY.into()],
+    ))
+    .await
+    .map_err(|e| {
+        anyhow::Error::new(e).context(format!(
+            "failed to acquire the migration lock within {}s",
+            timeout
+        ))
+    })?;
+    // Migrations waiting on their own locks are not bound by our timeout.
+    txn.execute_unprepared("SET LOCAL lock_timeout = 0").await?;
+    Ok(true)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use sea_orm_migration::prelude::*;
+
+    struct CreateNotes;
+
+    impl MigrationName for CreateNotes {
+        fn name(&self) -> &str {
+            "m20240101_000001_create_notes"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for CreateNotes {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .get_connection()
+                .execute_unprepared("CREATE TABLE notes (id INTEGER PRIMARY KEY)")
+                .await
+                .map(|_| ())
+        }
+    }
+
+    struct Migrator;
+
+    impl MigratorTrait for Migrator {
+        fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+            vec![Box::new(CreateNotes)]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_mode_refuses_pending_migrations() {
+        let mut config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            db_migrations: MigrationMode::Check,
+            ..Default::default()
+        };
+        let db = super::super::connect_db(&config).await.unwrap();
+
+        let err = run_migrations::<Migrator>(&db, &config).await.unwrap_err();
+        assert!(err.to_string().contains("m20240101_000001_create_notes"));
+
+        config.db_migrations = MigrationMode::Apply;
+        run_migrations::<Migrator>(&db, &config).await.unwrap();
+        config.db_migrations = MigrationMode::Check;
+        run_migrations::<Migrator>(&db, &config).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_is_written_to_out() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = super::super::connect_db(&config).await.unwrap();
+
+        let mut out = Vec::new();
+        MigrateCommand::Status
+            .execute::<Migrator>(&db, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Pending  m20240101_000001_create_notes\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_migrate_command() {
+        let parse = |args: &[&str]| MigrateCommand::from_args(args.iter().copied());
+
+        assert_eq!(parse(&["app"]).unwrap(), None);
+        assert_eq!(
+            parse(&["app", "migrate", "up"]).unwrap(),
+            Some(MigrateCommand::Up(None))
+        );
+        assert_eq!(
+            parse(&["app", "migrate", "down"]).unwrap(),
+            Some(MigrateCommand::Down(1))
+        );
+        assert_eq!(
+            parse(&["app", "migrate", "up", "2"]).unwrap(),
+            Some(MigrateCommand::Up(Some(2)))
+        );
+        assert!(parse(&["app", "migrate", "down", "0"]).is_err());
+        assert!(parse(&["app", "migrate", "status", "1"]).is_err());
+        assert!(parse(&["app", "migrate", "redo"]).is_err());
+    }
+}
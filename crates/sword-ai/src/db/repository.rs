@@ -0,0 +1,499 @@
+//! Generic CRUD repositories over SeaORM entities.
+
+use super::Executor;
+use crate::pagination::{Cursor, Page, Pagination};
+use axum::async_trait;
+use sea_orm::sea_query::{
+    FromValueTuple, IntoCondition, IntoIden, IntoValueTuple, Value, ValueTuple,
+};
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, Condition, DbErr, EntityTrait, Identity,
+    IntoActiveModel, Iterable, ModelTrait, Order, PaginatorTrait, PrimaryKeyToColumn,
+    PrimaryKeyTrait, QueryFilter, QueryOrder, QuerySelect, Select,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::type_name;
+
+/// Primary key value of the entity `E`, e.g. `i64` or `(i32, String)`.
+pub type PrimaryKey<E> = <<E as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType;
+
+/// Create, find, list, update and delete for the SeaORM entity `E`,
+/// returning the domain type `D`.
+///
+/// Every method has a default implementation, so a repository only
+/// implements the trait and adds its own queries:
+///
+/// - models are turned into `D` with `TryFrom<E::Model>` (a `From` impl is
+///   enough);
+/// - inputs are anything that converts into `E::ActiveModel`: an active
+///   model, or a domain type with a `From` impl. Columns left `NotSet` get
+///   their database default on `create` and are left untouched on
+///   `update`.
+///
+/// ```rust,ignore
+/// use sword_ai::db::{CrudRepository, ListQuery};
+///
+/// impl From<users::Model> for User { /* ... */ }
+/// impl From<NewUser> for users::ActiveModel { /* ... */ }
+///
+/// pub struct UserRepository;
+///
+/// impl CrudRepository<users::Entity, User> for UserRepository {}
+///
+/// let user = repository.create(&*tx, new_user).await?;
+/// let page = repository
+///     .list(db, ListQuery::new().filter(users::Column::Active.eq(true)).page(2, 20))
+///     .await?;
+/// ```
+///
+/// Methods take the [`Executor`] to run on, so they work with
+/// `ctx.db.reader()`, `ctx.db.writer()` or a request transaction (`&*tx`).
+#[async_trait]
+pub trait CrudRepository<E, D>: Send + Sync
+where
+    E: EntityTrait,
+    E::Model: IntoActiveModel<E::ActiveModel> + Sync,
+    E::ActiveModel: ActiveModelBehavior + Send,
+    D: TryFrom<E::Model> + Send,
+    D::Error: std::error::Error + Send + Sync + 'static,
+{
+    /// Inserts a row and returns it as stored.
+    async fn create<C, I>(&self, db: &C, input: I) -> Result<D, DbErr>
+    where
+        C: Executor,
+        I: Into<E::ActiveModel> + Send,
+    {
+        let model = input.into().insert(db).await?;
+        to_domain(model)
+    }
+
+    /// Finds a row by primary key.
+    async fn find<C: Executor>(&self, db: &C, id: PrimaryKey<E>) -> Result<Option<D>, DbErr> {
+        E::find_by_id(id).one(db).await?.map(to_domain).transpose()
+    }
+
+    /// Lists the rows matching `query`, by primary key unless it sets an
+    /// order.
+    async fn list<C: Executor>(&self, db: &C, query: ListQuery<E>) -> Result<Vec<D>, DbErr> {
+        let (limit, offset) = (query.limit, query.offset);
+        query
+            .ordered()
+            .limit(limit)
+            .offset(offset)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(to_domain)
+            .collect()
+    }
+
+    /// Returns the page of rows matching `query` that `pagination` asks
+    /// for, with the number of matching rows as its total.
+    ///
+    /// Rows are ordered as in [`list`](Self::list); the page replaces any
+    /// limit and offset set on `query`.
+    async fn paginate<C: Executor>(
+        &self,
+        db: &C,
+        query: ListQuery<E>,
+        pagination: &Pagination,
+    ) -> Result<Page<D>, DbErr> {
+        let paginator = query.ordered().paginate(db, pagination.per_page);
+        let total = paginator.num_items().await?;
+        let models = paginator
+            .fetch_page(pagination.page.saturating_sub(1))
+            .await?;
+        let items = models
+            .into_iter()
+            .map(to_domain)
+            .collect::<Result<_, _>>()?;
+        Ok(pagination.page_of(items, total))
+    }
+
+    /// Returns the page of rows matching `query` next to the cursor of
+    /// `pagination`, or the first page without one.
+    ///
+    /// Keyset pages are always in primary key order: the order, limit and
+    /// offset of `query` are not used.
+    async fn paginate_keyset<C: Executor>(
+        &self,
+        db: &C,
+        query: ListQuery<E>,
+        pagination: &Pagination,
+    ) -> Result<Page<D>, DbErr>
+    where
+        PrimaryKey<E>: Serialize + DeserializeOwned,
+    {
+        let mut select = E::find()
+            .filter(query.condition)
+            .cursor_by(primary_key_identity::<E>());
+        // One extra row tells whether there is another page that way.
+        let fetch = pagination.per_page.saturating_add(1);
+        let backwards = match pagination.cursor::<PrimaryKey<E>>() {
+            None => {
+                select.first(fetch);
+                false
+            }
+            Some(Cursor::After(key)) => {
+                select.after(key).first(fetch);
+                false
+            }
+            Some(Cursor::Before(key)) => {
+                select.before(key).last(fetch);
+                true
+            }
+        };
+        let mut models = select.all(db).await?;
+        let has_more = models.len() as u64 > pagination.per_page;
+        if has_more && backwards {
+            models.remove(0);
+        } else if has_more {
+            models.pop();
+        }
+
+        let page = pagination.keyset_page(models, has_more, primary_key_of::<E>);
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(to_domain)
+                .collect::<Result<_, _>>()?,
+            total: page.total,
+            next: page.next,
+            prev: page.prev,
+        })
+    }
+
+    /// Counts the rows matching `filter`, e.g. to report the total of a
+    /// paginated list.
+    async fn count<C, F>(&self, db: &C, filter: F) -> Result<u64, DbErr>
+    where
+        C: Executor,
+        F: IntoCondition + Send,
+    {
+        E::find().filter(filter).count(db).await
+    }
+
+    /// Updates the columns set in `input` on the row with primary key `id`.
+    ///
+    /// Returns `Ok(None)` if there is no such row.
+    async fn update<C, I>(&self, db: &C, id: PrimaryKey<E>, input: I) -> Result<Option<D>, DbErr>
+    where
+        C: Executor,
+        I: Into<E::ActiveModel> + Send,
+    {
+        let mut active: E::ActiveModel = input.into();
+        for (key, value) in E::PrimaryKey::iter().zip(id.into_value_tuple()) {
+            active.set(key.into_column(), value);
+        }
+        match active.update(db).await {
+            Ok(model) => to_domain(model).map(Some),
+            Err(DbErr::RecordNotUpdated | DbErr::RecordNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes the row with primary key `id`, returning whether it existed.
+    async fn delete<C: Executor>(&self, db: &C, id: PrimaryKey<E>) -> Result<bool, DbErr> {
+        let result = E::delete_by_id(id).exec(db).await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+/// Filter, order and page of a [`CrudRepository::list`] call.
+///
+/// ```rust,ignore
+/// ListQuery::<posts::Entity>::new()
+///     .filter(posts::Column::Published.eq(true))
+///     .order_by(posts::Column::CreatedAt, Order::Desc)
+///     .page(1, 20)
+/// ```
+#[derive(Debug, Clone)]
+pub struct ListQuery<E: EntityTrait> {
+    condition: Condition,
+    order: Vec<(E::Column, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl<E: EntityTrait> ListQuery<E> {
+    /// All rows, ordered by primary key.
+    pub fn new() -> Self {
+        Self {
+            condition: Condition::all(),
+            order: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Keeps the rows matching `filter`, in addition to earlier filters.
+    pub fn filter(mut self, filter: impl IntoCondition) -> Self {
+        self.condition = self.condition.add(filter.into_condition());
+        self
+    }
+
+    /// Orders by `column`, after earlier orderings.
+    pub fn order_by(mut self, column: E::Column, order: Order) -> Self {
+        self.order.push((column, order));
+        self
+    }
+
+    /// Returns at most `limit` rows.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `offset` rows.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns page `page` (starting at 1) of `per_page` rows.
+    pub fn page(self, page: u64, per_page: u64) -> Self {
+        self.limit(per_page)
+            .offset(page.saturating_sub(1).saturating_mul(per_page))
+    }
+
+    /// The combined filter, e.g. for [`CrudRepository::count`].
+    pub fn condition(&self) -> Condition {
+        self.condition.clone()
+    }
+
+    /// The filtered rows in query order, by primary key by default.
+    fn ordered(self) -> Select<E> {
+        let mut select = E::find().filter(self.condition);
+        if self.order.is_empty() {
+            for key in E::PrimaryKey::iter() {
+                select = select.order_by_asc(key.into_column());
+            }
+        }
+        for (column, order) in self.order {
+            select = select.order_by(column, order);
+        }
+        select
+    }
+}
+
+impl<E: EntityTrait> Default for ListQuery<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The primary key columns of `E`, as SeaORM cursors expect them.
+fn primary_key_identity<E: EntityTrait>() -> Identity {
+    let mut columns: Vec<_> = E::PrimaryKey::iter()
+        .map(|key| key.into_column().into_iden())
+        .collect();
+    match columns.len() {
+        1 => Identity::Unary(columns.remove(0)),
+        2 => Identity::Binary(columns.remove(0), columns.remove(0)),
+        3 => Identity::Ternary(columns.remove(0), columns.remove(0), columns.remove(0)),
+        _ => Identity::Many(columns),
+    }
+}
+
+fn primary_key_of<E: EntityTrait>(model: &E::Model) -> PrimaryKey<E> {
+    let mut values: Vec<Value> = E::PrimaryKey::iter()
+        .map(|key| model.get(key.into_column()))
+        .collect();
+    let tuple = match values.len() {
+        1 => ValueTuple::One(values.remove(0)),
+        2 => ValueTuple::Two(values.remove(0), values.remove(0)),
+        3 => ValueTuple::Three(values.remove(0), values.remove(0), values.remove(0)),
+        _ => ValueTuple::Many(values),
+    };
+    PrimaryKey::<E>::from_value_tuple(tuple)
+}
+
+fn to_domain<M, D>(model: M) -> Result<D, DbErr>
+where
+    D: TryFrom<M>,
+    D::Error: std::error::Error + Send + Sync + 'static,
+{
+    D::try_from(model).map_err(|e| DbErr::TryIntoErr {
+        from: type_name::<M>(),
+        into: type_name::<D>(),
+        source: Box::new(e),
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use sea_orm::entity::prelude::*;
+    use sea_orm::{ConnectionTrait, Set};
+
+    mod notes {
+        use sea_orm::entity::prelude::*;
+
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "notes")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub title: String,
+            pub done: bool,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Note {
+        id: i32,
+        title: String,
+    }
+
+    impl From<notes::Model> for Note {
+        fn from(model: notes::Model) -> Self {
+            Self {
+                id: model.id,
+                title: model.title,
+            }
+        }
+    }
+
+    struct NewNote(&'static str);
+
+    impl From<NewNote> for notes::ActiveModel {
+        fn from(input: NewNote) -> Self {
+            Self {
+                title: Set(input.0.to_string()),
+                done: Set(false),
+                ..Default::default()
+            }
+        }
+    }
+
+    struct Notes;
+
+    impl CrudRepository<notes::Entity, Note> for Notes {}
+
+    #[tokio::test]
+    async fn test_crud_repository() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = super::super::connect_db(&config).await.unwrap();
+        db.execute_unprepared(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, done BOOLEAN NOT NULL)",
+        )
+        .await
+        .unwrap();
+
+        for title in ["a", "b", "c"] {
+            Notes.create(&db, NewNote(title)).await.unwrap();
+        }
+        let done = notes::ActiveModel {
+            done: Set(true),
+            ..Default::default()
+        };
+        let updated = Notes.update(&db, 2, done).await.unwrap().unwrap();
+        assert_eq!(updated.title, "b");
+        assert_eq!(Notes.update(&db, 9, NewNote("x")).await.unwrap(), None);
+
+        let open = ListQuery::new().filter(notes::Column::Done.eq(false));
+        assert_eq!(Notes.count(&db, open.condition()).await.unwrap(), 2);
+        let titles: Vec<String> = Notes
+            .list(
+                &db,
+                open.order_by(notes::Column::Id, Order::Desc).page(1, 1),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, ["c"]);
+
+        assert!(Notes.delete(&db, 1).await.unwrap());
+        assert_eq!(Notes.find(&db, 1).await.unwrap(), None);
+        assert_eq!(Notes.list(&db, ListQuery::new()).await.unwrap().len(), 2);
+    }
+
+    async fn pagination(uri: &str) -> Pagination {
+        use axum::extract::FromRequestParts;
+
+        let request = axum::http::Request::builder().uri(uri).body(()).unwrap();
+        let (mut parts, ()) = request.into_parts();
+        Pagination::from_request_parts(&mut parts, &())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = super::super::connect_db(&config).await.unwrap();
+        db.execute_unprepared(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, done BOOLEAN NOT NULL)",
+        )
+        .await
+        .unwrap();
+        for title in ["a", "b", "c", "d", "e"] {
+            Notes.create(&db, NewNote(title)).await.unwrap();
+        }
+        let ids = |page: &Page<Note>| page.items.iter().map(|n| n.id).collect::<Vec<_>>();
+
+        let page = Notes
+            .paginate(
+                &db,
+                ListQuery::new(),
+                &pagination("/notes?page=2&per_page=2").await,
+            )
+            .await
+            .unwrap();
+        assert_eq!((ids(&page), page.total), (vec![3, 4], Some(5)));
+        assert_eq!(page.next.as_deref(), Some("/notes?per_page=2&page=3"));
+
+        let first = Notes
+            .paginate_keyset(
+                &db,
+                ListQuery::new(),
+                &pagination("/notes?per_page=2").await,
+            )
+            .await
+            .unwrap();
+        assert_eq!((ids(&first), first.prev.as_ref()), (vec![1, 2], None));
+        let second = Notes
+            .paginate_keyset(
+                &db,
+                ListQuery::new(),
+                &pagination(first.next.as_ref().unwrap()).await,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&second), [3, 4]);
+        let last = Notes
+            .paginate_keyset(
+                &db,
+                ListQuery::new(),
+                &pagination(second.next.as_ref().unwrap()).await,
+            )
+            .await
+            .unwrap();
+        assert_eq!((ids(&last), last.next.as_ref()), (vec![5], None));
+        let back = Notes
+            .paginate_keyset(
+                &db,
+                ListQuery::new(),
+                &pagination(second.prev.as_ref().unwrap()).await,
+            )
+            .await
+            .unwrap();
+        assert_eq!((ids(&back), back.prev.as_ref()), (vec![1, 2], None));
+    }
+}
@@ -0,0 +1,80 @@
+//! Startup connection retry.
+
+use crate::config::AppConfig;
+use std::time::Duration;
+
+/// Exponential backoff used when connecting to the database at startup.
+///
+/// The delay before attempt `n + 1` is `initial_delay * 2^(n - 1)`, capped
+/// at `max_delay`. With `jitter`, each delay is randomized between half and
+/// all of that value so that many instances restarting together do not
+/// retry in lockstep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of connection attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay after the first failed attempt.
+    pub initial_delay: Duration,
+    /// Upper bound for any single delay.
+    pub max_delay: Duration,
+    /// Whether to randomize delays.
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Builds the policy from the `DB_CONNECT_*` settings.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            max_attempts: config.db_connect_max_attempts,
+            initial_delay: Duration::from_millis(config.db_connect_initial_delay_ms),
+            max_delay: Duration::from_millis(config.db_connect_max_delay_ms),
+            jitter: config.db_connect_jitter,
+        }
+    }
+
+    /// Delay to wait after failed attempt number `attempt` (starting at 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(0.5 + random_fraction() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Returns a random number in `0.0..1.0`.
+fn random_fraction() -> f64 {
+    let mut bytes = [0u8; 8];
+    getrandom::getrandom(&mut bytes).expect("the OS random number generator is available");
+    (u64::from_le_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let mut policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(3),
+            jitter: false,
+        };
+
+        let delays: Vec<u128> = (1..=5).map(|n| policy.delay(n).as_millis()).collect();
+        assert_eq!(delays, [500, 1000, 2000, 3000, 3000]);
+
+        policy.jitter = true;
+        for attempt in 1..=5 {
+            let delay = policy.delay(attempt);
+            assert!(delay <= Duration::from_secs(3));
+            assert!(delay >= Duration::from_millis(250));
+        }
+    }
+}
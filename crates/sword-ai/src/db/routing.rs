@@ -0,0 +1,319 @@
+//! Primary/replica read routing.
+
+use super::{connect_pool, Backend};
+use crate::config::AppConfig;
+use crate::shutdown::Shutdown;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use sea_orm::DatabaseConnection;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+tokio::task_local! {
+    /// Set once the current request has used the writer.
+    static PINNED: Cell<bool>;
+}
+
+/// Database handle that routes writes to the primary and reads to replicas.
+///
+/// Available as [`FrameworkContext::db`](crate::FrameworkContext::db).
+/// Without `DATABASE_REPLICA_URLS`, [`Db::reader`] and [`Db::writer`] both
+/// return the primary.
+///
+/// - **Round-robin**: each call to [`Db::reader`] picks the next healthy
+///   replica.
+/// - **Health-based ejection**: replicas are pinged every
+///   `DB_REPLICA_CHECK_INTERVAL` seconds. A replica that fails is taken out
+///   of rotation until a ping succeeds again. With no healthy replica left,
+///   reads go to the primary.
+/// - **Read-your-writes** (`DB_READ_YOUR_WRITES`, on by default): once a
+///   request has called [`Db::writer`], its later [`Db::reader`] calls
+///   return the primary, so it never reads data older than its own writes.
+///   This is tracked per request task; tasks spawned from a handler start
+///   unpinned.
+///
+/// ```rust,ignore
+/// async fn create_post(State(ctx): State<FrameworkContext>, Json(input): Json<NewPost>) -> ... {
+///     let post = input.into_active_model().insert(ctx.db.writer()).await?;
+///     // Reads from the primary: this request has written.
+///     let count = Post::find().count(ctx.db.reader()).await?;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Db {
+    writer: DatabaseConnection,
+    replicas: Arc<[Replica]>,
+    next: Arc<AtomicUsize>,
+    read_your_writes: bool,
+    ready: Arc<AtomicBool>,
+}
+
+struct Replica {
+    conn: DatabaseConnection,
+    healthy: AtomicBool,
+}
+
+impl fmt::Debug for Db {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Db")
+            .field("replicas", &self.replicas.len())
+            .field("healthy_replicas", &self.healthy_replicas())
+            .field("read_your_writes", &self.read_your_writes)
+            .field("ready", &self.is_ready())
+            .finish()
+    }
+}
+
+impl Db {
+    /// Creates a handle over a primary and its read replicas.
+    pub fn new(writer: DatabaseConnection, replicas: Vec<DatabaseConnection>) -> Self {
+        let replicas = replicas
+            .into_iter()
+            .map(|conn| Replica {
+                conn,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+        Self {
+            writer,
+            replicas,
+            next: Arc::new(AtomicUsize::new(0)),
+            read_your_writes: true,
+            ready: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Enables or disables read-your-writes pinning (default: enabled).
+    pub fn read_your_writes(mut self, enabled: bool) -> Self {
+        self.read_your_writes = enabled;
+        self
+    }
+
+    /// Connects to the primary and every replica in `config`, retrying
+    /// failed attempts as described in [`RetryPolicy`](super::RetryPolicy).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the primary or any replica cannot be reached.
+    pub async fn connect(config: &AppConfig) -> anyhow::Result<Self> {
+        Self::connect_with(config, false).await
+    }
+
+    /// Creates the pools without connecting, for `DB_START_DEGRADED`. The
+    /// handle is not [ready](Db::is_ready) until marked so with `set_ready`.
+    pub(crate) async fn connect_lazy(config: &AppConfig) -> anyhow::Result<Self> {
+        let db = Self::connect_with(config, true).await?;
+        db.set_ready(false);
+        Ok(db)
+    }
+
+    async fn connect_with(config: &AppConfig, lazy: bool) -> anyhow::Result<Self> {
+        let writer = connect_pool(
+            config,
+            config.database_url.expose(),
+            config.db_max_connections,
+            config.db_min_connections,
+            lazy,
+        )
+        .await?;
+
+        let mut replicas = Vec::new();
+        let urls = config
+            .database_replica_urls
+            .expose()
+            .iter()
+            .map(|url| url.trim())
+            .filter(|url| !url.is_empty());
+        for (i, url) in urls.enumerate() {
+            let conn = connect_pool(
+                config,
+                url,
+                config
+                    .db_replica_max_connections
+                    .or(config.db_max_connections),
+                config
+                    .db_replica_min_connections
+                    .or(config.db_min_connections),
+                lazy,
+            )
+            .await
+            .map_err(|e| e.context(format!("failed to connect to replica {}", i + 1)))?;
+            replicas.push(conn);
+        }
+        if !lazy && !replicas.is_empty() {
+            let backend = Backend::from_url(config.database_url.expose());
+            tracing::info!(
+                "Connected to {} read replica(s){}",
+                replicas.len(),
+                backend.map(|b| format!(" ({})", b)).unwrap_or_default()
+            );
+        }
+
+        Ok(Self::new(writer, replicas).read_your_writes(config.db_read_your_writes))
+    }
+
+    /// Returns the primary connection, for writes and reads that must see
+    /// the latest data.
+    ///
+    /// With read-your-writes enabled, this pins the rest of the current
+    /// request to the primary.
+    pub fn writer(&self) -> &DatabaseConnection {
+        if self.read_your_writes {
+            let _ = PINNED.try_with(|pinned| pinned.set(true));
+        }
+        &self.writer
+    }
+
+    /// Returns a connection for reads: the next healthy replica, or the
+    /// primary if there is none or the request is pinned to it.
+    pub fn reader(&self) -> &DatabaseConnection {
+        if self.read_your_writes && PINNED.try_with(Cell::get).unwrap_or(false) {
+            return &self.writer;
+        }
+        match self.pick() {
+            Some(i) => &self.replicas[i].conn,
+            None => &self.writer,
+        }
+    }
+
+    /// Whether the database is usable: always `true`, except with
+    /// `DB_START_DEGRADED` until the primary has answered and migrations
+    /// have run.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::Relaxed);
+    }
+
+    /// Number of configured replicas.
+    pub fn replica_count(&self) -> usize {
+        self.replicas.len()
+    }
+
+    /// Number of replicas currently in rotation.
+    pub fn healthy_replicas(&self) -> usize {
+        self.replicas
+            .iter()
+            .filter(|r| r.healthy.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Index of the next healthy replica in round-robin order.
+    fn pick(&self) -> Option<usize> {
+        let n = self.replicas.len();
+        if n == 0 {
+            return None;
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..n)
+            .map(|offset| (start + offset) % n)
+            .find(|&i| self.replicas[i].healthy.load(Ordering::Relaxed))
+    }
+
+    /// Pings every replica each `interval` until shutdown, ejecting the ones
+    /// that fail and restoring them once they answer again.
+    pub(crate) fn monitor(&self, shutdown: &Shutdown, interval: Duration) {
+        if self.replicas.is_empty() {
+            return;
+        }
+        let replicas = Arc::clone(&self.replicas);
+        let stop = shutdown.clone();
+        shutdown.spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = stop.triggered() => break,
+                    _ = tokio::time::sleep(interval) => {}
+                }
+                for (i, replica) in replicas.iter().enumerate() {
+                    let result = tokio::time::timeout(interval, replica.conn.ping()).await;
+                    let healthy = matches!(result, Ok(Ok(())));
+                    let was_healthy = replica.healthy.swap(healthy, Ordering::Relaxed);
+                    match (was_healthy, healthy) {
+                        (true, false) => {
+                            let reason = match result {
+                                Ok(Err(e)) => e.to_string(),
+                                _ => "ping timed out".to_string(),
+                            };
+                            tracing::warn!("Replica {} ejected from rotation: {}", i + 1, reason);
+                        }
+                        (false, true) => {
+                            tracing::info!("Replica {} back in rotation", i + 1);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+    }
+
+    /// Closes the primary and replica pools.
+    pub(crate) async fn close(self) {
+        if let Err(e) = self.writer.close().await {
+            tracing::error!("Failed to close database connection: {}", e);
+        }
+        for (i, replica) in self.replicas.iter().enumerate() {
+            if let Err(e) = replica.conn.clone().close().await {
+                tracing::error!("Failed to close replica {} connection: {}", i + 1, e);
+            }
+        }
+    }
+}
+
+/// Middleware giving each request its own read-your-writes scope.
+pub(crate) async fn request_scope(request: Request, next: Next) -> Response {
+    PINNED.scope(Cell::new(false), next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(replicas: usize) -> Db {
+        let replicas = (0..replicas)
+            .map(|_| DatabaseConnection::Disconnected)
+            .collect();
+        Db::new(DatabaseConnection::Disconnected, replicas)
+    }
+
+    #[test]
+    fn test_round_robin_skips_ejected_replicas() {
+        let db = db(3);
+        let picks: Vec<_> = (0..4).map(|_| db.pick()).collect();
+        assert_eq!(picks, [Some(0), Some(1), Some(2), Some(0)]);
+
+        db.replicas[1].healthy.store(false, Ordering::Relaxed);
+        let picks: Vec<_> = (0..6).filter_map(|_| db.pick()).collect();
+        assert_eq!(picks.len(), 6);
+        assert!(picks.contains(&0) && picks.contains(&2) && !picks.contains(&1));
+        assert_eq!(db.healthy_replicas(), 2);
+
+        for replica in db.replicas.iter() {
+            replica.healthy.store(false, Ordering::Relaxed);
+        }
+        assert_eq!(db.pick(), None);
+    }
+
+    #[tokio::test]
+    async fn test_writer_pins_request_to_primary() {
+        let db = db(1);
+        let is_primary = |conn: &DatabaseConnection| std::ptr::eq(conn, &db.writer);
+
+        PINNED
+            .scope(Cell::new(false), async {
+                assert!(!is_primary(db.reader()));
+                db.writer();
+                assert!(is_primary(db.reader()));
+            })
+            .await;
+
+        // Outside a request scope nothing is pinned.
+        db.writer();
+        assert!(!is_primary(db.reader()));
+    }
+}
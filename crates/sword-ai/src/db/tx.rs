@@ -0,0 +1,263 @@
+//! Per-request transactions.
+
+use super::Db;
+use crate::error::SwordError;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sea_orm::{DatabaseTransaction, DbErr, TransactionTrait};
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Extractor for a transaction spanning the whole request.
+///
+/// The transaction is opened on the primary ([`Db::writer`]) when a handler
+/// first extracts `Tx`; extracting it again in the same request returns the
+/// same transaction. Once the handler returns, it is:
+///
+/// - **committed** if the response status is below 400,
+/// - **rolled back** if the status is 4xx or 5xx, or if the handler panics.
+///
+/// A failed commit turns the response into a `500 Internal Server Error`.
+///
+/// `Tx` dereferences to a SeaORM [`DatabaseTransaction`], so pass `&*tx`
+/// anywhere an [`Executor`](super::Executor) is expected:
+///
+/// ```rust,ignore
+/// use sword_ai::db::Tx;
+///
+/// async fn transfer(tx: Tx, Json(input): Json<Transfer>) -> Result<StatusCode, AppError> {
+///     accounts.debit(&*tx, input.from, input.amount).await?;
+///     accounts.credit(&*tx, input.to, input.amount).await?;
+///     Ok(StatusCode::NO_CONTENT) // both writes are committed, or neither
+/// }
+/// ```
+///
+/// Do not keep a `Tx` beyond the handler (e.g. in a spawned task): a
+/// transaction that is still in use when the handler returns is rolled
+/// back.
+pub struct Tx(Arc<DatabaseTransaction>);
+
+impl Deref for Tx {
+    type Target = DatabaseTransaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Rejection returned when a [`Tx`] cannot be extracted.
+#[derive(Debug, thiserror::Error)]
+pub enum TxRejection {
+    /// The request did not go through the transaction middleware, e.g. the
+    /// router was served without [`SwordApp`](crate::SwordApp).
+    #[error("Tx used on a router without the transaction middleware")]
+    MissingLayer,
+    /// The transaction could not be started.
+    #[error("failed to begin transaction: {0}")]
+    Begin(#[from] DbErr),
+}
+
+impl IntoResponse for TxRejection {
+    fn into_response(self) -> Response {
+        match self {
+            TxRejection::Begin(e) => SwordError::from(e).into_response(),
+            rejection => SwordError::internal(rejection).into_response(),
+        }
+    }
+}
+
+/// The request's transaction, shared between [`Tx`] and the middleware.
+#[derive(Clone)]
+struct TxSlot {
+    db: Db,
+    tx: Arc<Mutex<Option<Arc<DatabaseTransaction>>>>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Tx {
+    type Rejection = TxRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let slot = parts
+            .extensions
+            .get::<TxSlot>()
+            .cloned()
+            .ok_or(TxRejection::MissingLayer)?;
+        let mut tx = slot.tx.lock().await;
+        if let Some(tx) = tx.as_ref() {
+            return Ok(Tx(Arc::clone(tx)));
+        }
+        let begun = Arc::new(slot.db.writer().begin().await?);
+        *tx = Some(Arc::clone(&begun));
+        Ok(Tx(begun))
+    }
+}
+
+/// Middleware that commits or rolls back the request's [`Tx`], if any.
+pub(crate) async fn transaction_scope(
+    State(db): State<Db>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let slot = TxSlot {
+        db,
+        tx: Arc::new(Mutex::new(None)),
+    };
+    request.extensions_mut().insert(slot.clone());
+
+    let response = next.run(request).await;
+
+    let Some(tx) = slot.tx.lock().await.take() else {
+        return response;
+    };
+    let Ok(tx) = Arc::try_unwrap(tx) else {
+        return SwordError::internal(anyhow::anyhow!(
+            "transaction still in use after the handler returned, rolling back"
+        ))
+        .into_response();
+    };
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        if let Err(e) = tx.rollback().await {
+            tracing::error!("Failed to roll back transaction: {}", e);
+        }
+        return response;
+    }
+    match tx.commit().await {
+        Ok(()) => response,
+        // Logged with the failure when the error becomes a response.
+        Err(e) => {
+            SwordError::internal(anyhow::Error::new(e).context("failed to commit transaction"))
+                .into_response()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use axum::body::Body;
+    use axum::http::{header, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use sea_orm::{ConnectionTrait, Statement};
+    use tower::ServiceExt;
+    use tower_http::catch_panic::CatchPanicLayer;
+
+    async fn count(db: &Db) -> i32 {
+        let row = db
+            .writer()
+            .query_one(Statement::from_string(
+                db.writer().get_database_backend(),
+                "SELECT COUNT(*) AS n FROM notes",
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        row.try_get("", "n").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_commits_on_success_and_rolls_back_on_error() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = Db::connect(&config).await.unwrap();
+        db.writer()
+            .execute_unprepared("CREATE TABLE notes (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        async fn insert(tx: Tx, body: String) -> StatusCode {
+            tx.execute_unprepared("INSERT INTO notes DEFAULT VALUES")
+                .await
+                .unwrap();
+            StatusCode::from_bytes(body.as_bytes()).unwrap()
+        }
+        let app =
+            Router::new()
+                .route("/", post(insert))
+                .layer(axum::middleware::from_fn_with_state(
+                    db.clone(),
+                    transaction_scope,
+                ));
+        let send = |status: &'static str| {
+            app.clone()
+                .oneshot(Request::post("/").body(Body::from(status)).unwrap())
+        };
+
+        assert_eq!(send("201").await.unwrap().status(), StatusCode::CREATED);
+        assert_eq!(count(&db).await, 1);
+
+        assert_eq!(send("409").await.unwrap().status(), StatusCode::CONFLICT);
+        assert_eq!(count(&db).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rolls_back_on_panic() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = Db::connect(&config).await.unwrap();
+        db.writer()
+            .execute_unprepared("CREATE TABLE notes (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        async fn insert_then_panic(tx: Tx) -> StatusCode {
+            tx.execute_unprepared("INSERT INTO notes DEFAULT VALUES")
+                .await
+                .unwrap();
+            panic!("handler bug");
+        }
+        let app = Router::new()
+            .route("/", post(insert_then_panic))
+            .layer(axum::middleware::from_fn_with_state(
+                db.clone(),
+                transaction_scope,
+            ))
+            .layer(CatchPanicLayer::new());
+
+        let response = app
+            .oneshot(Request::post("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(count(&db).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_in_use_is_a_problem() {
+        let config = AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = Db::connect(&config).await.unwrap();
+
+        async fn leak(tx: Tx) -> StatusCode {
+            std::mem::forget(tx);
+            StatusCode::OK
+        }
+        let app = Router::new()
+            .route("/", post(leak))
+            .layer(axum::middleware::from_fn_with_state(db, transaction_scope));
+
+        let response = app
+            .oneshot(Request::post("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+    }
+}
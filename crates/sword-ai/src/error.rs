@@ -0,0 +1,427 @@
+//! Error responses module.
+//!
+//! Provides [`SwordError`], an error handlers return with `?` that
+//! responds with an [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807)
+//! `application/problem+json` body:
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Conflict",
+//!   "status": 409,
+//!   "detail": "A record with the same unique value already exists",
+//!   "code": "unique_violation",
+//!   "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736"
+//! }
+//! ```
+//!
+//! `code` is a stable, machine-readable identifier for clients to match
+//! on. `trace_id` is the request's
+//! [trace id](crate::tracing::current_trace_id), which also tags its logs.
+//! Further members can be added with [`SwordError::with_extension`].
+//!
+//! ## Conversions
+//!
+//! | Error | Status | `code` |
+//! |-------|--------|--------|
+//! | `DbErr`: unique constraint violation | `409` | `unique_violation` |
+//! | `DbErr`: foreign key constraint violation | `409` | `foreign_key_violation` |
+//! | `DbErr::RecordNotFound`, `DbErr::RecordNotUpdated` | `404` | `not_found` |
+//! | `DbErr`: no connection to the database | `503` | `database_unavailable` |
+//! | Any other `DbErr` | `500` | `internal_error` |
+//! | A [`DomainError`] | its own | its own |
+//! | [`ValidationErrors`](crate::validation::ValidationErrors) | `422` | `validation_failed` |
+//! | `anyhow::Error` | as the `SwordError`, `ValidationErrors` or `DbErr` it wraps, else `500` | |
+//!
+//! The underlying error is logged, at `ERROR` for server errors and
+//! `DEBUG` otherwise, but never sent to the client.
+//!
+//! ## Domain Errors
+//!
+//! Implement [`DomainError`] for an application's error types to give
+//! each one a status and code; the `Display` message becomes the `detail`:
+//!
+//! ```rust,ignore
+//! use sword_ai::error::{DomainError, SwordError};
+//!
+//! #[derive(Debug, thiserror::Error)]
+//! pub enum OrderError {
+//!     #[error("order {0} has already shipped")]
+//!     AlreadyShipped(i64),
+//! }
+//!
+//! impl DomainError for OrderError {
+//!     fn status(&self) -> StatusCode {
+//!         StatusCode::CONFLICT
+//!     }
+//!
+//!     fn code(&self) -> &'static str {
+//!         "order.already_shipped"
+//!     }
+//! }
+//!
+//! async fn cancel_order(Path(id): Path<i64>) -> Result<StatusCode, SwordError> {
+//!     orders.cancel(id).await?; // Result<(), OrderError>
+//!     Ok(StatusCode::NO_CONTENT)
+//! }
+//! ```
+//!
+//! Functions that can fail with a domain error should return
+//! `Result<_, SwordError>` rather than `anyhow::Result`: an `anyhow::Error`
+//! does not know the error is a [`DomainError`], so it becomes a `500`.
+
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+#[cfg(feature = "database")]
+use sea_orm::{DbErr, SqlErr};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Content type of problem details responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An error that responds with `application/problem+json`.
+///
+/// See the [module documentation](self) for the conversions from other
+/// errors.
+#[derive(Debug)]
+pub struct SwordError {
+    status: StatusCode,
+    code: Cow<'static, str>,
+    detail: Option<String>,
+    extensions: serde_json::Map<String, serde_json::Value>,
+    source: Option<anyhow::Error>,
+}
+
+impl SwordError {
+    /// An error with `status` and `code` and no detail.
+    pub fn new(status: StatusCode, code: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status,
+            code: code.into(),
+            detail: None,
+            extensions: serde_json::Map::new(),
+            source: None,
+        }
+    }
+
+    /// `400 Bad Request`, code `bad_request`.
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request").with_detail(detail)
+    }
+
+    /// `401 Unauthorized`, code `unauthorized`.
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized").with_detail(detail)
+    }
+
+    /// `403 Forbidden`, code `forbidden`.
+    pub fn forbidden(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden").with_detail(detail)
+    }
+
+    /// `404 Not Found`, code `not_found`.
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found").with_detail(detail)
+    }
+
+    /// `409 Conflict`, code `conflict`.
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict").with_detail(detail)
+    }
+
+    /// `500 Internal Server Error`, code `internal_error`. `error` is logged
+    /// but not sent to the client.
+    pub fn internal(error: impl Into<anyhow::Error>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error").with_source(error)
+    }
+
+    /// Sets the human-readable explanation sent to the client.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the machine-readable code, e.g. `user.email_taken`.
+    pub fn with_code(mut self, code: impl Into<Cow<'static, str>>) -> Self {
+        self.code = code.into();
+        self
+    }
+
+    /// Adds an extension member to the response body, e.g. the `errors`
+    /// of a validation failure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` cannot be serialized to JSON.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("problem extensions serialize to JSON");
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// Sets the underlying error, which is logged but not sent to the
+    /// client.
+    pub fn with_source(mut self, source: impl Into<anyhow::Error>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// The response status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The machine-readable code.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// The explanation sent to the client, if any.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    fn title(&self) -> &'static str {
+        self.status.canonical_reason().unwrap_or("Error")
+    }
+}
+
+impl fmt::Display for SwordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.detail().unwrap_or(self.title()),
+            self.code
+        )
+    }
+}
+
+impl std::error::Error for SwordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as _)
+    }
+}
+
+/// Body of a problem details response.
+#[derive(Serialize)]
+struct Problem<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    title: &'a str,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+    code: &'a str,
+    #[serde(flatten)]
+    extensions: &'a serde_json::Map<String, serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_id: Option<String>,
+}
+
+impl IntoResponse for SwordError {
+    fn into_response(self) -> Response {
+        let cause = self
+            .source
+            .as_ref()
+            .map(|e| format!(": {:#}", e))
+            .unwrap_or_default();
+        if self.status.is_server_error() {
+            tracing::error!("{}{}", self, cause);
+        } else {
+            tracing::debug!("{}{}", self, cause);
+        }
+
+        let problem = Problem {
+            kind: "about:blank",
+            title: self.title(),
+            status: self.status.as_u16(),
+            detail: self.detail(),
+            code: &self.code,
+            extensions: &self.extensions,
+            trace_id: crate::tracing::current_trace_id(),
+        };
+        let body = serde_json::to_vec(&problem).expect("problem details serialize to JSON");
+        (self.status, [(header::CONTENT_TYPE, PROBLEM_JSON)], body).into_response()
+    }
+}
+
+/// An application error with its own status and code.
+///
+/// Converts into a [`SwordError`] whose `detail` is the error's `Display`
+/// message.
+pub trait DomainError: std::error::Error + Send + Sync + 'static {
+    /// Response status, e.g. `409 Conflict`.
+    fn status(&self) -> StatusCode;
+
+    /// Machine-readable code, e.g. `order.already_shipped`.
+    fn code(&self) -> &'static str;
+}
+
+impl<E: DomainError> From<E> for SwordError {
+    fn from(error: E) -> Self {
+        Self::new(error.status(), error.code())
+            .with_detail(error.to_string())
+            .with_source(error)
+    }
+}
+
+#[cfg(feature = "database")]
+impl From<DbErr> for SwordError {
+    fn from(error: DbErr) -> Self {
+        let problem = match error.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => Some((
+                StatusCode::CONFLICT,
+                "unique_violation",
+                "A record with the same unique value already exists",
+            )),
+            Some(SqlErr::ForeignKeyConstraintViolation(_)) => Some((
+                StatusCode::CONFLICT,
+                "foreign_key_violation",
+                "The change would break a reference between records",
+            )),
+            _ => match error {
+                DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated => {
+                    Some((StatusCode::NOT_FOUND, "not_found", "Record not found"))
+                }
+                DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => Some((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "database_unavailable",
+                    "The database is unavailable",
+                )),
+                _ => None,
+            },
+        };
+        match problem {
+            Some((status, code, detail)) => Self::new(status, code)
+                .with_detail(detail)
+                .with_source(error),
+            None => Self::internal(error),
+        }
+    }
+}
+
+impl From<anyhow::Error> for SwordError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<SwordError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<crate::validation::ValidationErrors>() {
+            Ok(errors) => return errors.into(),
+            Err(error) => error,
+        };
+        #[cfg(feature = "database")]
+        let error = match error.downcast::<DbErr>() {
+            Ok(error) => return error.into(),
+            Err(error) => error,
+        };
+        Self::internal(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("order {0} has already shipped")]
+    struct AlreadyShipped(i64);
+
+    impl DomainError for AlreadyShipped {
+        fn status(&self) -> StatusCode {
+            StatusCode::CONFLICT
+        }
+
+        fn code(&self) -> &'static str {
+            "order.already_shipped"
+        }
+    }
+
+    async fn problem(error: SwordError) -> (StatusCode, serde_json::Value) {
+        let response =
+            crate::tracing::with_trace_id("abc".to_string(), async { error.into_response() }).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_problem_details() {
+        let (status, body) = problem(AlreadyShipped(7).into()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "order 7 has already shipped",
+                "code": "order.already_shipped",
+                "trace_id": "abc",
+            })
+        );
+
+        let hidden = anyhow::anyhow!("password=hunter2").context("connecting to the cache");
+        let (status, body) = problem(hidden.into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "internal_error");
+        assert!(body.get("detail").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_domain_errors_in_services() {
+        async fn cancel(shipped: bool) -> Result<(), SwordError> {
+            if shipped {
+                Err(AlreadyShipped(7))?;
+            }
+            Ok(())
+        }
+        let error = cancel(true).await.unwrap_err();
+        assert_eq!(
+            (error.status(), error.code()),
+            (StatusCode::CONFLICT, "order.already_shipped")
+        );
+
+        // Converted before it is wrapped, the error keeps its status.
+        let error = SwordError::from(anyhow::Error::new(SwordError::from(AlreadyShipped(7))));
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+        let error = SwordError::from(anyhow::Error::new(AlreadyShipped(7)));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_database_errors() {
+        use sea_orm::ConnectionTrait;
+
+        let config = crate::AppConfig {
+            database_url: "sqlite::memory:".into(),
+            ..Default::default()
+        };
+        let db = crate::connect_db(&config).await.unwrap();
+        db.execute_unprepared("CREATE TABLE users (email TEXT UNIQUE)")
+            .await
+            .unwrap();
+        db.execute_unprepared("INSERT INTO users VALUES ('a@example.com')")
+            .await
+            .unwrap();
+        let duplicate = db
+            .execute_unprepared("INSERT INTO users VALUES ('a@example.com')")
+            .await
+            .unwrap_err();
+        let error = SwordError::from(anyhow::Error::new(duplicate));
+        assert_eq!(
+            (error.status(), error.code()),
+            (StatusCode::CONFLICT, "unique_violation")
+        );
+
+        let error = SwordError::from(DbErr::RecordNotUpdated);
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    }
+}
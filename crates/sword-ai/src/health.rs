@@ -0,0 +1,330 @@
+//! Health check module.
+//!
+//! Every [`SwordApp`](crate::SwordApp) mounts two endpoints:
+//!
+//! - **Liveness** (`HEALTH_LIVENESS_PATH`, default `/livez`): returns `200`
+//!   as long as the process is serving requests.
+//! - **Readiness** (`HEALTH_READINESS_PATH`, default `/readyz`): pings the
+//!   database pool (with the `database` feature) and runs every registered
+//!   [`HealthCheck`]. Returns `200`
+//!   when all checks pass and `503` when any check fails or the server is
+//!   draining connections during shutdown.
+//!
+//! ## Response Format
+//!
+//! ```json
+//! {
+//!   "status": "ok",
+//!   "checks": {
+//!     "database": { "status": "ok", "latency_ms": 0.84 },
+//!     "redis": { "status": "error", "latency_ms": 2001.2, "error": "timed out" }
+//!   }
+//! }
+//! ```
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use sword_ai::health::HealthCheck;
+//!
+//! struct RedisCheck(redis::Client);
+//!
+//! #[async_trait::async_trait]
+//! impl HealthCheck for RedisCheck {
+//!     fn name(&self) -> &str {
+//!         "redis"
+//!     }
+//!
+//!     async fn check(&self) -> anyhow::Result<()> {
+//!         self.0.get_multiplexed_async_connection().await?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! SwordApp::builder()
+//!     .health_check(RedisCheck(client))
+//!     .router(build_router)
+//!     .serve()
+//!     .await?;
+//! ```
+
+#[cfg(feature = "database")]
+use crate::db::Db;
+use crate::server::FrameworkContext;
+use crate::shutdown::Shutdown;
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+#[cfg(feature = "database")]
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// A dependency check run by the readiness endpoint.
+#[async_trait]
+pub trait HealthCheck: Send + Sync + 'static {
+    /// Name used as the key for this check in the readiness response.
+    fn name(&self) -> &str;
+
+    /// Returns `Ok(())` when the dependency is healthy.
+    async fn check(&self) -> anyhow::Result<()>;
+}
+
+/// Built-in check that pings the database connection pool.
+#[cfg(feature = "database")]
+pub struct DatabaseCheck {
+    db: DatabaseConnection,
+    handle: Option<Db>,
+}
+
+#[cfg(feature = "database")]
+impl DatabaseCheck {
+    /// Creates a check for the given database connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db, handle: None }
+    }
+
+    /// Creates a check for the primary of `db` that also fails while the
+    /// database is not [ready](Db::is_ready).
+    pub fn for_db(db: &Db) -> Self {
+        Self {
+            db: db.writer().clone(),
+            handle: Some(db.clone()),
+        }
+    }
+}
+
+#[cfg(feature = "database")]
+#[async_trait]
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        if self.handle.as_ref().is_some_and(|db| !db.is_ready()) {
+            anyhow::bail!("waiting for the database to come up");
+        }
+        self.db.ping().await?;
+        Ok(())
+    }
+}
+
+/// Overall status of a health response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// All checks passed.
+    Ok,
+    /// At least one check failed.
+    Error,
+    /// The server is shutting down and no longer accepts traffic.
+    Draining,
+}
+
+/// Result of a single [`HealthCheck`].
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    /// Whether the check passed.
+    pub status: HealthStatus,
+    /// Time taken by the check, in milliseconds.
+    pub latency_ms: f64,
+    /// Error message when the check failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// JSON body returned by the health endpoints.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// Overall status.
+    pub status: HealthStatus,
+    /// Per-check results, keyed by [`HealthCheck::name`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<String, CheckReport>,
+}
+
+#[derive(Clone)]
+struct HealthState {
+    checks: Arc<Vec<Arc<dyn HealthCheck>>>,
+    shutdown: Shutdown,
+    timeout: Duration,
+}
+
+/// Builds the router serving the liveness and readiness endpoints.
+///
+/// With the `database` feature the database check always runs first,
+/// followed by `checks` in registration order.
+pub(crate) fn router(ctx: &FrameworkContext, checks: Vec<Arc<dyn HealthCheck>>) -> Router {
+    let mut all: Vec<Arc<dyn HealthCheck>> = Vec::with_capacity(checks.len() + 1);
+    #[cfg(feature = "database")]
+    all.push(Arc::new(DatabaseCheck::for_db(&ctx.db)));
+    all.extend(checks);
+
+    let state = HealthState {
+        checks: Arc::new(all),
+        shutdown: ctx.shutdown.clone(),
+        timeout: Duration::from_secs(ctx.config.health_check_timeout),
+    };
+
+    Router::new()
+        .route(&ctx.config.health_liveness_path, get(liveness))
+        .route(&ctx.config.health_readiness_path, get(readiness))
+        .with_state(state)
+}
+
+async fn liveness() -> Json<HealthReport> {
+    Json(HealthReport {
+        status: HealthStatus::Ok,
+        checks: BTreeMap::new(),
+    })
+}
+
+async fn readiness(State(state): State<HealthState>) -> (StatusCode, Json<HealthReport>) {
+    if state.shutdown.is_triggered() {
+        let report = HealthReport {
+            status: HealthStatus::Draining,
+            checks: BTreeMap::new(),
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(report));
+    }
+
+    let mut set = JoinSet::new();
+    for check in state.checks.iter() {
+        let check = Arc::clone(check);
+        let timeout = state.timeout;
+        set.spawn(async move {
+            let started = Instant::now();
+            let result = tokio::time::timeout(timeout, check.check()).await;
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+            let error = match result {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(format!("{:#}", e)),
+                Err(_) => Some(format!("timed out after {}s", timeout.as_secs_f32())),
+            };
+            let report = CheckReport {
+                status: if error.is_none() {
+                    HealthStatus::Ok
+                } else {
+                    HealthStatus::Error
+                },
+                latency_ms,
+                error,
+            };
+            (check.name().to_string(), report)
+        });
+    }
+
+    let mut checks = BTreeMap::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((name, report)) => {
+                checks.insert(name, report);
+            }
+            Err(e) => tracing::error!("Health check panicked: {}", e),
+        }
+    }
+
+    let healthy =
+        checks.len() == state.checks.len() && checks.values().all(|c| c.status == HealthStatus::Ok);
+    if healthy {
+        let report = HealthReport {
+            status: HealthStatus::Ok,
+            checks,
+        };
+        (StatusCode::OK, Json(report))
+    } else {
+        let report = HealthReport {
+            status: HealthStatus::Error,
+            checks,
+        };
+        (StatusCode::SERVICE_UNAVAILABLE, Json(report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticCheck(&'static str, bool);
+
+    #[async_trait]
+    impl HealthCheck for StaticCheck {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn check(&self) -> anyhow::Result<()> {
+            if self.1 {
+                Ok(())
+            } else {
+                anyhow::bail!("{} is down", self.0)
+            }
+        }
+    }
+
+    fn state(checks: Vec<Arc<dyn HealthCheck>>) -> HealthState {
+        HealthState {
+            checks: Arc::new(checks),
+            shutdown: Shutdown::new(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_readiness_ok_when_all_checks_pass() {
+        let state = state(vec![Arc::new(StaticCheck("cache", true))]);
+
+        let (status, Json(report)) = readiness(State(state)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report.status, HealthStatus::Ok);
+        assert_eq!(report.checks["cache"].status, HealthStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_readiness_reports_failing_check() {
+        let state = state(vec![
+            Arc::new(StaticCheck("cache", true)),
+            Arc::new(StaticCheck("queue", false)),
+        ]);
+
+        let (status, Json(report)) = readiness(State(state)).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(report.status, HealthStatus::Error);
+        assert_eq!(report.checks["cache"].status, HealthStatus::Ok);
+        assert_eq!(
+            report.checks["queue"].error.as_deref(),
+            Some("queue is down")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readiness_unavailable_while_draining() {
+        let state = state(vec![Arc::new(StaticCheck("cache", true))]);
+        state.shutdown.trigger();
+
+        let (status, Json(report)) = readiness(State(state)).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(report.status, HealthStatus::Draining);
+        assert!(report.checks.is_empty());
+    }
+
+    #[cfg(feature = "database")]
+    #[tokio::test]
+    async fn test_database_check_fails_until_ready() {
+        let db = Db::new(DatabaseConnection::Disconnected, Vec::new());
+        db.set_ready(false);
+
+        let err = DatabaseCheck::for_db(&db).check().await.unwrap_err();
+
+        assert_eq!(err.to_string(), "waiting for the database to come up");
+    }
+}
@@ -11,14 +11,33 @@
 //! ## Features
 //!
 //! - Pre-configured Axum server with tracing
-//! - SeaORM integration with PostgreSQL
+//! - SeaORM integration with PostgreSQL, MySQL and SQLite
 //! - Automatic database migration support
 //! - Environment-based configuration
+//! - Graceful shutdown with connection draining
+//! - Startup and shutdown lifecycle hooks
+//! - Liveness and readiness endpoints with pluggable checks
+//! - OpenMetrics endpoint and slow-query detection
+//! - Offset and keyset pagination with signed cursors
+//! - RFC 7807 problem details error responses
+//! - Validated JSON, query and path extractors with field-level errors
+//! - OpenAPI 3.1 document generation with Swagger UI/Redoc
+//! - Request id, timeout, body limit, compression, CORS and panic-catching middleware
+//! - Rate limiting, concurrency limits and load shedding
+//!
+//! ## Cargo Features
+//!
+//! - `postgres` *(default)*, `mysql`, `sqlite`: database backends. Each
+//!   enables `database`; several can be enabled at once.
+//! - `database`: SeaORM connection pool, migrations and the
+//!   `DATABASE_URL`/`DB_*` settings. Disable the default features with
+//!   `default-features = false` to run stateless services without a database.
+//! - `swagger-ui`: embeds Swagger UI (about 1.6 MB) for `OPENAPI_DOCS=swagger`.
 //!
 //! ## Quick Start
 //!
 //! ```rust,ignore
-//! use sword_ai::{server::run_with_migrator, FrameworkContext};
+//! use sword_ai::{FrameworkContext, SwordApp};
 //! use axum::{Router, routing::get};
 //!
 //! #[tokio::main]
@@ -26,7 +45,11 @@
 //!     dotenvy::dotenv().ok();
 //!     sword_ai::tracing::init_tracing();
 //!
-//!     run_with_migrator::<Migrator, _>(build_router, true).await
+//!     SwordApp::builder()
+//!         .migrator::<Migrator>()
+//!         .router(build_router)
+//!         .serve()
+//!         .await
 //! }
 //!
 //! fn build_router(ctx: &FrameworkContext) -> Router {
@@ -36,11 +59,36 @@
 //! }
 //! ```
 
+#[cfg(all(
+    feature = "database",
+    not(any(feature = "postgres", feature = "mysql", feature = "sqlite"))
+))]
+compile_error!("the `database` feature needs a backend: enable `postgres`, `mysql` or `sqlite`");
+
+// Lets `#[derive(ApiSchema)]` refer to `::sword_ai` inside this crate.
+extern crate self as sword_ai;
+
+pub mod app;
 pub mod config;
+#[cfg(feature = "database")]
 pub mod db;
+pub mod error;
+pub mod health;
+pub mod lifecycle;
+pub mod metrics;
+pub mod middleware;
+pub mod openapi;
+pub mod pagination;
+pub mod rate_limit;
 pub mod server;
+pub mod shutdown;
 pub mod tracing;
+pub mod validation;
 
+pub use app::{SwordApp, SwordAppBuilder};
 pub use config::AppConfig;
-pub use db::connect_db;
+#[cfg(feature = "database")]
+pub use db::{connect_db, Db};
+pub use error::{DomainError, SwordError};
 pub use server::FrameworkContext;
+pub use shutdown::Shutdown;
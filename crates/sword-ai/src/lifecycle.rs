@@ -0,0 +1,211 @@
+//! Lifecycle hooks module.
+//!
+//! Startup and shutdown hooks are async functions that receive the
+//! [`FrameworkContext`] and are registered with
+//! [`SwordAppBuilder::on_startup`](crate::SwordAppBuilder::on_startup) and
+//! [`SwordAppBuilder::on_shutdown`](crate::SwordAppBuilder::on_shutdown).
+//!
+//! ## Ordering
+//!
+//! - Startup hooks run in registration order after migrations and before the
+//!   router is built. The first failing hook aborts boot.
+//! - Shutdown hooks run in registration order after in-flight requests have
+//!   drained and background tasks have finished, and before the database
+//!   pool is closed. Each hook has a time budget (`APP_SHUTDOWN_HOOK_TIMEOUT`
+//!   by default); failures and timeouts are logged and do not stop the
+//!   remaining hooks.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! SwordApp::builder()
+//!     .on_startup(|ctx| async move {
+//!         warm_cache(ctx.db.reader()).await?;
+//!         Ok(())
+//!     })
+//!     .on_shutdown(|_ctx| async move {
+//!         flush_metrics().await;
+//!         Ok(())
+//!     })
+//!     .router(build_router)
+//!     .serve()
+//!     .await?;
+//! ```
+
+use crate::server::FrameworkContext;
+use anyhow::Context;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type HookFn = Box<dyn FnOnce(FrameworkContext) -> BoxFuture<anyhow::Result<()>> + Send>;
+
+struct ShutdownHook {
+    run: HookFn,
+    timeout: Option<Duration>,
+}
+
+/// Registered startup and shutdown hooks.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    startup: Vec<HookFn>,
+    shutdown: Vec<ShutdownHook>,
+}
+
+impl Hooks {
+    pub(crate) fn add_startup<F, Fut>(&mut self, hook: F)
+    where
+        F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.startup.push(boxed(hook));
+    }
+
+    pub(crate) fn add_shutdown<F, Fut>(&mut self, hook: F, timeout: Option<Duration>)
+    where
+        F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.shutdown.push(ShutdownHook {
+            run: boxed(hook),
+            timeout,
+        });
+    }
+
+    /// Runs startup hooks in order, stopping at the first failure.
+    pub(crate) async fn run_startup(&mut self, ctx: &FrameworkContext) -> anyhow::Result<()> {
+        let total = self.startup.len();
+        for (index, hook) in self.startup.drain(..).enumerate() {
+            tracing::debug!("Running startup hook {} of {}", index + 1, total);
+            hook(ctx.clone())
+                .await
+                .with_context(|| format!("startup hook {} of {} failed", index + 1, total))?;
+        }
+        Ok(())
+    }
+
+    /// Runs shutdown hooks in order, each within its time budget.
+    pub(crate) async fn run_shutdown(&mut self, ctx: &FrameworkContext, default_timeout: Duration) {
+        let total = self.shutdown.len();
+        for (index, hook) in self.shutdown.drain(..).enumerate() {
+            let timeout = hook.timeout.unwrap_or(default_timeout);
+            tracing::debug!("Running shutdown hook {} of {}", index + 1, total);
+            match tokio::time::timeout(timeout, (hook.run)(ctx.clone())).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    tracing::error!("Shutdown hook {} of {} failed: {:#}", index + 1, total, e)
+                }
+                Err(_) => tracing::warn!(
+                    "Shutdown hook {} of {} exceeded its {}s budget",
+                    index + 1,
+                    total,
+                    timeout.as_secs_f32()
+                ),
+            }
+        }
+    }
+}
+
+fn boxed<F, Fut>(hook: F) -> HookFn
+where
+    F: FnOnce(FrameworkContext) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    Box::new(move |ctx| Box::pin(hook(ctx)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    #[cfg(feature = "database")]
+    use crate::db::Db;
+    use crate::shutdown::Shutdown;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    async fn context() -> FrameworkContext {
+        let config = AppConfig {
+            #[cfg(feature = "database")]
+            database_url: if cfg!(feature = "postgres") {
+                "postgres://localhost/test"
+            } else {
+                "sqlite::memory:"
+            }
+            .into(),
+            ..Default::default()
+        };
+        FrameworkContext {
+            #[cfg(feature = "database")]
+            db: Db::connect_lazy(&config).await.unwrap(),
+            config,
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// A hook recording `id` in `ran`, then returning `result`.
+    fn record(
+        ran: &Arc<Mutex<Vec<u32>>>,
+        id: u32,
+        result: anyhow::Result<()>,
+    ) -> impl FnOnce(FrameworkContext) -> BoxFuture<anyhow::Result<()>> {
+        let ran = ran.clone();
+        move |_| {
+            Box::pin(async move {
+                ran.lock().unwrap().push(id);
+                result
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_startup_order() {
+        let ctx = context().await;
+        let ran = Arc::default();
+        let mut hooks = Hooks::default();
+        for id in 1..=3 {
+            hooks.add_startup(record(&ran, id, Ok(())));
+        }
+
+        hooks.run_startup(&ctx).await.unwrap();
+        assert_eq!(*ran.lock().unwrap(), [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_startup_failure_stops_boot() {
+        let ctx = context().await;
+        let ran = Arc::default();
+        let mut hooks = Hooks::default();
+        hooks.add_startup(record(&ran, 1, Ok(())));
+        hooks.add_startup(record(&ran, 2, Err(anyhow::anyhow!("cache unavailable"))));
+        hooks.add_startup(record(&ran, 3, Ok(())));
+
+        let err = hooks.run_startup(&ctx).await.unwrap_err();
+        assert_eq!(err.to_string(), "startup hook 2 of 3 failed");
+        assert_eq!(err.root_cause().to_string(), "cache unavailable");
+        assert_eq!(*ran.lock().unwrap(), [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_budget() {
+        let ctx = context().await;
+        let ran = Arc::default();
+        let mut hooks = Hooks::default();
+        hooks.add_shutdown(record(&ran, 1, Ok(())), None);
+        hooks.add_shutdown(
+            |_| async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            },
+            Some(Duration::from_millis(50)),
+        );
+        hooks.add_shutdown(record(&ran, 3, Err(anyhow::anyhow!("flush failed"))), None);
+        hooks.add_shutdown(record(&ran, 4, Ok(())), None);
+
+        let started = Instant::now();
+        hooks.run_shutdown(&ctx, Duration::from_secs(5)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(*ran.lock().unwrap(), [1, 3, 4]);
+    }
+}
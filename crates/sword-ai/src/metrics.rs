@@ -0,0 +1,218 @@
+//! Metrics module.
+//!
+//! Framework metrics are kept in a process-wide [`Registry`] and served in
+//! the [OpenMetrics](https://openmetrics.io) text format at `METRICS_PATH`
+//! (default: `/metrics`) once `METRICS_ENABLED=true`. The endpoint is off
+//! by default because it is served on the application port: keep it from
+//! the public, e.g. behind the load balancer.
+//!
+//! ## Framework Metrics
+//!
+//! | Metric | Type | Description |
+//! |--------|------|-------------|
+//! | `sword_db_slow_queries_total` | counter | Queries slower than `DB_SLOW_QUERY_THRESHOLD_MS` |
+//! | `sword_http_rate_limited_total` | counter | Requests rejected by a [rate limit](crate::rate_limit) |
+//! | `sword_http_shed_total` | counter | Requests shed past `HTTP_CONCURRENCY_LIMIT` and `HTTP_CONCURRENCY_QUEUE` |
+//!
+//! Counters carry an exemplar with the trace id of the request that last
+//! incremented them (see [`crate::tracing::current_trace_id`]), so a spike
+//! can be followed to an example trace:
+//!
+//! ```text
+//! # TYPE sword_db_slow_queries counter
+//! # HELP sword_db_slow_queries Queries slower than DB_SLOW_QUERY_THRESHOLD_MS.
+//! sword_db_slow_queries_total 3 # {trace_id="4bf92f3577b34da6a3ce929d0e0e4736"} 1.52
+//! # EOF
+//! ```
+//!
+//! ## Example
+//!
+//! Applications can register their own counters:
+//!
+//! ```rust,ignore
+//! use sword_ai::metrics::Registry;
+//!
+//! let signups = Registry::global().counter("app_signups", "Completed signups.");
+//! signups.inc();
+//! ```
+
+use crate::server::FrameworkContext;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Content type of the metrics endpoint.
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Example observation attached to a counter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exemplar {
+    /// Trace id of the request the observation was made in.
+    pub trace_id: String,
+    /// Observed value, e.g. a duration in seconds.
+    pub value: f64,
+}
+
+/// A monotonically increasing counter.
+///
+/// Created with [`Registry::counter`].
+#[derive(Debug)]
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU64,
+    exemplar: Mutex<Option<Exemplar>>,
+}
+
+impl Counter {
+    /// Increments the counter by one.
+    pub fn inc(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Increments the counter by one and records `exemplar` as its latest
+    /// example.
+    pub fn inc_with_exemplar(&self, exemplar: Exemplar) {
+        self.inc();
+        *self.exemplar.lock().unwrap_or_else(|e| e.into_inner()) = Some(exemplar);
+    }
+
+    /// Returns the current value.
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// Returns the latest exemplar, if any.
+    pub fn exemplar(&self) -> Option<Exemplar> {
+        self.exemplar
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// A set of metrics rendered together.
+#[derive(Debug, Default)]
+pub struct Registry {
+    counters: Mutex<Vec<Arc<Counter>>>,
+}
+
+impl Registry {
+    /// Returns the registry served by the metrics endpoint.
+    pub fn global() -> &'static Registry {
+        static GLOBAL: OnceLock<Registry> = OnceLock::new();
+        GLOBAL.get_or_init(Registry::default)
+    }
+
+    /// Returns the counter named `name`, registering it on first use.
+    ///
+    /// `name` is the metric family name; the sample is exposed as
+    /// `{name}_total`.
+    pub fn counter(&self, name: &'static str, help: &'static str) -> Arc<Counter> {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(counter) = counters.iter().find(|c| c.name == name) {
+            return Arc::clone(counter);
+        }
+        let counter = Arc::new(Counter {
+            name,
+            help,
+            value: AtomicU64::new(0),
+            exemplar: Mutex::new(None),
+        });
+        counters.push(Arc::clone(&counter));
+        counter
+    }
+
+    /// Renders every metric in the OpenMetrics text format.
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for counter in counters.iter() {
+            let _ = writeln!(out, "# TYPE {} counter", counter.name);
+            let _ = writeln!(out, "# HELP {} {}", counter.name, counter.help);
+            let _ = write!(out, "{}_total {}", counter.name, counter.get());
+            if let Some(exemplar) = counter.exemplar() {
+                let _ = write!(
+                    out,
+                    " # {{trace_id=\"{}\"}} {}",
+                    escape_label(&exemplar.trace_id),
+                    exemplar.value
+                );
+            }
+            out.push('\n');
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Escapes a label value as OpenMetrics requires.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Builds the metrics router, or an empty one unless `METRICS_ENABLED=true`.
+pub(crate) fn router(ctx: &FrameworkContext) -> Router {
+    if !ctx.config.metrics_enabled {
+        return Router::new();
+    }
+    Router::new().route(
+        &ctx.config.metrics_path,
+        get(|| async {
+            (
+                [(header::CONTENT_TYPE, CONTENT_TYPE)],
+                Registry::global().render(),
+            )
+                .into_response()
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counter_with_exemplar() {
+        let registry = Registry::default();
+        let counter = registry.counter("jobs", "Jobs run.");
+        counter.inc();
+        registry
+            .counter("jobs", "Jobs run.")
+            .inc_with_exemplar(Exemplar {
+                trace_id: "abc".to_string(),
+                value: 0.5,
+            });
+
+        assert_eq!(counter.get(), 2);
+        assert_eq!(
+            registry.render(),
+            "# TYPE jobs counter\n\
+             # HELP jobs Jobs run.\n\
+             jobs_total 2 # {trace_id=\"abc\"} 0.5\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_trace_id() {
+        let registry = Registry::default();
+        registry
+            .counter("jobs", "Jobs run.")
+            .inc_with_exemplar(Exemplar {
+                trace_id: "a\"} 9\n# EOF\\".to_string(),
+                value: 1.0,
+            });
+
+        assert!(registry
+            .render()
+            .contains("jobs_total 1 # {trace_id=\"a\\\"} 9\\n# EOF\\\\\"} 1\n"));
+    }
+}
@@ -0,0 +1,459 @@
+//! HTTP middleware module.
+//!
+//! Every [`SwordApp`](crate::SwordApp) wraps its routes in a standard
+//! stack, configured from [`AppConfig`]. From the outermost layer in:
+//!
+//! | Layer | Settings | Default |
+//! |-------|----------|---------|
+//! | Request id | `HTTP_REQUEST_ID` | on |
+//! | Panic catching | `HTTP_CATCH_PANIC` | on |
+//! | CORS | `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS`, `CORS_ALLOWED_HEADERS`, `CORS_ALLOW_CREDENTIALS`, `CORS_MAX_AGE` | off |
+//! | Rate limit | `RATE_LIMIT_*`, see [`crate::rate_limit`] | off |
+//! | Compression | `HTTP_COMPRESSION` | on |
+//! | Timeout | `HTTP_REQUEST_TIMEOUT` | 30 seconds |
+//! | Concurrency limit | `HTTP_CONCURRENCY_LIMIT`, `HTTP_CONCURRENCY_QUEUE` | off |
+//! | Body size limit | `HTTP_BODY_LIMIT` | 2 MiB |
+//!
+//! Each layer is left out when its setting is `false` or `0`, or, for
+//! CORS, when no origin is allowed.
+//!
+//! - **Request id**: the request's [trace id](crate::tracing::current_trace_id),
+//!   taken from `traceparent`, a well-formed `x-request-id` or else
+//!   generated, replaces its `x-request-id` header before it reaches the
+//!   handler and is echoed on the response, so the header always matches
+//!   the `trace_id` of problem responses and logs.
+//! - **Panic catching**: a panicking handler gets a `500` problem with code
+//!   `internal_error`, which carries the request's trace id and
+//!   `x-request-id`, instead of a dropped connection.
+//! - **Timeout**: a request still running after `HTTP_REQUEST_TIMEOUT`
+//!   is cancelled, rolling back its transaction, and gets a `503` problem
+//!   with code `request_timeout`.
+//! - **Concurrency limit**: past `HTTP_CONCURRENCY_LIMIT` requests in
+//!   flight, requests wait for a slot, which counts towards their timeout.
+//!   Once `HTTP_CONCURRENCY_QUEUE` requests are waiting, new ones are shed
+//!   with a `503` problem, code `overloaded`, and a `Retry-After` header.
+//!   The health and metrics endpoints are never held back.
+//! - **Body size limit**: a body larger than `HTTP_BODY_LIMIT` gets a `413`
+//!   problem with code `payload_too_large`. Bodies without a
+//!   `Content-Length` are cut off by the extractors reading them.
+//!
+//! Layers added with [`SwordAppBuilder::layer`](crate::SwordAppBuilder::layer)
+//! wrap this stack.
+
+use crate::config::AppConfig;
+use crate::error::SwordError;
+use crate::metrics::{Exemplar, Registry};
+use crate::rate_limit::RateLimit;
+use axum::extract::{DefaultBodyLimit, Request};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+/// Header carrying the request id.
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Name of the shed request counter in the metrics registry.
+const SHED: &str = "sword_http_shed";
+
+/// Wraps `router` in the layers enabled by `config`, and `rate_limit`.
+pub(crate) fn apply(mut router: Router, config: &AppConfig, rate_limit: RateLimit) -> Router {
+    if config.http_body_limit > 0 {
+        let limit = config.http_body_limit;
+        router = router
+            .layer(DefaultBodyLimit::max(
+                usize::try_from(limit).unwrap_or(usize::MAX),
+            ))
+            .layer(middleware::from_fn(move |request: Request, next: Next| {
+                body_limit(limit, request, next)
+            }));
+    } else {
+        router = router.layer(DefaultBodyLimit::disable());
+    }
+    if config.http_concurrency_limit > 0 {
+        let limit = Arc::new(ConcurrencyLimit::new(config));
+        router = router.layer(middleware::from_fn(move |request: Request, next: Next| {
+            let limit = Arc::clone(&limit);
+            async move { limit.run(request, next).await }
+        }));
+    }
+    if config.http_request_timeout > 0 {
+        let timeout = Duration::from_secs(config.http_request_timeout);
+        router = router.layer(middleware::from_fn(move |request: Request, next: Next| {
+            request_timeout(timeout, request, next)
+        }));
+    }
+    if config.http_compression {
+        router = router.layer(CompressionLayer::new());
+    }
+    if rate_limit.is_active() {
+        router = router.layer(rate_limit);
+    }
+    if let Some(cors) = cors(config) {
+        router = router.layer(cors);
+    }
+    if config.http_catch_panic {
+        router = router.layer(CatchPanicLayer::custom(panic_response));
+    }
+    if config.http_request_id {
+        router = router.layer(middleware::from_fn(request_id));
+    }
+    router
+}
+
+/// Rejects requests whose `Content-Length` exceeds `limit`.
+async fn body_limit(limit: u64, request: Request, next: Next) -> Response {
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if length.is_some_and(|length| length > limit) {
+        return SwordError::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large")
+            .with_detail(format!("The request body is larger than {} bytes", limit))
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Caps the requests in flight, queueing a bounded number of others.
+struct ConcurrencyLimit {
+    slots: Semaphore,
+    queued: AtomicU64,
+    max_queued: u64,
+    exempt: [String; 3],
+}
+
+impl ConcurrencyLimit {
+    fn new(config: &AppConfig) -> Self {
+        Self {
+            slots: Semaphore::new(
+                usize::try_from(config.http_concurrency_limit)
+                    .unwrap_or(Semaphore::MAX_PERMITS)
+                    .min(Semaphore::MAX_PERMITS),
+            ),
+            queued: AtomicU64::new(0),
+            max_queued: config.http_concurrency_queue,
+            exempt: [
+                config.health_liveness_path.clone(),
+                config.health_readiness_path.clone(),
+                config.metrics_path.clone(),
+            ],
+        }
+    }
+
+    async fn run(&self, request: Request, next: Next) -> Response {
+        if self.exempt.iter().any(|path| path == request.uri().path()) {
+            return next.run(request).await;
+        }
+        let _slot = match self.slots.try_acquire() {
+            Ok(slot) => slot,
+            Err(_) => {
+                if self.queued.fetch_add(1, Ordering::AcqRel) >= self.max_queued {
+                    self.queued.fetch_sub(1, Ordering::AcqRel);
+                    return overloaded();
+                }
+                // Leaves the queue even if the request is cancelled while
+                // waiting.
+                let _queued = Dequeue(&self.queued);
+                self.slots
+                    .acquire()
+                    .await
+                    .expect("the semaphore is never closed")
+            }
+        };
+        next.run(request).await
+    }
+}
+
+struct Dequeue<'a>(&'a AtomicU64);
+
+impl Drop for Dequeue<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The `503` problem for a shed request.
+fn overloaded() -> Response {
+    let counter = Registry::global().counter(
+        SHED,
+        "Requests shed past HTTP_CONCURRENCY_LIMIT and HTTP_CONCURRENCY_QUEUE.",
+    );
+    match crate::tracing::current_trace_id() {
+        Some(trace_id) => counter.inc_with_exemplar(Exemplar {
+            trace_id,
+            value: 1.0,
+        }),
+        None => counter.inc(),
+    }
+    tracing::warn!("Server overloaded, shedding request");
+    let mut response = SwordError::new(StatusCode::SERVICE_UNAVAILABLE, "overloaded")
+        .with_detail("The server is overloaded, retry later")
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    response
+}
+
+/// Cancels requests that run longer than `timeout`.
+async fn request_timeout(timeout: Duration, request: Request, next: Next) -> Response {
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request timed out after {}s", timeout.as_secs());
+            SwordError::new(StatusCode::SERVICE_UNAVAILABLE, "request_timeout")
+                .with_detail("The request took too long to handle")
+                .into_response()
+        }
+    }
+}
+
+/// Sets `x-request-id` on the request to its trace id, and echoes it on the
+/// response.
+async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = crate::tracing::current_trace_id().and_then(|id| HeaderValue::from_str(&id).ok());
+    let Some(id) = id else {
+        return next.run(request).await;
+    };
+    request.headers_mut().insert(REQUEST_ID, id.clone());
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID, id);
+    response
+}
+
+/// Responds to a handler panic with a `500` problem.
+fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    SwordError::internal(anyhow::anyhow!("handler panicked: {}", message)).into_response()
+}
+
+/// The CORS layer, or `None` when no origin is allowed.
+fn cors(config: &AppConfig) -> Option<CorsLayer> {
+    let origins = entries(&config.cors_allowed_origins);
+    if origins.is_empty() {
+        return None;
+    }
+    let origin = if origins.contains(&"*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()))
+    };
+    let methods = entries(&config.cors_allowed_methods);
+    let methods = if methods.contains(&"*") {
+        AllowMethods::any()
+    } else {
+        AllowMethods::list(
+            methods
+                .iter()
+                .filter_map(|m| Method::from_bytes(m.to_ascii_uppercase().as_bytes()).ok()),
+        )
+    };
+    let headers = entries(&config.cors_allowed_headers);
+    let headers = if headers.contains(&"*") {
+        AllowHeaders::any()
+    } else {
+        AllowHeaders::list(
+            headers
+                .iter()
+                .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok()),
+        )
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .allow_credentials(config.cors_allow_credentials)
+            .expose_headers([REQUEST_ID])
+            .max_age(Duration::from_secs(config.cors_max_age)),
+    )
+}
+
+/// Trimmed, non-empty entries of a list setting.
+fn entries(values: &[String]) -> Vec<&str> {
+    values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::{get, post};
+    use tower::ServiceExt;
+
+    fn app(config: &AppConfig) -> Router {
+        let router = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/echo", post(|body: String| async move { body }))
+            .route("/panic", get(boom))
+            .route("/livez", get(|| async { "live" }))
+            .route(
+                "/busy",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    "done"
+                }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            );
+        apply(router, config, RateLimit::from_config(config))
+            .layer(middleware::from_fn(crate::tracing::trace_scope))
+    }
+
+    async fn boom() -> &'static str {
+        panic!("boom")
+    }
+
+    async fn send(app: &Router, request: Request) -> Response {
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_default_stack() {
+        let config = AppConfig {
+            http_request_timeout: 1,
+            http_body_limit: 8,
+            cors_allowed_origins: vec!["https://app.example.com".to_string()],
+            ..AppConfig::default()
+        };
+        let app = app(&config);
+
+        let request = Request::get("/ok")
+            .header(REQUEST_ID, "req-1")
+            .header(header::ORIGIN, "https://app.example.com")
+            .body(Body::empty())
+            .unwrap();
+        let response = send(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[&REQUEST_ID], "req-1");
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+
+        // traceparent wins over x-request-id, and a malformed id is replaced.
+        let request = Request::get("/ok")
+            .header(
+                "traceparent",
+                "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            )
+            .header(REQUEST_ID, "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = send(&app, request).await;
+        assert_eq!(
+            response.headers()[&REQUEST_ID],
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        let request = Request::get("/panic")
+            .header(REQUEST_ID, "x\"} 1e9 # EOF")
+            .body(Body::empty())
+            .unwrap();
+        let response = send(&app, request).await;
+        let id = response.headers()[&REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(id.len(), 32);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["trace_id"], id.as_str());
+
+        let response = send(&app, Request::get("/panic").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let id = response.headers()[&REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "internal_error");
+        assert_eq!(problem["trace_id"], id.as_str());
+
+        let request = Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "9")
+            .body(Body::from("123456789"))
+            .unwrap();
+        let response = send(&app, request).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = send(&app, Request::get("/slow").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_load_shedding() {
+        let config = AppConfig {
+            http_concurrency_limit: 1,
+            http_concurrency_queue: 1,
+            ..AppConfig::default()
+        };
+        let app = app(&config);
+        let busy = || Request::get("/busy").body(Body::empty()).unwrap();
+
+        let first = tokio::spawn(app.clone().oneshot(busy()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let queued = tokio::spawn(app.clone().oneshot(busy()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let response = send(&app, busy()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        let response = send(&app, Request::get("/livez").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(queued.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_opt_out() {
+        let config = AppConfig {
+            http_request_id: false,
+            http_compression: false,
+            http_body_limit: 0,
+            ..AppConfig::default()
+        };
+        let app = app(&config);
+
+        let request = Request::post("/echo")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::from("x".repeat(4 * 1024 * 1024)))
+            .unwrap();
+        let response = send(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(&REQUEST_ID).is_none());
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+}
@@ -0,0 +1,495 @@
+//! OpenAPI module.
+//!
+//! Builds an [OpenAPI 3.1](https://spec.openapis.org/oas/v3.1.0) document
+//! from the routes of an [`ApiRouter`]: each handler is described from its
+//! extractors ([`OperationInput`]) and response type ([`OperationOutput`]),
+//! and request and response bodies from their [`ApiSchema`], which is
+//! derived alongside `Serialize`/`Deserialize` and follows their `serde`
+//! attributes.
+//!
+//! The document is served at `OPENAPI_PATH` (default: `/openapi.json`).
+//! Setting `OPENAPI_DOCS` to `swagger` or `redoc` adds an interactive page
+//! at `OPENAPI_DOCS_PATH` (default: `/docs`). Swagger UI needs the
+//! `swagger-ui` feature, which embeds it (about 1.6 MB) to be served next
+//! to the page; Redoc is loaded from jsDelivr. Set `OPENAPI_ENABLED=false`
+//! to remove both endpoints, e.g. in production.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use sword_ai::openapi::{get, post_with, ApiRouter, ApiSchema, OpenApi};
+//!
+//! #[derive(Deserialize, ApiSchema)]
+//! struct CreateUserRequest {
+//!     /// Display name.
+//!     name: String,
+//!     email: String,
+//! }
+//!
+//! fn api() -> ApiRouter<AppState> {
+//!     ApiRouter::new()
+//!         .route("/users", get(list_users).post_with(create_user, |op| op.status(StatusCode::CREATED)))
+//!         .route("/users/:id", get(get_user))
+//! }
+//!
+//! SwordApp::builder()
+//!     .openapi(api().openapi().info("My API", "1.0.0"))
+//!     .router(|ctx| api().into_router().with_state(AppState::new(ctx)))
+//!     .serve()
+//!     .await?;
+//! ```
+//!
+//! ## Exporting
+//!
+//! [`OpenApiCommand`] writes the document without starting the server, so
+//! `sword openapi export` can produce it in CI and diff it against the
+//! committed copy.
+
+mod operation;
+mod router;
+mod schema;
+
+pub use operation::{Operation, OperationInput, OperationOutput};
+pub use router::{
+    delete, delete_with, get, get_with, patch, patch_with, post, post_with, put, put_with,
+    ApiMethodRouter, ApiRouter, OperationHandler,
+};
+pub use schema::{ApiSchema, Components};
+/// Derives [`ApiSchema`](trait@ApiSchema) from a struct or enum, following
+/// its `serde` attributes. Doc comments become descriptions.
+pub use sword_ai_macros::ApiSchema;
+
+use crate::server::FrameworkContext;
+use axum::http::header;
+use axum::response::{Html, IntoResponse};
+use axum::routing::get as get_route;
+use axum::Router;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// An OpenAPI 3.1 document.
+///
+/// Created with [`ApiRouter::openapi`] and served with
+/// [`SwordAppBuilder::openapi`](crate::SwordAppBuilder::openapi).
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    paths: BTreeMap<String, BTreeMap<String, Value>>,
+    components: Components,
+}
+
+impl OpenApi {
+    pub(crate) fn new(
+        paths: BTreeMap<String, BTreeMap<String, Value>>,
+        components: Components,
+    ) -> Self {
+        Self {
+            title: "API".to_string(),
+            version: "0.1.0".to_string(),
+            description: None,
+            paths,
+            components,
+        }
+    }
+
+    /// Sets the title and version of the API, e.g.
+    /// `env!("CARGO_PKG_NAME")` and `env!("CARGO_PKG_VERSION")`.
+    pub fn info(mut self, title: impl Into<String>, version: impl Into<String>) -> Self {
+        self.title = title.into();
+        self.version = version.into();
+        self
+    }
+
+    /// Sets the description of the API. CommonMark is allowed.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The document as JSON.
+    pub fn to_json(&self) -> Value {
+        let mut info = json!({ "title": self.title, "version": self.version });
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+        json!({
+            "openapi": "3.1.0",
+            "info": info,
+            "paths": self.paths,
+            "components": self.components.to_json(),
+        })
+    }
+}
+
+impl Serialize for OpenApi {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+/// The UI rendering the docs page (`OPENAPI_DOCS`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocsUi {
+    /// [Swagger UI](https://swagger.io/tools/swagger-ui/) (`swagger`),
+    /// embedded in the framework. Requires the `swagger-ui` feature.
+    #[cfg(feature = "swagger-ui")]
+    Swagger,
+    /// [Redoc](https://redocly.com/redoc) (`redoc`), loaded from jsDelivr.
+    Redoc,
+    /// No docs page (`off`).
+    #[default]
+    Off,
+}
+
+/// Swagger UI 5.17.14, served next to the docs page. See
+/// `assets/swagger-ui/LICENSE`.
+#[cfg(feature = "swagger-ui")]
+const SWAGGER_UI_JS: &[u8] = include_bytes!("../../assets/swagger-ui/swagger-ui-bundle.js");
+#[cfg(feature = "swagger-ui")]
+const SWAGGER_UI_CSS: &[u8] = include_bytes!("../../assets/swagger-ui/swagger-ui.css");
+
+/// Redoc, loaded from jsDelivr at an exact version.
+const REDOC_JS: &str = "https://cdn.jsdelivr.net/npm/redoc@2.1.5/bundles/redoc.standalone.js";
+
+/// Serves `doc` at `OPENAPI_PATH` and the docs page at
+/// `OPENAPI_DOCS_PATH`, unless disabled.
+pub(crate) fn router(ctx: &FrameworkContext, doc: Option<&OpenApi>) -> Router {
+    let config = &ctx.config;
+    let Some(doc) = doc.filter(|_| config.openapi_enabled) else {
+        return Router::new();
+    };
+    let json = doc.to_json().to_string();
+    let mut router = Router::new().route(
+        &config.openapi_path,
+        get_route(|| async move {
+            ([(header::CONTENT_TYPE, "application/json")], json).into_response()
+        }),
+    );
+    let assets = config.openapi_docs_path.trim_end_matches('/');
+    if let Some(page) = docs_page(
+        config.openapi_docs,
+        &doc.title,
+        &config.openapi_path,
+        assets,
+    ) {
+        router = router.route(
+            &config.openapi_docs_path,
+            get_route(|| async move { Html(page) }),
+        );
+    }
+    #[cfg(feature = "swagger-ui")]
+    if config.openapi_docs == DocsUi::Swagger {
+        router = router
+            .route(
+                &format!("{}/swagger-ui-bundle.js", assets),
+                get_route(|| async { asset("text/javascript", SWAGGER_UI_JS) }),
+            )
+            .route(
+                &format!("{}/swagger-ui.css", assets),
+                get_route(|| async { asset("text/css", SWAGGER_UI_CSS) }),
+            );
+    }
+    router
+}
+
+#[cfg(feature = "swagger-ui")]
+fn asset(content_type: &'static str, body: &'static [u8]) -> axum::response::Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// The HTML page rendering the document at `spec_url` with `ui`. Swagger UI
+/// is loaded from `assets`, Redoc from jsDelivr.
+#[cfg_attr(not(feature = "swagger-ui"), allow(unused_variables))]
+fn docs_page(ui: DocsUi, title: &str, spec_url: &str, assets: &str) -> Option<String> {
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let spec_url = json!(spec_url).to_string().replace("</", "<\\/");
+    let (head, body) = match ui {
+        #[cfg(feature = "swagger-ui")]
+        DocsUi::Swagger => (
+            format!(
+                r#"<link rel="stylesheet" href="{}/swagger-ui.css">"#,
+                assets
+            ),
+            format!(
+                r##"<div id="docs"></div>
+<script src="{}/swagger-ui-bundle.js"></script>
+<script>SwaggerUIBundle({{ url: {}, dom_id: "#docs" }});</script>"##,
+                assets, spec_url
+            ),
+        ),
+        DocsUi::Redoc => (
+            String::new(),
+            format!(
+                r#"<div id="docs"></div>
+<script src="{}" crossorigin="anonymous"></script>
+<script>Redoc.init({}, {{}}, document.getElementById("docs"));</script>"#,
+                REDOC_JS, spec_url
+            ),
+        ),
+        DocsUi::Off => return None,
+    };
+    Some(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{}</title>
+{}
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        title, head, body
+    ))
+}
+
+/// An OpenAPI command, as invoked by `sword openapi export`.
+///
+/// Generated projects dispatch it from `main` before starting the server,
+/// so the document is written without a database or a bound port:
+///
+/// ```rust,ignore
+/// use sword_ai::openapi::OpenApiCommand;
+///
+/// if let Some(command) = OpenApiCommand::from_args(std::env::args())? {
+///     return command.run(&app::routes::openapi());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenApiCommand {
+    /// Writes the document as pretty-printed JSON to the given file, or to
+    /// stdout when `None`.
+    Export(Option<PathBuf>),
+}
+
+impl OpenApiCommand {
+    /// Parses `<program> openapi export [file]`, where `-` is stdout.
+    ///
+    /// Returns `Ok(None)` if the first argument is not `openapi`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown command or extra arguments.
+    pub fn from_args<I>(args: I) -> anyhow::Result<Option<Self>>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().skip(1).map(Into::into).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let command = match args.as_slice() {
+            ["openapi", "export"] | ["openapi", "export", "-"] => Self::Export(None),
+            ["openapi", "export", file] => Self::Export(Some(PathBuf::from(file))),
+            ["openapi", "export", file, ..] => {
+                anyhow::bail!("unexpected arguments after '{}'", file)
+            }
+            ["openapi", other, ..] => {
+                anyhow::bail!("unknown openapi command '{}': expected export", other)
+            }
+            ["openapi"] => anyhow::bail!("usage: openapi export [file]"),
+            _ => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+
+    /// Runs the command on `doc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn run(self, doc: &OpenApi) -> anyhow::Result<()> {
+        let mut json = serde_json::to_string_pretty(doc)?;
+        json.push('\n');
+        match self {
+            Self::Export(Some(path)) => std::fs::write(&path, json)
+                .map_err(|e| anyhow::anyhow!("cannot write {}: {}", path.display(), e)),
+            Self::Export(None) => Ok(std::io::stdout().write_all(json.as_bytes())?),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    //! Support for the code generated by `#[derive(ApiSchema)]`.
+
+    pub use serde_json::{json, Map, Value};
+
+    /// Adds `description` to `schema`.
+    pub fn describe(mut schema: Value, description: Option<&str>) -> Value {
+        if let (Some(object), Some(description)) = (schema.as_object_mut(), description) {
+            object.insert("description".to_string(), json!(description));
+        }
+        schema
+    }
+
+    /// The schema of a struct with named fields.
+    pub fn object(
+        properties: Map<String, Value>,
+        required: Vec<Value>,
+        flattened: Vec<Value>,
+        description: Option<&str>,
+    ) -> Value {
+        let mut object = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            object["required"] = Value::Array(required);
+        }
+        let object = describe(object, description);
+        if flattened.is_empty() {
+            return object;
+        }
+        let mut all_of = vec![object];
+        all_of.extend(flattened);
+        json!({ "allOf": all_of })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{Validate, ValidatedJson, Validator};
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use axum::Json;
+
+    #[derive(serde::Deserialize, ApiSchema)]
+    struct CreateNote {
+        /// Body of the note.
+        text: String,
+    }
+
+    impl Validate for CreateNote {
+        fn validate(&self, _v: &mut Validator) {}
+    }
+
+    #[derive(serde::Serialize, ApiSchema)]
+    struct Note {
+        id: i64,
+        text: String,
+    }
+
+    async fn create_note(
+        ValidatedJson(note): ValidatedJson<CreateNote>,
+    ) -> Result<(StatusCode, Json<Note>), crate::SwordError> {
+        Ok((
+            StatusCode::CREATED,
+            Json(Note {
+                id: 1,
+                text: note.text,
+            }),
+        ))
+    }
+
+    async fn get_note(Path(id): Path<i64>) -> Json<Note> {
+        Json(Note {
+            id,
+            text: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_document() {
+        let api = ApiRouter::<()>::new()
+            .route(
+                "/notes",
+                post_with(create_note, |op| op.status(StatusCode::CREATED)),
+            )
+            .route("/notes/:id", get(get_note));
+        let doc = ApiRouter::new()
+            .nest("/api", api)
+            .openapi()
+            .info("Notes", "1.0.0")
+            .to_json();
+
+        assert_eq!(doc["openapi"], "3.1.0");
+        assert_eq!(doc["info"], json!({ "title": "Notes", "version": "1.0.0" }));
+        let create = &doc["paths"]["/api/notes"]["post"];
+        assert_eq!(create["operationId"], "create_note");
+        assert_eq!(create["summary"], "Create note");
+        assert_eq!(create["tags"], json!(["notes"]));
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/CreateNote" })
+        );
+        assert_eq!(
+            create["responses"]["201"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/Note" })
+        );
+        for status in ["400", "415", "422", "default"] {
+            assert_eq!(
+                create["responses"][status]["content"]["application/problem+json"]["schema"],
+                json!({ "$ref": "#/components/schemas/Problem" })
+            );
+        }
+        assert_eq!(
+            doc["paths"]["/api/notes/{id}"]["get"]["parameters"],
+            json!([{
+                "name": "id",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "integer", "format": "int64" },
+            }])
+        );
+        assert_eq!(
+            doc["components"]["schemas"]["CreateNote"],
+            json!({
+                "type": "object",
+                "properties": { "text": { "type": "string", "description": "Body of the note." } },
+                "required": ["text"],
+            })
+        );
+        assert!(doc["components"]["schemas"]["FieldError"].is_object());
+    }
+
+    #[test]
+    fn test_from_args() {
+        let parse = |args: &[&str]| OpenApiCommand::from_args(args.iter().copied());
+        assert_eq!(parse(&["app"]).unwrap(), None);
+        assert_eq!(
+            parse(&["app", "openapi", "export"]).unwrap(),
+            Some(OpenApiCommand::Export(None))
+        );
+        assert_eq!(
+            parse(&["app", "openapi", "export", "api.json"]).unwrap(),
+            Some(OpenApiCommand::Export(Some(PathBuf::from("api.json"))))
+        );
+        assert!(parse(&["app", "openapi", "import"]).is_err());
+        assert!(parse(&["app", "openapi", "export", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_docs_page() {
+        let page = docs_page(DocsUi::Redoc, "<Notes>", "/openapi.json</script>", "/docs").unwrap();
+        assert!(page.contains("<title>&lt;Notes&gt;</title>"));
+        assert!(page.contains(r#"Redoc.init("/openapi.json<\/script>""#));
+        assert!(page.contains("redoc@2.1.5"));
+        assert!(docs_page(DocsUi::Off, "Notes", "/openapi.json", "/docs").is_none());
+    }
+
+    #[cfg(feature = "swagger-ui")]
+    #[test]
+    fn test_swagger_ui_page() {
+        let page = docs_page(DocsUi::Swagger, "Notes", "/openapi.json", "/docs").unwrap();
+        assert!(page.contains(r#"url: "/openapi.json""#));
+        assert!(page.contains(r#"<script src="/docs/swagger-ui-bundle.js">"#));
+        assert!(!page.contains("https://"));
+    }
+}
@@ -0,0 +1,545 @@
+//! Operations, described from the extractors and responses of handlers.
+
+use super::{ApiSchema, Components};
+use crate::error::{SwordError, PROBLEM_JSON};
+use crate::pagination::{Page, Pagination};
+use crate::validation::{
+    FieldError, ValidatedJson, ValidatedPath, ValidatedQuery, ValidationErrors,
+};
+use axum::body::Bytes;
+use axum::extract::{
+    ConnectInfo, Extension, Form, Json, MatchedPath, OriginalUri, Path, Query, RawPathParams,
+    Request, State,
+};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{Html, Response};
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// An operation of the document: one method of one path.
+///
+/// Inferred from the handler's extractors ([`OperationInput`]) and
+/// response ([`OperationOutput`]), and adjusted with the `*_with` route
+/// functions:
+///
+/// ```rust,ignore
+/// post_with(create_user, |op| op.status(StatusCode::CREATED).tag("accounts"))
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Operation {
+    operation_id: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+    status: Option<StatusCode>,
+    parameters: Vec<Value>,
+    path: Option<Value>,
+    request_body: Option<Value>,
+    success: Option<Value>,
+    responses: BTreeMap<String, Value>,
+    extra: Vec<(StatusCode, String, SchemaFn)>,
+}
+
+type SchemaFn = fn(&mut Components) -> Value;
+
+impl Operation {
+    /// Sets the `operationId`, which defaults to the name of the handler.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.operation_id = Some(id.into());
+        self
+    }
+
+    /// Sets the one-line summary, which defaults to the name of the handler
+    /// in words, e.g. `Create user`.
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Sets the longer description. CommonMark is allowed.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a tag. Without one, the operation is tagged with the first
+    /// segment of its path, e.g. `users` for `/users/{id}`.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Marks the operation as deprecated.
+    pub fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// Sets the status of a successful response, which defaults to
+    /// `200 OK`, e.g. `201 Created` for a handler returning
+    /// `(StatusCode, Json<T>)`.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Documents another response, with a JSON body of type `T`.
+    pub fn response<T: ApiSchema>(
+        mut self,
+        status: StatusCode,
+        description: impl Into<String>,
+    ) -> Self {
+        self.extra
+            .push((status, description.into(), Components::schema::<T>));
+        self
+    }
+
+    /// Adds a parameter. `location` is `query`, `header`, `path` or
+    /// `cookie`.
+    pub fn add_parameter(&mut self, location: &str, name: &str, required: bool, schema: Value) {
+        self.parameters.push(json!({
+            "name": name,
+            "in": location,
+            "required": required,
+            "schema": schema,
+        }));
+    }
+
+    /// Adds a query parameter for each property of the object `schema`.
+    pub fn add_query_parameters(&mut self, components: &Components, schema: &Value) {
+        let schema = components.resolve(schema);
+        let required = schema["required"].as_array().cloned().unwrap_or_default();
+        if let Some(properties) = schema["properties"].as_object() {
+            for (name, property) in properties {
+                let required = required.iter().any(|r| r == name.as_str());
+                self.add_parameter("query", name, required, property.clone());
+            }
+        }
+    }
+
+    /// Sets the required request body.
+    pub fn set_request_body(&mut self, content_type: &str, schema: Value) {
+        self.request_body = Some(json!({
+            "required": true,
+            "content": { content_type: { "schema": schema } },
+        }));
+    }
+
+    /// Sets the body of a successful response, if it has one.
+    pub fn set_response_body(&mut self, content_type: &str, schema: Value) {
+        self.success = Some(json!({ content_type: { "schema": schema } }));
+    }
+
+    /// Adds an `application/problem+json` error response, for `status` or
+    /// for any other status when `None`.
+    pub fn add_problem(&mut self, components: &mut Components, status: Option<StatusCode>) {
+        let (key, description) = match status {
+            Some(status) => (
+                status.as_u16().to_string(),
+                status.canonical_reason().unwrap_or("Error"),
+            ),
+            None => ("default".to_string(), "Error"),
+        };
+        let schema = components.schema::<Problem>();
+        self.responses.insert(
+            key,
+            response(description.to_string(), Some((PROBLEM_JSON, schema))),
+        );
+    }
+
+    /// Fills in the path parameters and the defaults that depend on where
+    /// the operation is routed.
+    pub(crate) fn finish(&mut self, path: &str, components: &mut Components) {
+        for (status, description, schema) in std::mem::take(&mut self.extra) {
+            let schema = schema(components);
+            self.responses.insert(
+                status.as_u16().to_string(),
+                response(description, Some(("application/json", schema))),
+            );
+        }
+
+        let names = path_parameters(path);
+        let schema = self
+            .path
+            .take()
+            .map(|schema| components.resolve(&schema).clone());
+        let mut parameters = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            let schema = match &schema {
+                Some(schema) if schema.get("properties").is_some() => {
+                    schema["properties"].get(name).cloned()
+                }
+                Some(schema) if schema.get("prefixItems").is_some() => {
+                    schema["prefixItems"].get(i).cloned()
+                }
+                Some(schema) if i == 0 => Some(schema.clone()),
+                _ => None,
+            };
+            parameters.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": schema.unwrap_or_else(|| json!({ "type": "string" })),
+            }));
+        }
+        parameters.append(&mut self.parameters);
+        self.parameters = parameters;
+
+        if self.tags.is_empty() {
+            if let Some(segment) = path
+                .split('/')
+                .find(|s| !s.is_empty() && !s.starts_with('{'))
+            {
+                self.tags.push(segment.to_string());
+            }
+        }
+        if self.summary.is_none() {
+            self.summary = self.operation_id.as_deref().map(|id| {
+                let words = id.replace('_', " ");
+                let mut chars = words.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            });
+        }
+    }
+
+    /// Adds the parameters of the prefix the operation is nested under.
+    pub(crate) fn add_prefix(&mut self, prefix: &str) {
+        let mut parameters: Vec<Value> = path_parameters(prefix)
+            .into_iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                })
+            })
+            .collect();
+        parameters.append(&mut self.parameters);
+        self.parameters = parameters;
+    }
+
+    pub(crate) fn set_default_id(&mut self, id: Option<String>) {
+        if self.operation_id.is_none() {
+            self.operation_id = id;
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        let mut operation = Map::new();
+        if !self.tags.is_empty() {
+            operation.insert("tags".to_string(), json!(self.tags));
+        }
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".to_string(), json!(summary));
+        }
+        if let Some(description) = &self.description {
+            operation.insert("description".to_string(), json!(description));
+        }
+        if let Some(id) = &self.operation_id {
+            operation.insert("operationId".to_string(), json!(id));
+        }
+        if !self.parameters.is_empty() {
+            operation.insert("parameters".to_string(), json!(self.parameters));
+        }
+        if let Some(body) = &self.request_body {
+            operation.insert("requestBody".to_string(), body.clone());
+        }
+        let status = self.status.unwrap_or(StatusCode::OK);
+        let mut responses = Map::new();
+        let mut success = json!({
+            "description": status.canonical_reason().unwrap_or("Success"),
+        });
+        if let Some(content) = &self.success {
+            success["content"] = content.clone();
+        }
+        responses.insert(status.as_u16().to_string(), success);
+        for (key, response) in &self.responses {
+            responses.insert(key.clone(), response.clone());
+        }
+        operation.insert("responses".to_string(), Value::Object(responses));
+        if self.deprecated {
+            operation.insert("deprecated".to_string(), json!(true));
+        }
+        Value::Object(operation)
+    }
+}
+
+fn response(description: String, content: Option<(&str, Value)>) -> Value {
+    let mut response = json!({ "description": description });
+    if let Some((content_type, schema)) = content {
+        response["content"] = json!({ content_type: { "schema": schema } });
+    }
+    response
+}
+
+/// Names of the parameters of an OpenAPI path, e.g. `id` for `/users/{id}`.
+fn path_parameters(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The schema of problem details responses; see [`crate::error`].
+struct Problem;
+
+impl ApiSchema for Problem {
+    fn name() -> Option<Cow<'static, str>> {
+        Some("Problem".into())
+    }
+
+    fn schema(components: &mut Components) -> Value {
+        json!({
+            "type": "object",
+            "description": "An RFC 7807 problem details response.",
+            "properties": {
+                "type": { "type": "string" },
+                "title": { "type": "string" },
+                "status": { "type": "integer", "format": "int32" },
+                "detail": { "type": "string" },
+                "code": {
+                    "type": "string",
+                    "description": "Machine-readable error code, e.g. `unique_violation`.",
+                },
+                "trace_id": { "type": "string" },
+                "errors": {
+                    "type": "array",
+                    "description": "The invalid fields of a `validation_failed` problem.",
+                    "items": components.schema::<FieldError>(),
+                },
+            },
+            "required": ["type", "title", "status", "code"],
+        })
+    }
+}
+
+/// An extractor that documents what it reads from the request.
+///
+/// Every extractor of a handler routed through
+/// [`ApiRouter`](super::ApiRouter) implements it. For an application's own
+/// extractors, an empty implementation documents nothing:
+///
+/// ```rust,ignore
+/// impl OperationInput for CurrentUser {
+///     fn describe(operation: &mut Operation, components: &mut Components) {
+///         operation.add_problem(components, Some(StatusCode::UNAUTHORIZED));
+///     }
+/// }
+/// ```
+pub trait OperationInput {
+    /// Adds the parameters, request body or error responses of the
+    /// extractor to `operation`.
+    fn describe(_operation: &mut Operation, _components: &mut Components) {}
+}
+
+/// A handler's response type, which documents its responses.
+///
+/// Handlers routed through [`ApiRouter`](super::ApiRouter) must name their
+/// response type rather than return `impl IntoResponse`.
+pub trait OperationOutput {
+    /// Adds the successful response to `operation`.
+    fn describe(_operation: &mut Operation, _components: &mut Components) {}
+
+    /// Adds the responses of this type as the error of a `Result`.
+    fn describe_error(_operation: &mut Operation, _components: &mut Components) {}
+}
+
+macro_rules! undocumented_input {
+    ($($ty:ty),* $(,)?) => {
+        $(impl OperationInput for $ty {})*
+    };
+}
+
+undocumented_input!(
+    HeaderMap,
+    Method,
+    Uri,
+    OriginalUri,
+    MatchedPath,
+    RawPathParams,
+    Request,
+    Bytes,
+    String
+);
+
+impl<T> OperationInput for State<T> {}
+
+impl<T> OperationInput for Extension<T> {}
+
+impl<T> OperationInput for ConnectInfo<T> {}
+
+#[cfg(feature = "database")]
+impl OperationInput for crate::db::Tx {}
+
+impl<T: ApiSchema> OperationInput for Json<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        let schema = components.schema::<T>();
+        operation.set_request_body("application/json", schema);
+    }
+}
+
+impl<T: ApiSchema> OperationInput for ValidatedJson<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        <Json<T> as OperationInput>::describe(operation, components);
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ] {
+            operation.add_problem(components, Some(status));
+        }
+    }
+}
+
+impl<T: ApiSchema> OperationInput for Form<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        let schema = components.schema::<T>();
+        operation.set_request_body("application/x-www-form-urlencoded", schema);
+    }
+}
+
+impl<T: ApiSchema> OperationInput for Query<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        let schema = components.schema::<T>();
+        operation.add_query_parameters(components, &schema);
+    }
+}
+
+impl<T: ApiSchema> OperationInput for ValidatedQuery<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        Query::<T>::describe(operation, components);
+        operation.add_problem(components, Some(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+impl<T: ApiSchema> OperationInput for Path<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        operation.path = Some(components.schema::<T>());
+    }
+}
+
+impl<T: ApiSchema> OperationInput for ValidatedPath<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        Path::<T>::describe(operation, components);
+        operation.add_problem(components, Some(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+impl OperationInput for Pagination {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        let page = json!({ "type": "integer", "format": "int64", "minimum": 1 });
+        operation.add_parameter("query", "page", false, page.clone());
+        operation.add_parameter("query", "per_page", false, page);
+        operation.add_parameter("query", "cursor", false, json!({ "type": "string" }));
+        operation.add_problem(components, Some(StatusCode::BAD_REQUEST));
+    }
+}
+
+macro_rules! input_tuple {
+    ($($ty:ident),*) => {
+        impl<$($ty: OperationInput),*> OperationInput for ($($ty,)*) {
+            #[allow(unused_variables)]
+            fn describe(operation: &mut Operation, components: &mut Components) {
+                $($ty::describe(operation, components);)*
+            }
+        }
+    };
+}
+
+input_tuple!();
+input_tuple!(T1);
+input_tuple!(T1, T2);
+input_tuple!(T1, T2, T3);
+input_tuple!(T1, T2, T3, T4);
+input_tuple!(T1, T2, T3, T4, T5);
+input_tuple!(T1, T2, T3, T4, T5, T6);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+input_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+
+impl<T: ApiSchema> OperationOutput for Json<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        let schema = components.schema::<T>();
+        operation.set_response_body("application/json", schema);
+    }
+}
+
+impl<T: ApiSchema> OperationOutput for Page<T> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        <Json<Page<T>> as OperationOutput>::describe(operation, components);
+    }
+}
+
+impl<T> OperationOutput for Html<T> {
+    fn describe(operation: &mut Operation, _components: &mut Components) {
+        operation.set_response_body("text/html", json!({ "type": "string" }));
+    }
+}
+
+impl OperationOutput for String {
+    fn describe(operation: &mut Operation, _components: &mut Components) {
+        operation.set_response_body("text/plain", json!({ "type": "string" }));
+    }
+}
+
+impl OperationOutput for &'static str {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        <String as OperationOutput>::describe(operation, components);
+    }
+}
+
+impl OperationOutput for () {}
+
+impl OperationOutput for StatusCode {}
+
+impl OperationOutput for Response {}
+
+impl<R: OperationOutput> OperationOutput for (StatusCode, R) {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        R::describe(operation, components);
+    }
+
+    fn describe_error(operation: &mut Operation, components: &mut Components) {
+        R::describe_error(operation, components);
+    }
+}
+
+impl<T: OperationOutput, E: OperationOutput> OperationOutput for Result<T, E> {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        T::describe(operation, components);
+        E::describe_error(operation, components);
+    }
+}
+
+impl OperationOutput for SwordError {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        Self::describe_error(operation, components);
+    }
+
+    fn describe_error(operation: &mut Operation, components: &mut Components) {
+        operation.add_problem(components, None);
+    }
+}
+
+impl OperationOutput for ValidationErrors {
+    fn describe(operation: &mut Operation, components: &mut Components) {
+        Self::describe_error(operation, components);
+    }
+
+    fn describe_error(operation: &mut Operation, components: &mut Components) {
+        operation.add_problem(components, Some(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
@@ -0,0 +1,333 @@
+//! A router that documents its routes.
+
+use super::operation::{Operation, OperationInput, OperationOutput};
+use super::{Components, OpenApi};
+use axum::extract::Request;
+use axum::handler::Handler;
+use axum::response::IntoResponse;
+use axum::routing::{MethodRouter, Route};
+use axum::Router;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::future::Future;
+use tower::{Layer, Service};
+
+/// An [`axum::Router`] that also builds the [`OpenApi`] document of its
+/// routes.
+///
+/// Routes take the method routers of this module ([`get`], [`post`], ...),
+/// which describe each handler from its extractors and response type:
+///
+/// ```rust,ignore
+/// use sword_ai::openapi::{get, post_with, ApiRouter};
+///
+/// let api = ApiRouter::new()
+///     .route("/users", get(list_users).post_with(create_user, |op| op.status(StatusCode::CREATED)))
+///     .route("/users/:id", get(get_user));
+/// let openapi = api.openapi();
+/// let router: Router<AppState> = api.into_router();
+/// ```
+///
+/// Plain routers can be [merged](ApiRouter::merge) in to serve routes
+/// that are left out of the document.
+pub struct ApiRouter<S = ()> {
+    router: Router<S>,
+    paths: BTreeMap<String, BTreeMap<&'static str, Operation>>,
+    components: Components,
+}
+
+impl<S: Clone + Send + Sync + 'static> ApiRouter<S> {
+    /// An empty router.
+    pub fn new() -> Self {
+        Self {
+            router: Router::new(),
+            paths: BTreeMap::new(),
+            components: Components::default(),
+        }
+    }
+
+    /// Adds a route at `path`, in Axum's syntax (e.g. `/users/:id`).
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`Router::route`], e.g. if `path` is already routed.
+    pub fn route(mut self, path: &str, method_router: ApiMethodRouter<S>) -> Self {
+        self.router = self.router.route(path, method_router.router);
+        self.components.merge(method_router.components);
+        let path = openapi_path(path);
+        for (method, mut operation) in method_router.operations {
+            operation.finish(&path, &mut self.components);
+            self.paths
+                .entry(path.clone())
+                .or_default()
+                .insert(method, operation);
+        }
+        self
+    }
+
+    /// Serves the routes of `other` as well. A plain [`Router`] adds routes
+    /// without documenting them.
+    pub fn merge(mut self, other: impl Into<ApiRouter<S>>) -> Self {
+        let other = other.into();
+        self.router = self.router.merge(other.router);
+        self.components.merge(other.components);
+        for (path, operations) in other.paths {
+            self.paths.entry(path).or_default().extend(operations);
+        }
+        self
+    }
+
+    /// Serves the routes of `other` under `prefix`, e.g. `/api/v1`.
+    pub fn nest(mut self, prefix: &str, other: ApiRouter<S>) -> Self {
+        self.router = self.router.nest(prefix, other.router);
+        self.components.merge(other.components);
+        let prefix = openapi_path(prefix.trim_end_matches('/'));
+        for (path, mut operations) in other.paths {
+            for operation in operations.values_mut() {
+                operation.add_prefix(&prefix);
+            }
+            let path = match path.as_str() {
+                "/" => prefix.clone(),
+                path => format!("{}{}", prefix, path),
+            };
+            self.paths.entry(path).or_default().extend(operations);
+        }
+        self
+    }
+
+    /// Applies a middleware layer to every route, as [`Router::layer`].
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.router = self.router.layer(layer);
+        self
+    }
+
+    /// The document of the routes, to complete with
+    /// [`OpenApi::info`].
+    pub fn openapi(&self) -> OpenApi {
+        OpenApi::new(
+            self.paths
+                .iter()
+                .map(|(path, operations)| {
+                    let operations = operations
+                        .iter()
+                        .map(|(method, operation)| {
+                            (method.to_ascii_lowercase(), operation.to_json())
+                        })
+                        .collect();
+                    (path.clone(), operations)
+                })
+                .collect(),
+            self.components.clone(),
+        )
+    }
+
+    /// The routes, to serve with [`SwordAppBuilder::router`](crate::SwordAppBuilder::router).
+    pub fn into_router(self) -> Router<S> {
+        self.router
+    }
+}
+
+impl<S: Clone + Send + Sync + 'static> Default for ApiRouter<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> From<Router<S>> for ApiRouter<S> {
+    fn from(router: Router<S>) -> Self {
+        Self {
+            router,
+            paths: BTreeMap::new(),
+            components: Components::default(),
+        }
+    }
+}
+
+/// Turns Axum's `/users/:id` and `/files/*path` into `/users/{id}` and
+/// `/files/{path}`.
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix([':', '*']) {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A [`MethodRouter`] that documents its handlers; see [`ApiRouter`].
+pub struct ApiMethodRouter<S = ()> {
+    router: MethodRouter<S>,
+    operations: Vec<(&'static str, Operation)>,
+    components: Components,
+}
+
+/// A function whose arguments are the extractors `I` and whose future
+/// resolves to `O`. Implemented by every async function handler.
+pub trait OperationHandler<I, O> {}
+
+macro_rules! operation_handler {
+    ($($ty:ident),*) => {
+        impl<F, Fut, $($ty,)*> OperationHandler<($($ty,)*), Fut::Output> for F
+        where
+            F: FnOnce($($ty,)*) -> Fut,
+            Fut: Future,
+        {
+        }
+    };
+}
+
+operation_handler!();
+operation_handler!(T1);
+operation_handler!(T1, T2);
+operation_handler!(T1, T2, T3);
+operation_handler!(T1, T2, T3, T4);
+operation_handler!(T1, T2, T3, T4, T5);
+operation_handler!(T1, T2, T3, T4, T5, T6);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+operation_handler!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+
+/// The name of a function handler, e.g. `create_user`.
+fn handler_name<H>() -> Option<String> {
+    let name = std::any::type_name::<H>();
+    if name.contains("{{closure}}") {
+        return None;
+    }
+    name.rsplit("::").next().map(str::to_string)
+}
+
+impl<S: Clone + Send + Sync + 'static> ApiMethodRouter<S> {
+    fn add<H, T, I, O>(
+        mut self,
+        method: &'static str,
+        handler: &H,
+        transform: impl FnOnce(Operation) -> Operation,
+    ) -> Self
+    where
+        H: Handler<T, S> + OperationHandler<I, O>,
+        I: OperationInput,
+        O: OperationOutput,
+    {
+        let mut operation = Operation::default();
+        I::describe(&mut operation, &mut self.components);
+        O::describe(&mut operation, &mut self.components);
+        let _ = handler;
+        let mut operation = transform(operation);
+        operation.set_default_id(handler_name::<H>());
+        self.operations.push((method, operation));
+        self
+    }
+}
+
+macro_rules! method {
+    ($method:ident, $with:ident, $name:literal, $doc:literal) => {
+        #[doc = concat!("Routes `", $name, "` requests to `handler`, ", $doc)]
+        pub fn $method<H, T, I, O, S>(handler: H) -> ApiMethodRouter<S>
+        where
+            H: Handler<T, S> + OperationHandler<I, O>,
+            I: OperationInput,
+            O: OperationOutput,
+            T: 'static,
+            S: Clone + Send + Sync + 'static,
+        {
+            $with(handler, |operation| operation)
+        }
+
+        #[doc = concat!("Like [`", stringify!($method), "`], adjusting the inferred operation with `transform`.")]
+        pub fn $with<H, T, I, O, S, F>(handler: H, transform: F) -> ApiMethodRouter<S>
+        where
+            H: Handler<T, S> + OperationHandler<I, O>,
+            I: OperationInput,
+            O: OperationOutput,
+            T: 'static,
+            S: Clone + Send + Sync + 'static,
+            F: FnOnce(Operation) -> Operation,
+        {
+            ApiMethodRouter {
+                router: MethodRouter::new(),
+                operations: Vec::new(),
+                components: Components::default(),
+            }
+            .$with(handler, transform)
+        }
+
+        impl<S: Clone + Send + Sync + 'static> ApiMethodRouter<S> {
+            #[doc = concat!("Also routes `", $name, "` requests to `handler`.")]
+            pub fn $method<H, T, I, O>(self, handler: H) -> Self
+            where
+                H: Handler<T, S> + OperationHandler<I, O>,
+                I: OperationInput,
+                O: OperationOutput,
+                T: 'static,
+            {
+                self.$with(handler, |operation| operation)
+            }
+
+            #[doc = concat!("Like [`ApiMethodRouter::", stringify!($method), "`], adjusting the inferred operation with `transform`.")]
+            pub fn $with<H, T, I, O, F>(self, handler: H, transform: F) -> Self
+            where
+                H: Handler<T, S> + OperationHandler<I, O>,
+                I: OperationInput,
+                O: OperationOutput,
+                T: 'static,
+                F: FnOnce(Operation) -> Operation,
+            {
+                let mut this = self.add($name, &handler, transform);
+                this.router = this.router.$method(handler);
+                this
+            }
+        }
+    };
+}
+
+method!(
+    get,
+    get_with,
+    "GET",
+    "documented from its extractors and response."
+);
+method!(
+    post,
+    post_with,
+    "POST",
+    "documented from its extractors and response."
+);
+method!(
+    put,
+    put_with,
+    "PUT",
+    "documented from its extractors and response."
+);
+method!(
+    patch,
+    patch_with,
+    "PATCH",
+    "documented from its extractors and response."
+);
+method!(
+    delete,
+    delete_with,
+    "DELETE",
+    "documented from its extractors and response."
+);
+
+impl<S> From<ApiMethodRouter<S>> for MethodRouter<S> {
+    fn from(router: ApiMethodRouter<S>) -> Self {
+        router.router
+    }
+}
@@ -0,0 +1,528 @@
+//! JSON Schemas of request and response types.
+
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A type with a JSON Schema, describing its serde representation.
+///
+/// Derive it with `#[derive(ApiSchema)]`, or implement it by hand:
+///
+/// ```rust,ignore
+/// use sword_ai::openapi::{ApiSchema, Components};
+///
+/// impl ApiSchema for Money {
+///     fn schema(_: &mut Components) -> serde_json::Value {
+///         serde_json::json!({ "type": "string", "pattern": "^[0-9]+\\.[0-9]{2}$" })
+///     }
+/// }
+/// ```
+pub trait ApiSchema {
+    /// Whether a field of this type may be left out, as for `Option`.
+    const OPTIONAL: bool = false;
+
+    /// Name under `#/components/schemas`, or `None` to inline the schema
+    /// where the type is used.
+    fn name() -> Option<Cow<'static, str>> {
+        None
+    }
+
+    /// The JSON Schema of the type. Refer to other types with
+    /// [`Components::schema`].
+    fn schema(components: &mut Components) -> Value;
+}
+
+/// The named schemas of a document, under `#/components/schemas`.
+#[derive(Debug, Clone, Default)]
+pub struct Components {
+    schemas: BTreeMap<String, Value>,
+}
+
+impl Components {
+    /// The schema of `T`: a `$ref` to its named schema, which is added on
+    /// first use, or the schema itself for unnamed types.
+    ///
+    /// Types are told apart by [`ApiSchema::name`] only, so two named
+    /// types must not share a name.
+    pub fn schema<T: ApiSchema + ?Sized>(&mut self) -> Value {
+        let Some(name) = T::name() else {
+            return T::schema(self);
+        };
+        if !self.schemas.contains_key(name.as_ref()) {
+            // A placeholder ends the recursion of self-referencing types.
+            self.schemas.insert(name.to_string(), Value::Null);
+            let schema = T::schema(self);
+            self.schemas.insert(name.to_string(), schema);
+        }
+        json!({ "$ref": format!("#/components/schemas/{}", name) })
+    }
+
+    /// The named schema `name`.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.schemas.get(name)
+    }
+
+    /// Follows `schema` if it is a `$ref` to one of these schemas.
+    pub(crate) fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|path| path.strip_prefix("#/components/schemas/"))
+            .and_then(|name| self.schemas.get(name))
+            .unwrap_or(schema)
+    }
+
+    pub(crate) fn merge(&mut self, other: Components) {
+        self.schemas.extend(other.schemas);
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({ "schemas": self.schemas })
+    }
+}
+
+/// Allows `null` besides the values of `schema`.
+fn nullable(schema: Value) -> Value {
+    match schema {
+        Value::Object(mut object)
+            if object.get("type").is_some_and(Value::is_string)
+                && !object.contains_key("enum")
+                && !object.contains_key("const") =>
+        {
+            let ty = object.remove("type").unwrap_or_default();
+            object.insert("type".to_string(), json!([ty, "null"]));
+            Value::Object(object)
+        }
+        schema => json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+}
+
+macro_rules! primitive {
+    ($($ty:ty => $schema:tt,)*) => {
+        $(
+            impl ApiSchema for $ty {
+                fn schema(_: &mut Components) -> Value {
+                    json!($schema)
+                }
+            }
+        )*
+    };
+}
+
+primitive! {
+    bool => { "type": "boolean" },
+    i8 => { "type": "integer", "format": "int32" },
+    i16 => { "type": "integer", "format": "int32" },
+    i32 => { "type": "integer", "format": "int32" },
+    i64 => { "type": "integer", "format": "int64" },
+    isize => { "type": "integer", "format": "int64" },
+    i128 => { "type": "integer" },
+    u8 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u16 => { "type": "integer", "format": "int32", "minimum": 0 },
+    u32 => { "type": "integer", "format": "int64", "minimum": 0 },
+    u64 => { "type": "integer", "format": "int64", "minimum": 0 },
+    usize => { "type": "integer", "format": "int64", "minimum": 0 },
+    u128 => { "type": "integer", "minimum": 0 },
+    f32 => { "type": "number", "format": "float" },
+    f64 => { "type": "number", "format": "double" },
+    char => { "type": "string", "minLength": 1, "maxLength": 1 },
+    str => { "type": "string" },
+    String => { "type": "string" },
+    () => { "type": "null" },
+    Value => {},
+    serde_json::Map<String, Value> => { "type": "object" },
+}
+
+#[cfg(feature = "database")]
+primitive! {
+    sea_orm::prelude::ChronoDateTimeUtc => { "type": "string", "format": "date-time" },
+    sea_orm::prelude::ChronoDateTimeLocal => { "type": "string", "format": "date-time" },
+    sea_orm::prelude::ChronoDateTimeWithTimeZone => { "type": "string", "format": "date-time" },
+    sea_orm::prelude::ChronoDateTime => { "type": "string", "format": "date-time" },
+    sea_orm::prelude::ChronoDate => { "type": "string", "format": "date" },
+    sea_orm::prelude::ChronoTime => { "type": "string", "format": "time" },
+    sea_orm::prelude::Decimal => { "type": "string", "format": "decimal" },
+    sea_orm::prelude::Uuid => { "type": "string", "format": "uuid" },
+}
+
+impl<T: ApiSchema> ApiSchema for Option<T> {
+    const OPTIONAL: bool = true;
+
+    fn schema(components: &mut Components) -> Value {
+        nullable(components.schema::<T>())
+    }
+}
+
+macro_rules! wrapper {
+    ($($ty:ty),*) => {
+        $(
+            impl<T: ApiSchema + ?Sized> ApiSchema for $ty {
+                const OPTIONAL: bool = T::OPTIONAL;
+
+                fn name() -> Option<Cow<'static, str>> {
+                    T::name()
+                }
+
+                fn schema(components: &mut Components) -> Value {
+                    T::schema(components)
+                }
+            }
+        )*
+    };
+}
+
+wrapper!(&T, Box<T>, Rc<T>, Arc<T>);
+
+impl<T: ApiSchema + ToOwned + ?Sized> ApiSchema for Cow<'_, T> {
+    const OPTIONAL: bool = T::OPTIONAL;
+
+    fn name() -> Option<Cow<'static, str>> {
+        T::name()
+    }
+
+    fn schema(components: &mut Components) -> Value {
+        T::schema(components)
+    }
+}
+
+macro_rules! array {
+    ($($ty:ty => $unique:expr),*) => {
+        $(
+            impl<T: ApiSchema> ApiSchema for $ty {
+                fn schema(components: &mut Components) -> Value {
+                    let mut schema = json!({ "type": "array", "items": components.schema::<T>() });
+                    if $unique {
+                        schema["uniqueItems"] = true.into();
+                    }
+                    schema
+                }
+            }
+        )*
+    };
+}
+
+array!(
+    [T] => false,
+    Vec<T> => false,
+    VecDeque<T> => false,
+    BTreeSet<T> => true,
+    HashSet<T> => true
+);
+
+impl<T: ApiSchema, const N: usize> ApiSchema for [T; N] {
+    fn schema(components: &mut Components) -> Value {
+        json!({
+            "type": "array",
+            "items": components.schema::<T>(),
+            "minItems": N,
+            "maxItems": N,
+        })
+    }
+}
+
+impl<K, V: ApiSchema> ApiSchema for BTreeMap<K, V> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "object", "additionalProperties": components.schema::<V>() })
+    }
+}
+
+impl<K, V: ApiSchema, H> ApiSchema for HashMap<K, V, H> {
+    fn schema(components: &mut Components) -> Value {
+        json!({ "type": "object", "additionalProperties": components.schema::<V>() })
+    }
+}
+
+macro_rules! tuple {
+    ($($len:literal => ($($ty:ident),*)),*) => {
+        $(
+            impl<$($ty: ApiSchema),*> ApiSchema for ($($ty,)*) {
+                fn schema(components: &mut Components) -> Value {
+                    json!({
+                        "type": "array",
+                        "prefixItems": [$(components.schema::<$ty>()),*],
+                        "minItems": $len,
+                        "maxItems": $len,
+                    })
+                }
+            }
+        )*
+    };
+}
+
+tuple!(1 => (A), 2 => (A, B), 3 => (A, B, C), 4 => (A, B, C, D));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openapi::ApiSchema;
+
+    /// A line of an order.
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(rename_all = "camelCase")]
+    struct OrderLine {
+        /// Stock keeping unit.
+        sku_code: String,
+        quantity: u32,
+        #[serde(default)]
+        gift: bool,
+        note: Option<String>,
+        #[serde(skip)]
+        _internal: (),
+        status: Status,
+        children: Vec<OrderLine>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Status {
+        InStock,
+        Backordered,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(tag = "kind")]
+    enum Payment {
+        Card { last4: String },
+        Cash,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, serde::Deserialize, ApiSchema)]
+    struct Account {
+        email: String,
+        #[serde(skip_serializing)]
+        password: String,
+        #[serde(skip_deserializing)]
+        created_at: i64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        nickname: Option<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(tag = "type", content = "data", rename_all = "snake_case")]
+    enum Event {
+        Created { id: i64 },
+        Renamed(String),
+        Deleted,
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(untagged)]
+    enum Key {
+        Id(i64),
+        Slug(String),
+    }
+
+    #[derive(serde::Serialize, ApiSchema)]
+    struct Audited {
+        #[serde(flatten)]
+        account: Account,
+        by: String,
+    }
+
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(transparent)]
+    struct Sku(String);
+
+    #[derive(serde::Serialize, ApiSchema)]
+    #[serde(transparent)]
+    struct Tagged {
+        tags: Vec<String>,
+        #[serde(skip)]
+        _cache: (),
+    }
+
+    #[allow(dead_code)]
+    #[derive(serde::Serialize, ApiSchema)]
+    enum Shape {
+        #[serde(rename_all = "camelCase")]
+        Rect { top_left: u32, bottom_right: u32 },
+        #[serde(skip_serializing)]
+        Hidden,
+    }
+
+    #[test]
+    fn test_derive() {
+        let mut components = Components::default();
+        assert_eq!(
+            components.schema::<Vec<OrderLine>>(),
+            json!({ "type": "array", "items": { "$ref": "#/components/schemas/OrderLine" } })
+        );
+        assert_eq!(
+            components.get("OrderLine").unwrap(),
+            &json!({
+                "type": "object",
+                "description": "A line of an order.",
+                "properties": {
+                    "skuCode": { "type": "string", "description": "Stock keeping unit." },
+                    "quantity": { "type": "integer", "format": "int64", "minimum": 0 },
+                    "gift": { "type": "boolean" },
+                    "note": { "type": ["string", "null"] },
+                    "status": { "$ref": "#/components/schemas/Status" },
+                    "children": {
+                        "type": "array",
+                        "items": { "$ref": "#/components/schemas/OrderLine" },
+                    },
+                },
+                "required": ["skuCode", "quantity", "status", "children"],
+            })
+        );
+        assert_eq!(
+            components.get("Status").unwrap(),
+            &json!({ "type": "string", "enum": ["in_stock", "backordered"] })
+        );
+
+        components.schema::<Payment>();
+        assert_eq!(
+            components.get("Payment").unwrap()["oneOf"][0],
+            json!({
+                "type": "object",
+                "properties": {
+                    "kind": { "const": "Card" },
+                    "last4": { "type": "string" },
+                },
+                "required": ["kind", "last4"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_skipped_fields() {
+        let mut components = Components::default();
+        components.schema::<Account>();
+        assert_eq!(
+            components.get("Account").unwrap(),
+            &json!({
+                "type": "object",
+                "properties": {
+                    "email": { "type": "string" },
+                    "nickname": { "type": ["string", "null"] },
+                },
+                "required": ["email"],
+            })
+        );
+
+        components.schema::<Shape>();
+        assert_eq!(
+            components.get("Shape").unwrap()["oneOf"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_adjacently_tagged() {
+        let mut components = Components::default();
+        components.schema::<Event>();
+        assert_eq!(
+            components.get("Event").unwrap(),
+            &json!({
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": { "const": "created" },
+                            "data": {
+                                "type": "object",
+                                "properties": { "id": { "type": "integer", "format": "int64" } },
+                                "required": ["id"],
+                            },
+                        },
+                        "required": ["type", "data"],
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": { "const": "renamed" },
+                            "data": { "type": "string" },
+                        },
+                        "required": ["type", "data"],
+                    },
+                    {
+                        "type": "object",
+                        "properties": { "type": { "const": "deleted" } },
+                        "required": ["type"],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_untagged() {
+        let mut components = Components::default();
+        components.schema::<Key>();
+        assert_eq!(
+            components.get("Key").unwrap(),
+            &json!({
+                "oneOf": [
+                    { "type": "integer", "format": "int64" },
+                    { "type": "string" },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut components = Components::default();
+        components.schema::<Audited>();
+        assert_eq!(
+            components.get("Audited").unwrap(),
+            &json!({
+                "allOf": [
+                    {
+                        "type": "object",
+                        "properties": { "by": { "type": "string" } },
+                        "required": ["by"],
+                    },
+                    { "$ref": "#/components/schemas/Account" },
+                ]
+            })
+        );
+        assert!(components.get("Account").is_some());
+    }
+
+    #[test]
+    fn test_transparent() {
+        let mut components = Components::default();
+        assert_eq!(components.schema::<Sku>(), json!({ "type": "string" }));
+        assert_eq!(
+            components.schema::<Tagged>(),
+            json!({ "type": "array", "items": { "type": "string" } })
+        );
+        assert!(components.get("Sku").is_none());
+    }
+
+    #[test]
+    fn test_variant_rename_all() {
+        let mut components = Components::default();
+        components.schema::<Shape>();
+        assert_eq!(
+            components.get("Shape").unwrap()["oneOf"][0],
+            json!({
+                "type": "object",
+                "properties": {
+                    "Rect": {
+                        "type": "object",
+                        "properties": {
+                            "topLeft": { "type": "integer", "format": "int64", "minimum": 0 },
+                            "bottomRight": { "type": "integer", "format": "int64", "minimum": 0 },
+                        },
+                        "required": ["topLeft", "bottomRight"],
+                    },
+                },
+                "required": ["Rect"],
+                "additionalProperties": false,
+            })
+        );
+    }
+}
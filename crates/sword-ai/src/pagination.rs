@@ -0,0 +1,467 @@
+//! Pagination module.
+//!
+//! Provides the [`Pagination`] extractor, which reads `page`, `per_page`
+//! and `cursor` from the query string, and the [`Page`] envelope that list
+//! endpoints return:
+//!
+//! ```json
+//! {
+//!   "items": [...],
+//!   "total": 42,
+//!   "next": "/posts?status=draft&per_page=20&page=3",
+//!   "prev": "/posts?status=draft&per_page=20&page=1"
+//! }
+//! ```
+//!
+//! `next` and `prev` are `null` on the last and first page. They keep the
+//! request's other query parameters, so filters carry over.
+//!
+//! ## Offset and Keyset Pages
+//!
+//! - **Offset** (`?page=3&per_page=20`): pages are numbered and `total`
+//!   counts the matching rows. Rows inserted while a client walks the
+//!   list shift the following pages, and deep pages get slower. See
+//!   [`CrudRepository::paginate`](crate::db::CrudRepository::paginate).
+//! - **Keyset** (`?cursor=...`): clients follow the `next` and `prev`
+//!   links, which carry an opaque cursor. Pages stay stable under inserts
+//!   and cost the same however deep they are, but there is no `total`.
+//!   See [`CrudRepository::paginate_keyset`](crate::db::CrudRepository::paginate_keyset).
+//!
+//! The handler picks the mode; the other query parameter is ignored.
+//!
+//! ## Page Size
+//!
+//! `per_page` defaults to `PAGINATION_DEFAULT_SIZE` (20) and is capped at
+//! `PAGINATION_MAX_SIZE` (100). A `page` or `per_page` that is not a
+//! positive integer is rejected with a `400 Bad Request`
+//! [problem](crate::error) with code `invalid_pagination`.
+//!
+//! ## Cursors
+//!
+//! A cursor holds the key of the first or last item of a page, signed with
+//! HMAC-SHA256 and bound to the endpoint's path. Cursors that were tampered
+//! with, or that come from another endpoint, are rejected with
+//! `400 Bad Request`.
+//!
+//! The signing key is `PAGINATION_CURSOR_SECRET`. Without it a random key
+//! is generated at startup, so cursors stop working after a restart and
+//! are not accepted by other instances: set it in production.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use sword_ai::db::{CrudRepository, ListQuery};
+//! use sword_ai::pagination::{Page, Pagination};
+//!
+//! async fn list_posts(
+//!     State(state): State<AppState>,
+//!     pagination: Pagination,
+//! ) -> Result<Page<PostResponse>, SwordError> {
+//!     let page = state
+//!         .post_repository
+//!         .paginate(state.db.reader(), ListQuery::new(), &pagination)
+//!         .await?;
+//!     Ok(page.map(Into::into))
+//! }
+//! ```
+
+use crate::config::AppConfig;
+use crate::error::SwordError;
+use crate::openapi::ApiSchema;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, OriginalUri};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Length of the signature at the end of a cursor.
+const TAG_LEN: usize = 32;
+
+/// Page size limits and cursor key, read from [`AppConfig`].
+///
+/// [`SwordApp`](crate::SwordApp) makes it available to every request; the
+/// [`Pagination`] extractor falls back to the defaults on routers served
+/// without it.
+#[derive(Clone)]
+pub struct PaginationConfig {
+    default_size: u64,
+    max_size: u64,
+    key: Arc<[u8]>,
+}
+
+impl PaginationConfig {
+    /// Builds the settings from `PAGINATION_*` in `config`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let secret = config.pagination_cursor_secret.expose();
+        let key: Arc<[u8]> = if secret.is_empty() {
+            Arc::from(random_key().as_slice())
+        } else {
+            Arc::from(secret.as_bytes())
+        };
+        Self {
+            default_size: config.pagination_default_size,
+            max_size: config.pagination_max_size.max(1),
+            key,
+        }
+    }
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self::from_config(&AppConfig::default())
+    }
+}
+
+impl fmt::Debug for PaginationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaginationConfig")
+            .field("default_size", &self.default_size)
+            .field("max_size", &self.max_size)
+            .field("key", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Key used when `PAGINATION_CURSOR_SECRET` is not set, the same for the
+/// whole process.
+fn random_key() -> &'static [u8; 32] {
+    static KEY: OnceLock<[u8; 32]> = OnceLock::new();
+    KEY.get_or_init(|| {
+        let mut key = [0u8; 32];
+        getrandom::getrandom(&mut key).expect("the OS random number generator is available");
+        key
+    })
+}
+
+/// Position of a keyset page, relative to the item with key `K`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cursor<K> {
+    /// The items following `K`.
+    After(K),
+    /// The items preceding `K`.
+    Before(K),
+}
+
+/// Extractor for the page a list endpoint should return.
+///
+/// Reads `page` (starting at 1, default 1), `per_page` and `cursor` from
+/// the query string. See the [module documentation](self) for the two
+/// modes and the limits that apply.
+#[derive(Debug, Clone)]
+pub struct Pagination {
+    /// Requested page number, starting at 1.
+    pub page: u64,
+    /// Items per page, at most `PAGINATION_MAX_SIZE`.
+    pub per_page: u64,
+    /// Verified cursor payload, if one was given.
+    cursor: Option<serde_json::Value>,
+    /// Request path, which cursors are bound to.
+    path: String,
+    /// Query parameters other than the pagination ones, kept in links.
+    params: Vec<(String, String)>,
+    config: PaginationConfig,
+}
+
+/// Rejection returned when a [`Pagination`] cannot be extracted.
+#[derive(Debug, thiserror::Error)]
+pub enum PaginationRejection {
+    /// The query string could not be parsed.
+    #[error("invalid query string")]
+    InvalidQuery,
+    /// `page` or `per_page` is not a positive integer.
+    #[error("invalid `{0}`: expected a positive integer")]
+    InvalidNumber(&'static str),
+    /// The cursor is malformed, was tampered with or belongs to another
+    /// endpoint.
+    #[error("invalid cursor")]
+    InvalidCursor,
+}
+
+impl IntoResponse for PaginationRejection {
+    fn into_response(self) -> Response {
+        SwordError::bad_request(self.to_string())
+            .with_code("invalid_pagination")
+            .into_response()
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Pagination {
+    type Rejection = PaginationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let config = parts
+            .extensions
+            .get::<PaginationConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let uri = match parts.extensions.get::<OriginalUri>() {
+            Some(OriginalUri(uri)) => uri,
+            None => &parts.uri,
+        };
+        let query: Vec<(String, String)> = serde_urlencoded::from_str(uri.query().unwrap_or(""))
+            .map_err(|_| PaginationRejection::InvalidQuery)?;
+        Pagination::from_query(config, uri.path(), query)
+    }
+}
+
+impl Pagination {
+    fn from_query(
+        config: PaginationConfig,
+        path: &str,
+        query: Vec<(String, String)>,
+    ) -> Result<Self, PaginationRejection> {
+        let mut pagination = Self {
+            page: 1,
+            per_page: config.default_size.min(config.max_size),
+            cursor: None,
+            path: path.to_string(),
+            params: Vec::new(),
+            config,
+        };
+        for (name, value) in query {
+            match name.as_str() {
+                "page" => pagination.page = positive(&value, "page")?,
+                "per_page" => {
+                    pagination.per_page =
+                        positive(&value, "per_page")?.min(pagination.config.max_size)
+                }
+                "cursor" if value.is_empty() => {}
+                "cursor" => {
+                    let payload = verify(&pagination.config.key, path, &value)
+                        .ok_or(PaginationRejection::InvalidCursor)?;
+                    pagination.cursor = Some(
+                        serde_json::from_slice(&payload)
+                            .map_err(|_| PaginationRejection::InvalidCursor)?,
+                    );
+                }
+                _ => pagination.params.push((name, value)),
+            }
+        }
+        Ok(pagination)
+    }
+
+    /// Number of items before the requested page, in offset mode.
+    pub fn offset(&self) -> u64 {
+        self.page.saturating_sub(1).saturating_mul(self.per_page)
+    }
+
+    /// The requested keyset position, or `None` for the first page.
+    ///
+    /// A cursor whose key does not deserialize as `K` (e.g. issued before
+    /// the key type changed) also starts over from the first page.
+    pub fn cursor<K: DeserializeOwned>(&self) -> Option<Cursor<K>> {
+        self.cursor
+            .clone()
+            .and_then(|cursor| serde_json::from_value(cursor).ok())
+    }
+
+    /// Builds an offset page from the items of the requested page and the
+    /// total number of items.
+    pub fn page_of<T>(&self, items: Vec<T>, total: u64) -> Page<T> {
+        let last = total.div_ceil(self.per_page).max(1);
+        let next = (self.page < last).then(|| self.link("page", (self.page + 1).to_string()));
+        let prev =
+            (self.page > 1).then(|| self.link("page", (self.page - 1).min(last).to_string()));
+        Page {
+            items,
+            total: Some(total),
+            next,
+            prev,
+        }
+    }
+
+    /// Builds a keyset page from at most `per_page` items, in key order.
+    ///
+    /// `has_more` tells whether more items were found past the requested
+    /// ones, in the direction of the [`cursor`](Self::cursor): after them
+    /// for the first page and `Cursor::After`, before them for
+    /// `Cursor::Before`. `key` returns the key that cursors are built from.
+    pub fn keyset_page<T, K>(&self, items: Vec<T>, has_more: bool, key: impl Fn(&T) -> K) -> Page<T>
+    where
+        K: Serialize + DeserializeOwned,
+    {
+        let (has_next, has_prev) = match self.cursor::<K>() {
+            None => (has_more, false),
+            Some(Cursor::After(_)) => (has_more, true),
+            Some(Cursor::Before(_)) => (true, has_more),
+        };
+        let next = items
+            .last()
+            .filter(|_| has_next)
+            .map(|item| self.cursor_link(&Cursor::After(key(item))));
+        let prev = items
+            .first()
+            .filter(|_| has_prev)
+            .map(|item| self.cursor_link(&Cursor::Before(key(item))));
+        Page {
+            items,
+            total: None,
+            next,
+            prev,
+        }
+    }
+
+    fn cursor_link<K: Serialize>(&self, cursor: &Cursor<K>) -> String {
+        let payload = serde_json::to_vec(cursor).expect("cursor keys serialize to JSON");
+        self.link("cursor", sign(&self.config.key, &self.path, &payload))
+    }
+
+    /// Link to the same endpoint and filters with `name` set to `value`.
+    fn link(&self, name: &str, value: String) -> String {
+        let per_page = self.per_page.to_string();
+        let params = self
+            .params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain([("per_page", per_page.as_str()), (name, value.as_str())]);
+        let query = serde_urlencoded::to_string(params.collect::<Vec<_>>())
+            .expect("string pairs are URL-encodable");
+        format!("{}?{}", self.path, query)
+    }
+}
+
+fn positive(value: &str, name: &'static str) -> Result<u64, PaginationRejection> {
+    value
+        .parse()
+        .ok()
+        .filter(|&n: &u64| n > 0)
+        .ok_or(PaginationRejection::InvalidNumber(name))
+}
+
+fn mac(key: &[u8], path: &str, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(path.as_bytes());
+    mac.update(&[0]);
+    mac.update(payload);
+    mac
+}
+
+/// Encodes `payload` followed by its signature for `path`.
+fn sign(key: &[u8], path: &str, payload: &[u8]) -> String {
+    let mut token = payload.to_vec();
+    token.extend_from_slice(&mac(key, path, payload).finalize().into_bytes());
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+/// Returns the payload of a cursor signed for `path`.
+fn verify(key: &[u8], path: &str, token: &str) -> Option<Vec<u8>> {
+    let mut token = URL_SAFE_NO_PAD.decode(token).ok()?;
+    let at = token.len().checked_sub(TAG_LEN)?;
+    let tag = token.split_off(at);
+    mac(key, path, &token).verify_slice(&tag).ok()?;
+    Some(token)
+}
+
+/// A page of items with links to its neighbours.
+///
+/// Responds with the page as JSON. `total` is only present for offset
+/// pages; `next` and `prev` are `null` when there is no such page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ApiSchema)]
+pub struct Page<T> {
+    /// Items of the page.
+    pub items: Vec<T>,
+    /// Number of items across all pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Link to the next page.
+    pub next: Option<String>,
+    /// Link to the previous page.
+    pub prev: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Converts the items with `f`, keeping the total and links, e.g. from
+    /// domain types to responses.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next: self.next,
+            prev: self.prev,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Page<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pagination(query: &str) -> Result<Pagination, PaginationRejection> {
+        let config = PaginationConfig {
+            default_size: 20,
+            max_size: 50,
+            key: Arc::from(b"secret".as_slice()),
+        };
+        Pagination::from_query(config, "/posts", serde_urlencoded::from_str(query).unwrap())
+    }
+
+    #[test]
+    fn test_offset_pages() {
+        let p = pagination("status=draft&page=2&per_page=500").unwrap();
+        assert_eq!((p.page, p.per_page, p.offset()), (2, 50, 50));
+        let page = p.page_of(vec![1, 2], 120);
+        assert_eq!(
+            page.next.as_deref(),
+            Some("/posts?status=draft&per_page=50&page=3")
+        );
+        assert_eq!(
+            page.prev.as_deref(),
+            Some("/posts?status=draft&per_page=50&page=1")
+        );
+        assert_eq!(p.page_of(Vec::<i32>::new(), 100).next, None);
+        assert!(matches!(
+            pagination("page=0"),
+            Err(PaginationRejection::InvalidNumber("page"))
+        ));
+    }
+
+    #[test]
+    fn test_cursor_links() {
+        let first = pagination("per_page=2").unwrap();
+        let page = first.keyset_page(vec![1i64, 2], true, |n| *n);
+        assert_eq!(page.prev, None);
+
+        let next = page.next.unwrap();
+        let query = next.split_once('?').unwrap().1;
+        let second = pagination(query).unwrap();
+        assert_eq!(second.cursor::<i64>(), Some(Cursor::After(2)));
+        let page = second.keyset_page(vec![3i64], false, |n| *n);
+        assert_eq!(page.next, None);
+        let prev = page.prev.unwrap();
+        let third = pagination(prev.split_once('?').unwrap().1).unwrap();
+        assert_eq!(third.cursor::<i64>(), Some(Cursor::Before(3)));
+
+        let cursor = query.rsplit_once("cursor=").unwrap().1;
+        let mut tampered = URL_SAFE_NO_PAD.decode(cursor).unwrap();
+        tampered[10] ^= 1;
+        let tampered = format!("cursor={}", URL_SAFE_NO_PAD.encode(tampered));
+        assert!(matches!(
+            pagination(&tampered),
+            Err(PaginationRejection::InvalidCursor)
+        ));
+        let config = PaginationConfig {
+            key: Arc::from(b"secret".as_slice()),
+            ..Default::default()
+        };
+        let other = serde_urlencoded::from_str(query).unwrap();
+        assert!(Pagination::from_query(config, "/users", other).is_err());
+    }
+}
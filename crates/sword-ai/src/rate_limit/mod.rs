@@ -0,0 +1,799 @@
+//! Rate limiting module.
+//!
+//! A [`RateLimit`] counts the requests of each client against a [`Quota`]
+//! and answers `429 Too Many Requests` problems, code `rate_limited`, past
+//! it. Every limited response carries the `RateLimit-Limit`,
+//! `RateLimit-Remaining`, `RateLimit-Reset` and `RateLimit-Policy`
+//! headers, and `429`s a `Retry-After` header.
+//!
+//! ## Global Limit
+//!
+//! Setting `RATE_LIMIT_REQUESTS` limits every route of a
+//! [`SwordApp`](crate::SwordApp), except the health and metrics endpoints:
+//!
+//! ```bash
+//! RATE_LIMIT_REQUESTS=100
+//! RATE_LIMIT_PERIOD=60
+//! RATE_LIMIT_ALGORITHM=sliding_window
+//! ```
+//!
+//! Routes get their own quota, or none, with
+//! [`SwordAppBuilder::rate_limit`](crate::SwordAppBuilder::rate_limit):
+//!
+//! ```rust,ignore
+//! use sword_ai::rate_limit::Quota;
+//!
+//! SwordApp::builder()
+//!     .rate_limit(|limit| {
+//!         limit
+//!             .route("/auth/login", Quota::per_minute(5))
+//!             .exempt("/webhooks/*rest")
+//!     })
+//!     .router(build_router)
+//!     .serve()
+//!     .await?;
+//! ```
+//!
+//! A `RateLimit` is also a layer, to apply to any router:
+//!
+//! ```rust,ignore
+//! let api = Router::new()
+//!     .route("/search", get(search))
+//!     .layer(RateLimit::new(Quota::per_second(10)).key_by(KeyBy::User));
+//! ```
+//!
+//! ## Clients
+//!
+//! Clients are told apart by [`KeyBy`]: their IP address, the
+//! [`AuthenticatedApiKey`] or [`AuthenticatedUser`] set by an
+//! authentication layer, or a custom function. Keys and users are only
+//! trusted once authenticated, so that a client cannot escape its limit by
+//! sending a new key with every request. Behind a load balancer or reverse proxy, list its
+//! addresses in `RATE_LIMIT_TRUSTED_PROXIES` so the client IP is read from
+//! `X-Forwarded-For`; the header is ignored on connections from anywhere
+//! else.
+//!
+//! ## Algorithms
+//!
+//! - [`Algorithm::TokenBucket`] allows bursts of up to `limit` requests,
+//!   then refills at `limit` per period.
+//! - [`Algorithm::SlidingWindow`] allows `limit` requests in any period,
+//!   approximated from the counts of the current and previous windows.
+//!
+//! State lives in a [`MemoryStore`] unless another [`RateLimitStore`] is
+//! set, e.g. one shared by every instance of the service.
+
+mod store;
+
+pub use store::{Decision, MemoryStore, RateLimitStore};
+
+use crate::config::AppConfig;
+use crate::error::SwordError;
+use crate::metrics::{Exemplar, Registry};
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// Name of the rate-limited request counter in the metrics registry.
+const RATE_LIMITED: &str = "sword_http_rate_limited";
+
+/// How requests are counted over time (`RATE_LIMIT_ALGORITHM`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// A bucket of `limit` tokens refilled over the period (`token_bucket`).
+    #[default]
+    TokenBucket,
+    /// A window of one period sliding with time (`sliding_window`).
+    SlidingWindow,
+}
+
+/// Requests allowed to a client per period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    limit: u64,
+    period: Duration,
+    algorithm: Algorithm,
+}
+
+impl Quota {
+    /// `limit` requests per `period`, with a token bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` or `period` is zero.
+    pub fn new(limit: u64, period: Duration) -> Self {
+        assert!(limit > 0, "rate limit quota must allow a request");
+        assert!(!period.is_zero(), "rate limit period must not be zero");
+        Self {
+            limit,
+            period,
+            algorithm: Algorithm::default(),
+        }
+    }
+
+    /// `limit` requests per second.
+    pub fn per_second(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(1))
+    }
+
+    /// `limit` requests per minute.
+    pub fn per_minute(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// `limit` requests per hour.
+    pub fn per_hour(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(3600))
+    }
+
+    /// Counts requests with `algorithm` instead of a token bucket.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Requests allowed per period.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// The period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// The counting algorithm.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+/// The user making a request, for [`KeyBy::User`].
+///
+/// Authentication layers insert it into the request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser(pub String);
+
+/// The API key a request was authenticated with, or its id, for
+/// [`KeyBy::ApiKey`].
+///
+/// Authentication layers insert it into the request extensions once the key
+/// is known to be valid.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AuthenticatedApiKey(pub String);
+
+impl fmt::Debug for AuthenticatedApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuthenticatedApiKey([REDACTED])")
+    }
+}
+
+type KeyFn = dyn Fn(&Request) -> Option<String> + Send + Sync;
+
+/// What identifies the client of a request.
+///
+/// Requests lacking an authenticated API key or user are counted by IP
+/// address instead.
+#[derive(Clone, Default)]
+pub enum KeyBy {
+    /// The client IP address (`ip`).
+    #[default]
+    Ip,
+    /// The [`AuthenticatedApiKey`] (`api_key`).
+    ApiKey,
+    /// The [`AuthenticatedUser`] (`user`).
+    User,
+    /// A custom key; requests it returns `None` for are not limited.
+    Custom(Arc<KeyFn>),
+}
+
+impl KeyBy {
+    /// Keys requests with `key`.
+    pub fn custom<F>(key: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        KeyBy::Custom(Arc::new(key))
+    }
+}
+
+impl fmt::Debug for KeyBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyBy::Ip => f.write_str("Ip"),
+            KeyBy::ApiKey => f.write_str("ApiKey"),
+            KeyBy::User => f.write_str("User"),
+            KeyBy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// The [`KeyBy`] of the global limit (`RATE_LIMIT_KEY`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientKey {
+    /// [`KeyBy::Ip`] (`ip`).
+    #[default]
+    Ip,
+    /// [`KeyBy::ApiKey`] (`api_key`).
+    ApiKey,
+    /// [`KeyBy::User`] (`user`).
+    User,
+}
+
+/// A rate limiting layer; see the [module documentation](self).
+#[derive(Clone)]
+pub struct RateLimit {
+    inner: Arc<Inner>,
+}
+
+#[derive(Clone)]
+struct Inner {
+    quota: Option<Quota>,
+    routes: Vec<RouteQuota>,
+    key: KeyBy,
+    trusted_proxies: Vec<IpNet>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+/// The quota of the routes matching `pattern`, `None` for none.
+#[derive(Clone)]
+struct RouteQuota {
+    pattern: String,
+    quota: Option<Quota>,
+}
+
+impl RateLimit {
+    /// Limits every request to `quota`, by client IP, in a
+    /// [`MemoryStore`].
+    pub fn new(quota: Quota) -> Self {
+        Self::with_quota(Some(quota))
+    }
+
+    fn with_quota(quota: Option<Quota>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                quota,
+                routes: Vec::new(),
+                key: KeyBy::default(),
+                trusted_proxies: Vec::new(),
+                store: Arc::new(MemoryStore::new()),
+            }),
+        }
+    }
+
+    /// The global limit set by the `RATE_LIMIT_*` settings, leaving out the
+    /// health and metrics endpoints.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let quota = (config.rate_limit_requests > 0).then(|| {
+            Quota::new(
+                config.rate_limit_requests,
+                Duration::from_secs(config.rate_limit_period.max(1)),
+            )
+            .with_algorithm(config.rate_limit_algorithm)
+        });
+        let key = match config.rate_limit_key {
+            ClientKey::ApiKey => KeyBy::ApiKey,
+            ClientKey::User => KeyBy::User,
+            ClientKey::Ip => KeyBy::Ip,
+        };
+        Self::with_quota(quota)
+            .key_by(key)
+            .trusted_proxies(
+                config
+                    .rate_limit_trusted_proxies
+                    .iter()
+                    .filter_map(|net| IpNet::parse(net.trim())),
+            )
+            .exempt(&config.health_liveness_path)
+            .exempt(&config.health_readiness_path)
+            .exempt(&config.metrics_path)
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    /// Identifies clients with `key` instead of their IP address.
+    pub fn key_by(mut self, key: KeyBy) -> Self {
+        self.inner().key = key;
+        self
+    }
+
+    /// Keeps state in `store` instead of a [`MemoryStore`].
+    pub fn store(mut self, store: impl RateLimitStore) -> Self {
+        self.inner().store = Arc::new(store);
+        self
+    }
+
+    /// Reads the client IP from `X-Forwarded-For` on connections from
+    /// `proxies`.
+    pub fn trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        self.inner().trusted_proxies.extend(proxies);
+        self
+    }
+
+    /// Limits the routes matching `pattern` to `quota` instead, counted
+    /// apart from other routes.
+    ///
+    /// `pattern` uses Axum's path syntax (`/users/:id`, `/files/*path`) and
+    /// is matched against the path seen by the layer. The first matching
+    /// pattern wins.
+    pub fn route(mut self, pattern: &str, quota: Quota) -> Self {
+        self.inner().routes.push(RouteQuota {
+            pattern: pattern.to_string(),
+            quota: Some(quota),
+        });
+        self
+    }
+
+    /// Leaves the routes matching `pattern` unlimited; see
+    /// [`RateLimit::route`].
+    pub fn exempt(mut self, pattern: &str) -> Self {
+        self.inner().routes.push(RouteQuota {
+            pattern: pattern.to_string(),
+            quota: None,
+        });
+        self
+    }
+
+    /// Whether any request can be limited.
+    pub(crate) fn is_active(&self) -> bool {
+        self.inner.quota.is_some() || self.inner.routes.iter().any(|r| r.quota.is_some())
+    }
+
+    /// The store key and quota `request` counts against, or `None` if it
+    /// is not limited.
+    fn target(&self, request: &Request) -> Option<(String, Quota)> {
+        let path = request.uri().path();
+        let (scope, quota) = match self
+            .inner
+            .routes
+            .iter()
+            .find(|route| matches(&route.pattern, path))
+        {
+            Some(route) => (route.pattern.as_str(), route.quota?),
+            None => ("*", self.inner.quota?),
+        };
+        let client = self.client_key(request)?;
+        Some((format!("{}|{}", scope, client), quota))
+    }
+
+    async fn hit(&self, key: &str, quota: &Quota) -> Option<Decision> {
+        match self.inner.store.hit(key, quota).await {
+            Ok(decision) => Some(decision),
+            Err(e) => {
+                tracing::warn!(
+                    "Rate limit store failed, letting the request through: {:#}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn client_key(&self, request: &Request) -> Option<String> {
+        let ip = || self.client_ip(request).map(|ip| format!("ip:{}", ip));
+        match &self.inner.key {
+            KeyBy::Ip => ip(),
+            KeyBy::ApiKey => request
+                .extensions()
+                .get::<AuthenticatedApiKey>()
+                .map(|key| format!("key:{}", digest(key.0.as_bytes())))
+                .or_else(ip),
+            KeyBy::User => request
+                .extensions()
+                .get::<AuthenticatedUser>()
+                .map(|user| format!("user:{}", user.0))
+                .or_else(ip),
+            KeyBy::Custom(key) => key(request),
+        }
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()?
+            .0
+            .ip();
+        Some(forwarded_for(
+            peer,
+            request.headers(),
+            &self.inner.trusted_proxies,
+        ))
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("quota", &self.inner.quota)
+            .field("key", &self.inner.key)
+            .field("trusted_proxies", &self.inner.trusted_proxies)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A hex SHA-256 prefix of an API key, so stores never see the key.
+fn digest(key: &[u8]) -> String {
+    Sha256::digest(key)[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The client IP: `peer`, or when `peer` is a trusted proxy the last
+/// untrusted address of `X-Forwarded-For`.
+fn forwarded_for(peer: IpAddr, headers: &HeaderMap, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !is_trusted(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+/// Whether `path` matches `pattern`, in Axum's path syntax.
+fn matches(pattern: &str, path: &str) -> bool {
+    let mut segments = path.trim_start_matches('/').split('/');
+    for part in pattern.trim_start_matches('/').split('/') {
+        if part.starts_with('*') {
+            return true;
+        }
+        match segments.next() {
+            Some(segment) if part.starts_with(':') => {
+                if segment.is_empty() {
+                    return false;
+                }
+            }
+            Some(segment) if segment == part => {}
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+/// An IP address range, like `10.0.0.0/8`, or a single address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Parses `10.0.0.0/8`, `fd00::/8` or a single address.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    /// Whether `ip` is in the range.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = match (self.addr, ip) {
+            (IpAddr::V6(_), IpAddr::V4(v4)) => IpAddr::V6(v4.to_ipv6_mapped()),
+            (IpAddr::V4(_), IpAddr::V6(v6)) => match v6.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => return false,
+            },
+            (_, ip) => *ip,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_eq(u32::from(net).into(), u32::from(ip).into(), self.prefix, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_eq(u128::from(net), u128::from(ip), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_eq(a: u128, b: u128, prefix: u8, bits: u32) -> bool {
+    let shift = bits - u32::from(prefix);
+    shift >= 128 || a >> shift == b >> shift
+}
+
+impl<S> Layer<S> for RateLimit {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            limit: self.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service of a [`RateLimit`] layer.
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    limit: RateLimit,
+    inner: S,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+    S: Service<Request, Error = Infallible> + Clone + Send + 'static,
+    S::Response: IntoResponse,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limit = self.limit.clone();
+        Box::pin(async move {
+            let target = limit.target(&request);
+            let decision = match &target {
+                Some((key, quota)) => limit.hit(key, quota).await,
+                None => None,
+            };
+            let (Some((_, quota)), Some(decision)) = (target, decision) else {
+                return inner.call(request).await.map(IntoResponse::into_response);
+            };
+            let mut response = if decision.allowed {
+                inner.call(request).await?.into_response()
+            } else {
+                rejected(&decision)
+            };
+            set_headers(response.headers_mut(), &quota, &decision);
+            Ok(response)
+        })
+    }
+}
+
+/// The `429` problem for a limited request.
+fn rejected(decision: &Decision) -> Response {
+    let counter = Registry::global().counter(
+        RATE_LIMITED,
+        "Requests rejected by RATE_LIMIT_REQUESTS or a rate limit layer.",
+    );
+    match crate::tracing::current_trace_id() {
+        Some(trace_id) => counter.inc_with_exemplar(Exemplar {
+            trace_id,
+            value: 1.0,
+        }),
+        None => counter.inc(),
+    }
+    let retry_after = seconds(decision.retry_after);
+    let mut response = SwordError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited")
+        .with_detail(format!(
+            "Too many requests, retry in {} seconds",
+            retry_after
+        ))
+        .into_response();
+    response
+        .headers_mut()
+        .insert("retry-after", HeaderValue::from(retry_after));
+    response
+}
+
+fn set_headers(headers: &mut HeaderMap, quota: &Quota, decision: &Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert(
+        "ratelimit-reset",
+        HeaderValue::from(seconds(decision.reset)),
+    );
+    if let Ok(policy) =
+        HeaderValue::from_str(&format!("{};w={}", quota.limit(), seconds(quota.period())))
+    {
+        headers.insert("ratelimit-policy", policy);
+    }
+}
+
+/// `duration` in whole seconds, rounded up.
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn request(path: &str, peer: [u8; 4], forwarded: Option<&str>) -> Request {
+        let mut request = Request::get(path);
+        if let Some(forwarded) = forwarded {
+            request = request.header("x-forwarded-for", forwarded);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 4000))));
+        request
+    }
+
+    async fn status(app: &Router, request: Request) -> StatusCode {
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_layer() {
+        let limit = RateLimit::new(Quota::per_minute(2))
+            .route("/login", Quota::per_minute(1))
+            .exempt("/files/*path")
+            .trusted_proxies(IpNet::parse("10.0.0.0/8"));
+        let app = Router::new()
+            .route("/users/:id", get(|| async { "user" }))
+            .route("/login", get(|| async { "login" }))
+            .route("/files/*path", get(|| async { "file" }))
+            .layer(limit);
+        let client = [192, 0, 2, 1];
+
+        let response = app
+            .clone()
+            .oneshot(request("/users/1", client, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["ratelimit-limit"], "2");
+        assert_eq!(response.headers()["ratelimit-remaining"], "1");
+        assert_eq!(response.headers()["ratelimit-policy"], "2;w=60");
+        assert_eq!(
+            status(&app, request("/users/2", client, None)).await,
+            StatusCode::OK
+        );
+        let response = app
+            .clone()
+            .oneshot(request("/users/1", client, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "30");
+
+        // The login route is counted apart, and files are not limited.
+        assert_eq!(
+            status(&app, request("/login", client, None)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, request("/login", client, None)).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        for _ in 0..3 {
+            assert_eq!(
+                status(&app, request("/files/a/b", client, None)).await,
+                StatusCode::OK
+            );
+        }
+
+        // X-Forwarded-For is only trusted from the proxy.
+        let proxy = [10, 1, 2, 3];
+        let forwarded = Some("192.0.2.1, 198.51.100.7, 10.0.0.2");
+        assert_eq!(
+            status(&app, request("/users/1", proxy, forwarded)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, request("/users/1", proxy, forwarded)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, request("/users/1", proxy, forwarded)).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        let spoofed = Some("198.51.100.7");
+        assert_eq!(
+            status(&app, request("/users/1", client, spoofed)).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_keys() {
+        let limit = RateLimit::new(Quota::per_minute(1)).key_by(KeyBy::ApiKey);
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(limit);
+        let with_key = |key: &str| {
+            let mut request = request("/", [192, 0, 2, 1], None);
+            request
+                .extensions_mut()
+                .insert(AuthenticatedApiKey(key.to_string()));
+            request
+        };
+        assert_eq!(status(&app, with_key("a")).await, StatusCode::OK);
+        assert_eq!(status(&app, with_key("b")).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, with_key("a")).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        // Unauthenticated keys are counted by IP, however often they change.
+        let with_header = |key: &str| {
+            let mut request = request("/", [192, 0, 2, 2], None);
+            request
+                .headers_mut()
+                .insert("x-api-key", key.parse().unwrap());
+            request
+        };
+        assert_eq!(status(&app, with_header("c")).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, with_header("d")).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        let limit = RateLimit::new(Quota::per_minute(1)).key_by(KeyBy::custom(|request| {
+            request.uri().query().map(|tenant| tenant.to_string())
+        }));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(limit);
+        for _ in 0..2 {
+            assert_eq!(
+                status(&app, request("/", [192, 0, 2, 1], None)).await,
+                StatusCode::OK
+            );
+        }
+        assert_eq!(
+            status(&app, request("/?t1", [192, 0, 2, 1], None)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, request("/?t1", [192, 0, 2, 1], None)).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[test]
+    fn test_ip_net() {
+        let net = IpNet::parse("10.0.0.0/8").unwrap();
+        assert!(net.contains(&"10.200.0.1".parse().unwrap()));
+        assert!(!net.contains(&"11.0.0.1".parse().unwrap()));
+        assert!(net.contains(&"::ffff:10.0.0.1".parse().unwrap()));
+        assert!(IpNet::parse("::/0")
+            .unwrap()
+            .contains(&"2001:db8::1".parse().unwrap()));
+        assert!(IpNet::parse("192.0.2.1")
+            .unwrap()
+            .contains(&"192.0.2.1".parse().unwrap()));
+        assert_eq!(IpNet::parse("10.0.0.0/33"), None);
+        assert_eq!(IpNet::parse("proxy"), None);
+    }
+}
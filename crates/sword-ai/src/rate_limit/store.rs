@@ -0,0 +1,358 @@
+//! Rate limit state stores.
+
+use super::{Algorithm, Quota};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often [`MemoryStore`] drops the state of idle clients.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Clients a [`MemoryStore`] keeps state for by default.
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// The outcome of counting a request against a [`Quota`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    /// Whether the request is within the quota.
+    pub allowed: bool,
+    /// Requests allowed per period.
+    pub limit: u64,
+    /// Requests left before the client is limited.
+    pub remaining: u64,
+    /// Time until the quota is fully available again.
+    pub reset: Duration,
+    /// Time until a request would be allowed; zero when `allowed`.
+    pub retry_after: Duration,
+}
+
+/// Where rate limit state is kept.
+///
+/// [`MemoryStore`] keeps it in the process. Implement this trait to share
+/// limits between instances, e.g. in Redis:
+///
+/// ```rust,ignore
+/// use sword_ai::rate_limit::{Decision, Quota, RateLimitStore};
+///
+/// struct RedisStore { client: redis::Client }
+///
+/// #[async_trait::async_trait]
+/// impl RateLimitStore for RedisStore {
+///     async fn hit(&self, key: &str, quota: &Quota) -> anyhow::Result<Decision> {
+///         // Run the algorithm in a Lua script keyed by `key`.
+///         todo!()
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Counts a request by the client `key` against `quota`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be reached. The request is
+    /// then let through.
+    async fn hit(&self, key: &str, quota: &Quota) -> anyhow::Result<Decision>;
+}
+
+/// The default [`RateLimitStore`], keeping state in memory.
+///
+/// Limits are per process: with several instances behind a load balancer,
+/// a client gets the quota once per instance.
+///
+/// At most 100 000 clients are tracked, or the number given to
+/// [`MemoryStore::with_capacity`]. When a new client would exceed it, the
+/// tenth of the clients closest to their full quota again are forgotten.
+#[derive(Debug)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct MemoryState {
+    entries: HashMap<String, Entry>,
+    swept: Instant,
+}
+
+#[derive(Debug)]
+struct Entry {
+    state: State,
+    expires: Instant,
+}
+
+#[derive(Debug)]
+enum State {
+    Bucket {
+        tokens: f64,
+        updated: Instant,
+    },
+    Window {
+        start: Instant,
+        current: u64,
+        previous: u64,
+    },
+}
+
+impl MemoryStore {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// An empty store tracking at most `capacity` clients.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(MemoryState {
+                entries: HashMap::new(),
+                swept: Instant::now(),
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn hit_at(&self, key: &str, quota: &Quota, now: Instant) -> Decision {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(state.swept) >= SWEEP_INTERVAL {
+            state.sweep(now);
+        }
+        if state.entries.len() >= self.capacity && !state.entries.contains_key(key) {
+            state.evict(now, self.capacity);
+        }
+        let entry = state
+            .entries
+            .entry(key.to_string())
+            .or_insert_with(|| Entry {
+                state: State::new(quota, now),
+                expires: now,
+            });
+        if !entry.state.fits(quota) {
+            entry.state = State::new(quota, now);
+        }
+        // Entries expire once they would be recreated in the same state:
+        // a full bucket, or a window whose counts no longer matter.
+        match &mut entry.state {
+            State::Bucket { tokens, updated } => {
+                let decision = token_bucket(quota, tokens, updated, now);
+                entry.expires = now + decision.reset;
+                decision
+            }
+            State::Window {
+                start,
+                current,
+                previous,
+            } => {
+                let decision = sliding_window(quota, start, current, previous, now);
+                entry.expires = *start + quota.period() * 2;
+                decision
+            }
+        }
+    }
+}
+
+impl MemoryState {
+    /// Drops the entries that have expired.
+    fn sweep(&mut self, now: Instant) {
+        self.entries.retain(|_, entry| entry.expires > now);
+        self.swept = now;
+    }
+
+    /// Makes room below `capacity`: drops expired entries and, if that is
+    /// not enough, a tenth of `capacity` expiring soonest.
+    fn evict(&mut self, now: Instant, capacity: usize) {
+        self.sweep(now);
+        if self.entries.len() < capacity {
+            return;
+        }
+        let excess = self.entries.len() - capacity * 9 / 10;
+        let mut expiries: Vec<Instant> = self.entries.values().map(|e| e.expires).collect();
+        let cutoff = *expiries.select_nth_unstable(excess - 1).1;
+        self.entries.retain(|_, entry| entry.expires > cutoff);
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn hit(&self, key: &str, quota: &Quota) -> anyhow::Result<Decision> {
+        Ok(self.hit_at(key, quota, Instant::now()))
+    }
+}
+
+impl State {
+    fn new(quota: &Quota, now: Instant) -> Self {
+        match quota.algorithm() {
+            Algorithm::TokenBucket => State::Bucket {
+                tokens: quota.limit() as f64,
+                updated: now,
+            },
+            Algorithm::SlidingWindow => State::Window {
+                start: now,
+                current: 0,
+                previous: 0,
+            },
+        }
+    }
+
+    /// Whether the state was kept for `quota`'s algorithm.
+    fn fits(&self, quota: &Quota) -> bool {
+        matches!(
+            (self, quota.algorithm()),
+            (State::Bucket { .. }, Algorithm::TokenBucket)
+                | (State::Window { .. }, Algorithm::SlidingWindow)
+        )
+    }
+}
+
+/// A bucket of `limit` tokens, refilled at `limit` per period. Each
+/// request takes a token, so bursts of up to `limit` requests are allowed.
+fn token_bucket(quota: &Quota, tokens: &mut f64, updated: &mut Instant, now: Instant) -> Decision {
+    let capacity = quota.limit() as f64;
+    let period = quota.period();
+    let elapsed = now.saturating_duration_since(*updated).as_secs_f64();
+    *tokens = (*tokens + capacity * elapsed / period.as_secs_f64()).min(capacity);
+    *updated = now;
+
+    let allowed = *tokens >= 1.0;
+    if allowed {
+        *tokens -= 1.0;
+    }
+    Decision {
+        allowed,
+        limit: quota.limit(),
+        remaining: tokens.floor() as u64,
+        reset: period.mul_f64((capacity - *tokens) / capacity),
+        retry_after: if allowed {
+            Duration::ZERO
+        } else {
+            period.mul_f64((1.0 - *tokens) / capacity)
+        },
+    }
+}
+
+/// Counts requests in fixed windows, weighting the previous window by how
+/// much of it still overlaps the sliding one.
+fn sliding_window(
+    quota: &Quota,
+    start: &mut Instant,
+    current: &mut u64,
+    previous: &mut u64,
+    now: Instant,
+) -> Decision {
+    let period = quota.period();
+    let limit = quota.limit() as f64;
+    let windows = now.saturating_duration_since(*start).as_nanos() / period.as_nanos();
+    if windows > 0 {
+        *previous = if windows == 1 { *current } else { 0 };
+        *current = 0;
+        *start += period * u32::try_from(windows).unwrap_or(u32::MAX);
+    }
+    let elapsed = now.saturating_duration_since(*start);
+    let reset = period.saturating_sub(elapsed);
+    let overlap = 1.0 - elapsed.as_secs_f64() / period.as_secs_f64();
+    let count = *previous as f64 * overlap + *current as f64;
+
+    let allowed = count + 1.0 <= limit;
+    if allowed {
+        *current += 1;
+    }
+    let retry_after = if allowed {
+        Duration::ZERO
+    } else if (*current + 1) as f64 <= limit {
+        // Wait for enough of the previous window to slide out.
+        let overlap = (limit - 1.0 - *current as f64) / *previous as f64;
+        (period.mul_f64(1.0 - overlap))
+            .saturating_sub(elapsed)
+            .min(reset)
+    } else {
+        // Wait for the next window, and enough of this one to slide out.
+        reset + period.mul_f64(1.0 - (limit - 1.0) / *current as f64)
+    };
+    Decision {
+        allowed,
+        limit: quota.limit(),
+        remaining: (limit - count - if allowed { 1.0 } else { 0.0 }).max(0.0) as u64,
+        reset,
+        retry_after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(store: &MemoryStore, quota: &Quota, now: Instant, n: usize) -> Vec<bool> {
+        (0..n)
+            .map(|_| store.hit_at("client", quota, now).allowed)
+            .collect()
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let store = MemoryStore::new();
+        let quota = Quota::per_minute(3);
+        let now = Instant::now();
+
+        assert_eq!(hits(&store, &quota, now, 4), [true, true, true, false]);
+        let denied = store.hit_at("client", &quota, now);
+        assert_eq!(denied.remaining, 0);
+        assert_eq!(denied.retry_after, Duration::from_secs(20));
+        assert!(store.hit_at("other", &quota, now).allowed);
+
+        let later = now + Duration::from_secs(20);
+        let decision = store.hit_at("client", &quota, later);
+        assert!(decision.allowed);
+        assert_eq!(decision.reset, Duration::from_secs(60));
+        assert!(!store.hit_at("client", &quota, later).allowed);
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let store = MemoryStore::new();
+        let quota = Quota::per_minute(4).with_algorithm(Algorithm::SlidingWindow);
+        let now = Instant::now();
+
+        assert_eq!(
+            hits(&store, &quota, now, 5),
+            [true, true, true, true, false]
+        );
+        let denied = store.hit_at("client", &quota, now);
+        assert_eq!(denied.reset, Duration::from_secs(60));
+        assert_eq!(denied.retry_after, Duration::from_secs(75));
+
+        // Half of the previous window still counts: 4 * 0.5 = 2.
+        let later = now + Duration::from_secs(90);
+        assert_eq!(hits(&store, &quota, later, 3), [true, true, false]);
+        let denied = store.hit_at("client", &quota, later);
+        assert_eq!(denied.retry_after, Duration::from_secs(15));
+
+        let much_later = now + Duration::from_secs(300);
+        assert_eq!(hits(&store, &quota, much_later, 4), [true; 4]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let store = MemoryStore::with_capacity(2);
+        let quota = Quota::per_minute(1);
+        let now = Instant::now();
+
+        assert!(store.hit_at("a", &quota, now).allowed);
+        assert!(
+            store
+                .hit_at("b", &quota, now + Duration::from_secs(1))
+                .allowed
+        );
+        // "a" is the closest to its full quota, so it makes room for "c".
+        let later = now + Duration::from_secs(2);
+        assert!(store.hit_at("c", &quota, later).allowed);
+        assert_eq!(store.state.lock().unwrap().entries.len(), 2);
+        assert!(!store.hit_at("b", &quota, later).allowed);
+        assert!(store.hit_at("a", &quota, later).allowed);
+    }
+}
@@ -1,13 +1,17 @@
 //! Server execution module.
 //!
 //! Provides functions to run an Axum server with database connectivity
-//! and optional automatic migrations.
+//! and optional automatic migrations. Both functions shut down gracefully
+//! on `SIGINT`/`SIGTERM` (see [`crate::shutdown`]).
 //!
 //! ## Functions
 //!
 //! - [`run`] - Run server without migrations
 //! - [`run_with_migrator`] - Run server with optional migrations
 //!
+//! Both are thin wrappers over [`SwordApp`](crate::SwordApp), which also
+//! supports explicit configuration and middleware layers.
+//!
 //! ## Example
 //!
 //! ```rust,ignore
@@ -19,22 +23,33 @@
 //! }, true).await?;
 //! ```
 
+use crate::app::SwordApp;
 use crate::config::AppConfig;
-use crate::db;
+#[cfg(feature = "database")]
+use crate::db::Db;
+use crate::lifecycle::Hooks;
+use crate::shutdown::{shutdown_signal, Shutdown};
 use axum::Router;
-use sea_orm::DatabaseConnection;
+#[cfg(feature = "database")]
 use sea_orm_migration::MigratorTrait;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
 
 /// Shared context available to all route handlers.
 ///
-/// Contains the application configuration and database connection.
-/// Clone this to pass it as Axum state.
+/// Contains the application configuration, database connection (with the
+/// `database` feature) and shutdown handle. Clone this to pass it as Axum state.
 #[derive(Clone)]
 pub struct FrameworkContext {
     /// Application configuration.
     pub config: AppConfig,
-    /// Database connection pool.
-    pub db: DatabaseConnection,
+    /// Database primary and read replicas (requires the `database`
+    /// feature). Use [`Db::writer`] for writes and [`Db::reader`] for reads.
+    #[cfg(feature = "database")]
+    pub db: Db,
+    /// Shutdown handle for spawning and cancelling background tasks.
+    pub shutdown: Shutdown,
 }
 
 /// Runs the Axum server without database migrations.
@@ -57,29 +72,15 @@ pub struct FrameworkContext {
 /// ```
 pub async fn run<F>(build_router: F) -> anyhow::Result<()>
 where
-    F: FnOnce(&FrameworkContext) -> Router,
+    F: FnOnce(&FrameworkContext) -> Router + Send + 'static,
 {
-    let config = AppConfig::from_env()?;
-    let db = db::connect_db(&config).await?;
-
-    let ctx = FrameworkContext {
-        config: config.clone(),
-        db,
-    };
-
-    let app = build_router(&ctx);
-
-    let bind_addr = config.bind_address();
-    tracing::info!("Starting server on {}", bind_addr);
-
-    let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
+    SwordApp::builder().router(build_router).serve().await
 }
 
 /// Runs the Axum server with optional database migrations.
 ///
+/// Requires the `database` feature.
+///
 /// # Arguments
 ///
 /// * `build_router` - A function that receives a [`FrameworkContext`] and returns a configured [`Router`].
@@ -101,32 +102,77 @@ where
 ///         .with_state(ctx.clone())
 /// }, true).await?;
 /// ```
+#[cfg(feature = "database")]
 pub async fn run_with_migrator<M, F>(build_router: F, run_migrations: bool) -> anyhow::Result<()>
 where
-    M: MigratorTrait,
-    F: FnOnce(&FrameworkContext) -> Router,
+    M: MigratorTrait + 'static,
+    F: FnOnce(&FrameworkContext) -> Router + Send + 'static,
 {
-    let config = AppConfig::from_env()?;
-    let db = db::connect_db(&config).await?;
+    let builder = SwordApp::builder().router(build_router);
+    let builder = if run_migrations {
+        builder.migrator::<M>()
+    } else {
+        builder
+    };
+    builder.serve().await
+}
 
-    if run_migrations {
-        tracing::info!("Running database migrations...");
-        M::up(&db, None).await?;
-        tracing::info!("Migrations completed successfully");
-    }
+/// Serves `app` on `listener` until a shutdown signal arrives, then drains connections,
+/// waits for background tasks, runs shutdown hooks and closes the database
+/// pool.
+pub(crate) async fn serve(
+    ctx: FrameworkContext,
+    listener: TcpListener,
+    app: Router,
+    mut hooks: Hooks,
+) -> anyhow::Result<()> {
+    tracing::info!("Starting server on {}", listener.local_addr()?);
+
+    let signal = ctx.shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        signal.trigger();
+    });
 
-    let ctx = FrameworkContext {
-        config: config.clone(),
-        db,
+    let token = ctx.shutdown.token();
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(token.clone().cancelled_owned());
+    let drain_timeout = Duration::from_secs(ctx.config.shutdown_drain_timeout);
+
+    let result = tokio::select! {
+        result = async { server.await } => result,
+        _ = async {
+            token.cancelled().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            tracing::warn!(
+                "Drain timeout of {}s elapsed, dropping remaining connections",
+                drain_timeout.as_secs()
+            );
+            Ok(())
+        }
     };
 
-    let app = build_router(&ctx);
+    ctx.shutdown.trigger();
+
+    let tasks_timeout = Duration::from_secs(ctx.config.shutdown_tasks_timeout);
+    if !ctx.shutdown.wait_for_tasks(tasks_timeout).await {
+        tracing::warn!(
+            "Background tasks did not finish within {}s",
+            tasks_timeout.as_secs()
+        );
+    }
+
+    let hook_timeout = Duration::from_secs(ctx.config.shutdown_hook_timeout);
+    hooks.run_shutdown(&ctx, hook_timeout).await;
 
-    let bind_addr = config.bind_address();
-    tracing::info!("Starting server on {}", bind_addr);
+    #[cfg(feature = "database")]
+    ctx.db.close().await;
 
-    let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-    axum::serve(listener, app).await?;
+    tracing::info!("Server stopped");
 
-    Ok(())
+    result.map_err(Into::into)
 }
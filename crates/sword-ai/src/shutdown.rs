@@ -0,0 +1,160 @@
+//! Graceful shutdown module.
+//!
+//! Provides [`shutdown_signal`] for waiting on `SIGINT`/`SIGTERM`, and
+//! [`Shutdown`], the handle used to cancel and await background tasks
+//! spawned from a [`FrameworkContext`](crate::FrameworkContext).
+//!
+//! ## Shutdown Sequence
+//!
+//! When a signal is received the server:
+//!
+//! 1. Triggers the [`Shutdown`] token and stops accepting new connections
+//! 2. Drains in-flight requests for up to `APP_SHUTDOWN_DRAIN_TIMEOUT` seconds
+//! 3. Waits for background tasks for up to `APP_SHUTDOWN_TASKS_TIMEOUT` seconds
+//! 4. Runs shutdown hooks (see [`crate::lifecycle`])
+//! 5. Closes the database connection pool
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! fn build_router(ctx: &FrameworkContext) -> Router {
+//!     let token = ctx.shutdown.token();
+//!     ctx.shutdown.spawn(async move {
+//!         loop {
+//!             tokio::select! {
+//!                 _ = token.cancelled() => break,
+//!                 _ = tokio::time::sleep(Duration::from_secs(60)) => refresh_cache().await,
+//!             }
+//!         }
+//!     });
+//!
+//!     Router::new()
+//! }
+//! ```
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Handle for coordinating shutdown of background tasks.
+///
+/// Cloning is cheap; all clones share the same token and task set.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Shutdown {
+    /// Creates a new, untriggered shutdown handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a token that is cancelled when shutdown begins.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Returns `true` once shutdown has begun.
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Begins shutdown, cancelling every token handed out by [`Shutdown::token`].
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    /// Waits until shutdown begins.
+    pub async fn triggered(&self) {
+        self.token.cancelled().await;
+    }
+
+    /// Spawns a background task that the server waits for on shutdown.
+    ///
+    /// The task should watch [`Shutdown::token`] and return promptly once
+    /// it is cancelled.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tracker.spawn(task)
+    }
+
+    /// Waits for all spawned tasks to finish, up to `timeout`.
+    ///
+    /// Returns `false` if the timeout elapsed first.
+    pub(crate) async fn wait_for_tasks(&self, timeout: Duration) -> bool {
+        self.tracker.close();
+        tokio::time::timeout(timeout, self.tracker.wait())
+            .await
+            .is_ok()
+    }
+}
+
+/// Completes when the process receives `SIGINT` (Ctrl+C) or, on Unix, `SIGTERM`.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trigger_cancels_token() {
+        let shutdown = Shutdown::new();
+        let token = shutdown.token();
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+
+        assert!(token.is_cancelled());
+        assert!(shutdown.is_triggered());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_tasks_after_cancellation() {
+        let shutdown = Shutdown::new();
+        let token = shutdown.token();
+        shutdown.spawn(async move { token.cancelled().await });
+
+        shutdown.trigger();
+
+        assert!(shutdown.wait_for_tasks(Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_tasks_times_out() {
+        let shutdown = Shutdown::new();
+        shutdown.spawn(std::future::pending::<()>());
+
+        assert!(!shutdown.wait_for_tasks(Duration::from_millis(10)).await);
+    }
+}
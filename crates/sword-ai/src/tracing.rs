@@ -1,7 +1,8 @@
 //! Tracing initialization module.
 //!
 //! Provides [`init_tracing`] for setting up structured logging with
-//! the `tracing` ecosystem.
+//! the `tracing` ecosystem, and [`current_trace_id`] for the trace id of
+//! the request being handled.
 //!
 //! ## Log Levels
 //!
@@ -13,6 +14,14 @@
 //! RUST_LOG=debug cargo run
 //! ```
 //!
+//! ## Trace Ids
+//!
+//! [`SwordApp`](crate::SwordApp) gives every request a trace id, taken from
+//! the W3C `traceparent` header, else `x-request-id`, else generated. The
+//! request is handled inside a `request` span carrying a `trace_id` field,
+//! and the id is available anywhere in the request task through
+//! [`current_trace_id`], e.g. to tag logs and metrics.
+//!
 //! ## Example
 //!
 //! ```rust,ignore
@@ -24,8 +33,19 @@
 //! }
 //! ```
 
+use axum::extract::Request;
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::future::Future;
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+tokio::task_local! {
+    /// Trace id of the request being handled.
+    static TRACE_ID: String;
+}
+
 /// Initializes the global tracing subscriber.
 ///
 /// Call this once at the start of your application, before any logging.
@@ -39,3 +59,117 @@ pub fn init_tracing() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 }
+
+/// Returns the trace id of the request being handled, or `None` outside a
+/// request (e.g. in startup hooks or spawned tasks).
+pub fn current_trace_id() -> Option<String> {
+    TRACE_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware running each request in a `request` span with its trace id.
+pub(crate) async fn trace_scope(request: Request, next: Next) -> Response {
+    let trace_id = trace_id_from(request.headers()).unwrap_or_else(generate_trace_id);
+    let span = tracing::info_span!("request", trace_id = %trace_id);
+    with_trace_id(trace_id, next.run(request).instrument(span)).await
+}
+
+/// Runs `f` with `trace_id` as the [current trace id](current_trace_id).
+pub(crate) async fn with_trace_id<F: Future>(trace_id: String, f: F) -> F::Output {
+    TRACE_ID.scope(trace_id, f).await
+}
+
+/// Reads the trace id from `traceparent` (`00-<trace-id>-<span-id>-<flags>`)
+/// or, failing that, `x-request-id`. Request ids are only taken if they are
+/// 1-64 ASCII letters, digits and dashes, as they end up in logs and
+/// metrics.
+fn trace_id_from(headers: &HeaderMap) -> Option<String> {
+    let traceparent = headers
+        .get("traceparent")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().split('-').nth(1))
+        .filter(|id| {
+            id.len() == 32
+                && id.bytes().all(|b| b.is_ascii_hexdigit())
+                && id.bytes().any(|b| b != b'0')
+        });
+    if let Some(id) = traceparent {
+        return Some(id.to_ascii_lowercase());
+    }
+    headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            (1..=64).contains(&id.len())
+                && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+        .map(str::to_string)
+}
+
+/// Generates a random 128-bit trace id in the W3C format (32 hex digits).
+fn generate_trace_id() -> String {
+    let mut id = [0u8; 16];
+    getrandom::getrandom(&mut id).expect("the OS random number generator is available");
+    format!("{:032x}", u128::from_be_bytes(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_id_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(trace_id_from(&headers), None);
+
+        headers.insert("x-request-id", "req-1".parse().unwrap());
+        assert_eq!(trace_id_from(&headers).as_deref(), Some("req-1"));
+
+        headers.insert(
+            "traceparent",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            trace_id_from(&headers).as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+
+        // An invalid traceparent falls back to x-request-id.
+        headers.insert(
+            "traceparent",
+            "00-0000-00f067aa0ba902b7-01".parse().unwrap(),
+        );
+        assert_eq!(trace_id_from(&headers).as_deref(), Some("req-1"));
+
+        headers.remove("traceparent");
+        headers.insert("x-request-id", "a\"} 9 # EOF".parse().unwrap());
+        assert_eq!(trace_id_from(&headers), None);
+        headers.insert("x-request-id", "a".repeat(65).parse().unwrap());
+        assert_eq!(trace_id_from(&headers), None);
+
+        assert_eq!(generate_trace_id().len(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_hostile_request_id_is_replaced() {
+        use axum::body::{to_bytes, Body};
+        use axum::routing::get;
+        use axum::Router;
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/", get(|| async { current_trace_id().unwrap() }))
+            .layer(axum::middleware::from_fn(trace_scope));
+        let request = Request::get("/")
+            .header("x-request-id", "x\"} 1e9 # EOF")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), 32);
+        assert!(body.iter().all(u8::is_ascii_hexdigit));
+    }
+}
@@ -0,0 +1,498 @@
+//! Extractors that deserialize and validate the request.
+
+use super::{validate, FieldError, Validate, ValidationErrors};
+use crate::error::SwordError;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::PathRejection;
+use axum::extract::{FromRequest, FromRequestParts, Path, RawPathParams, Request};
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+
+/// A JSON body that passes the rules of `T`.
+///
+/// Requires a `Content-Type` of `application/json` or `application/*+json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = SwordError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(req.headers()) {
+            return Err(SwordError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+            )
+            .with_detail("Expected a body with Content-Type: application/json"));
+        }
+        let body = Bytes::from_request(req, state).await.map_err(|rejection| {
+            SwordError::new(rejection.status(), "invalid_body").with_detail(rejection.body_text())
+        })?;
+
+        let mut de = serde_json::Deserializer::from_slice(&body);
+        let value: T = serde_path_to_error::deserialize(&mut de)
+            .map_err(|error| {
+                let path = error.path().clone();
+                let error = error.into_inner();
+                if error.classify() != serde_json::error::Category::Data {
+                    return invalid_json(error);
+                }
+                let message = error.to_string();
+                let suffix = format!(" at line {} column {}", error.line(), error.column());
+                let message = message.strip_suffix(&suffix).unwrap_or(&message);
+                deserialize_error(&path, message, "invalid_body")
+            })
+            .and_then(|value| de.end().map(|()| value).map_err(invalid_json))?;
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+/// A query string that passes the rules of `T`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = SwordError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let de = serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let value: T = serde_path_to_error::deserialize(de).map_err(|error| {
+            deserialize_error(error.path(), &error.inner().to_string(), "invalid_query")
+        })?;
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+/// Path parameters that pass the rules of `T`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedPath<T>
+where
+    T: DeserializeOwned + Validate + Send,
+    S: Send + Sync,
+{
+    type Rejection = SwordError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let value = match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => value,
+            Err(rejection) => {
+                let params = RawPathParams::from_request_parts(parts, state).await.ok();
+                return Err(path_error(rejection, params));
+            }
+        };
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    let essence = essence.to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn invalid_json(error: serde_json::Error) -> SwordError {
+    SwordError::bad_request(format!("Invalid JSON: {}", error)).with_code("invalid_json")
+}
+
+/// Reports a value that does not deserialize at `path` as a field error,
+/// or as a `422` with `code` when there is no field to blame.
+fn deserialize_error(
+    path: &serde_path_to_error::Path,
+    message: &str,
+    code: &'static str,
+) -> SwordError {
+    let mut field = String::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => field.push_str(&format!("[{}]", index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                if !field.is_empty() {
+                    field.push('.');
+                }
+                field.push_str(key);
+            }
+            Segment::Unknown => field.push_str(".?"),
+        }
+    }
+
+    let error = match message
+        .strip_prefix("missing field `")
+        .and_then(|name| name.strip_suffix('`'))
+    {
+        Some(name) => FieldError {
+            field: if field.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", field, name)
+            },
+            code: "required".into(),
+            message: "is required".to_string(),
+        },
+        None if field.is_empty() => {
+            return SwordError::new(StatusCode::UNPROCESSABLE_ENTITY, code).with_detail(message)
+        }
+        None => FieldError {
+            field,
+            code: "invalid".into(),
+            message: message.to_string(),
+        },
+    };
+    ValidationErrors::from(error).into()
+}
+
+fn path_error(rejection: PathRejection, params: Option<RawPathParams>) -> SwordError {
+    use axum::extract::path::ErrorKind;
+
+    let key = |index: usize| {
+        params
+            .as_ref()
+            .and_then(|params| params.iter().nth(index))
+            .map(|(key, _)| key.to_string())
+            .unwrap_or_else(|| index.to_string())
+    };
+    let invalid = match &rejection {
+        PathRejection::FailedToDeserializePathParams(error) => match error.kind() {
+            ErrorKind::ParseErrorAtKey {
+                key,
+                value,
+                expected_type,
+            } => Some((key.clone(), value, expected_type)),
+            ErrorKind::ParseErrorAtIndex {
+                index,
+                value,
+                expected_type,
+            } => Some((key(*index), value, expected_type)),
+            ErrorKind::ParseError {
+                value,
+                expected_type,
+            } => Some((key(0), value, expected_type)),
+            _ => None,
+        },
+        _ => None,
+    };
+    match invalid {
+        Some((field, value, expected_type)) => ValidationErrors::from(FieldError {
+            field,
+            code: "invalid".into(),
+            message: format!("cannot parse `{}` as {}", value, expected_type),
+        })
+        .into(),
+        None => {
+            SwordError::new(rejection.status(), "invalid_path").with_detail(rejection.body_text())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Validator;
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::{get, post};
+    use axum::Router;
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Signup {
+        name: String,
+        email: String,
+        tags: Vec<String>,
+    }
+
+    impl Validate for Signup {
+        fn validate(&self, v: &mut Validator) {
+            v.field("name", &self.name).length(1..=100);
+            v.field("email", &self.email).email();
+            v.field("tags", &self.tags).length(..=2);
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Shipment {
+        address: Address,
+    }
+
+    #[derive(Deserialize)]
+    struct Address {
+        city: String,
+        zip: u32,
+    }
+
+    impl Validate for Shipment {
+        fn validate(&self, v: &mut Validator) {
+            v.nested("address", &self.address);
+        }
+    }
+
+    impl Validate for Address {
+        fn validate(&self, v: &mut Validator) {
+            v.field("city", &self.city).length(1..);
+            v.field("zip", &self.zip).range(1..);
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Search {
+        q: String,
+        page: Option<u32>,
+    }
+
+    impl Validate for Search {
+        fn validate(&self, v: &mut Validator) {
+            v.field("q", &self.q).length(3..);
+            v.optional("page", &self.page).range(1..);
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct UserId {
+        id: i64,
+    }
+
+    impl Validate for UserId {
+        fn validate(&self, v: &mut Validator) {
+            v.field("id", &self.id).range(1..);
+        }
+    }
+
+    async fn send(request: Request) -> (StatusCode, serde_json::Value) {
+        let app = Router::new()
+            .route(
+                "/signup",
+                post(|ValidatedJson(_): ValidatedJson<Signup>| async {}),
+            )
+            .route(
+                "/shipments",
+                post(|ValidatedJson(_): ValidatedJson<Shipment>| async {}),
+            )
+            .route(
+                "/search",
+                get(|ValidatedQuery(_): ValidatedQuery<Search>| async {}),
+            )
+            .route(
+                "/users/:id",
+                get(|ValidatedPath(_): ValidatedPath<UserId>| async {}),
+            );
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn post_json(uri: &str, body: &str) -> Request {
+        Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn signup(body: &str) -> Request {
+        post_json("/signup", body)
+    }
+
+    fn get_uri(uri: &str) -> Request {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    fn errors(body: &serde_json::Value) -> Vec<(&str, &str)> {
+        body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| (e["field"].as_str().unwrap(), e["code"].as_str().unwrap()))
+            .collect()
+    }
+
+    const VALID_SIGNUP: &str = r#"{"name": "Ada", "email": "ada@example.com", "tags": []}"#;
+
+    #[tokio::test]
+    async fn test_json_valid() {
+        assert_eq!(send(signup(VALID_SIGNUP)).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_json_rules() {
+        let (status, body) = send(signup(
+            r#"{"name": "", "email": "ada", "tags": ["a", "b", "c"]}"#,
+        ))
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(
+            errors(&body),
+            [("name", "length"), ("email", "email"), ("tags", "length")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_invalid_field() {
+        let (status, body) = send(signup(r#"{"name": "Ada", "tags": [1]}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("tags[0]", "invalid")]);
+    }
+
+    #[tokio::test]
+    async fn test_json_missing_field() {
+        let (status, body) = send(signup(r#"{"name": "Ada", "tags": []}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("email", "required")]);
+    }
+
+    #[tokio::test]
+    async fn test_json_nested_paths() {
+        let shipment = |body| post_json("/shipments", body);
+
+        let (status, body) = send(shipment(r#"{"address": {"city": "Oslo", "zip": "x"}}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("address.zip", "invalid")]);
+
+        let (_, body) = send(shipment(r#"{"address": {"zip": 150}}"#)).await;
+        assert_eq!(errors(&body), [("address.city", "required")]);
+
+        let (_, body) = send(shipment(r#"{"address": {"city": "", "zip": 0}}"#)).await;
+        assert_eq!(
+            errors(&body),
+            [("address.city", "length"), ("address.zip", "range")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_wrong_shape() {
+        let (status, body) = send(signup(r#"["Ada"]"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "invalid_body");
+        assert!(body.get("errors").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_json_malformed() {
+        let (status, body) = send(signup(r#"{"name": "#)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_json");
+    }
+
+    #[tokio::test]
+    async fn test_json_trailing_data() {
+        let (status, body) = send(signup(&format!("{} {{}}", VALID_SIGNUP))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_json");
+
+        let (status, _) = send(signup(&format!("{}\n", VALID_SIGNUP))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_json_content_type() {
+        let with_type = |content_type: &str| {
+            Request::post("/signup")
+                .header(header::CONTENT_TYPE, content_type)
+                .body(Body::from(VALID_SIGNUP))
+                .unwrap()
+        };
+        for accepted in [
+            "application/json",
+            "Application/JSON; charset=utf-8",
+            "application/vnd.api+json",
+            "application/merge-patch+json",
+        ] {
+            assert_eq!(
+                send(with_type(accepted)).await.0,
+                StatusCode::OK,
+                "{accepted}"
+            );
+        }
+        for rejected in ["text/plain", "application/jsonp", "text/x+json"] {
+            let (status, body) = send(with_type(rejected)).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{rejected}");
+            assert_eq!(body["code"], "unsupported_media_type");
+        }
+
+        let untyped = Request::post("/signup")
+            .body(Body::from(VALID_SIGNUP))
+            .unwrap();
+        assert_eq!(send(untyped).await.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_query_valid() {
+        assert_eq!(send(get_uri("/search?q=rust")).await.0, StatusCode::OK);
+        assert_eq!(
+            send(get_uri("/search?q=rust&page=2")).await.0,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_rules() {
+        let (status, body) = send(get_uri("/search?q=r")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("q", "length")]);
+    }
+
+    #[tokio::test]
+    async fn test_query_invalid_field() {
+        let (status, body) = send(get_uri("/search?q=rust&page=two")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("page", "invalid")]);
+
+        let (_, body) = send(get_uri("/search")).await;
+        assert_eq!(errors(&body), [("q", "required")]);
+    }
+
+    #[tokio::test]
+    async fn test_query_wrong_shape() {
+        let (status, body) = send(get_uri("/search?q=rust&q=go")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "invalid_query");
+        assert!(body.get("errors").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_path_valid() {
+        assert_eq!(send(get_uri("/users/7")).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_path_rules() {
+        let (status, body) = send(get_uri("/users/0")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("id", "range")]);
+    }
+
+    #[tokio::test]
+    async fn test_path_invalid_field() {
+        let (status, body) = send(get_uri("/users/abc")).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(errors(&body), [("id", "invalid")]);
+    }
+}
@@ -0,0 +1,879 @@
+//! Request validation module.
+//!
+//! Types describe their rules by implementing [`Validate`], and the
+//! [`ValidatedJson`], [`ValidatedQuery`] and [`ValidatedPath`] extractors
+//! check them before the handler runs:
+//!
+//! ```rust,ignore
+//! use sword_ai::validation::{Validate, ValidatedJson, Validator};
+//!
+//! #[derive(Deserialize)]
+//! pub struct CreateUserRequest {
+//!     pub name: String,
+//!     pub email: String,
+//!     pub age: Option<u8>,
+//! }
+//!
+//! impl Validate for CreateUserRequest {
+//!     fn validate(&self, v: &mut Validator) {
+//!         v.field("name", &self.name).length(1..=100);
+//!         v.field("email", &self.email).email();
+//!         v.optional("age", &self.age).range(13..);
+//!     }
+//! }
+//!
+//! async fn create_user(ValidatedJson(payload): ValidatedJson<CreateUserRequest>) {}
+//! ```
+//!
+//! A request that fails any rule, or whose fields do not deserialize,
+//! gets a `422 Unprocessable Entity` [problem](crate::error) listing every
+//! failing field:
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Unprocessable Entity",
+//!   "status": 422,
+//!   "detail": "The request has invalid fields",
+//!   "code": "validation_failed",
+//!   "errors": [
+//!     { "field": "name", "code": "length", "message": "must be at least 1 and at most 100 characters" },
+//!     { "field": "email", "code": "email", "message": "must be a valid email address" }
+//!   ]
+//! }
+//! ```
+//!
+//! A field that does not deserialize is reported with code `invalid`, or
+//! `required` when it is missing; as deserialization stops there, it is
+//! the only one listed. A body or query string that does not deserialize
+//! as a whole, e.g. a JSON array where an object is expected, gets a `422`
+//! with code `invalid_body` or `invalid_query`. Malformed JSON gets a `400`
+//! with code `invalid_json`, and a body that is not `application/json` a
+//! `415` with code `unsupported_media_type`.
+//!
+//! ## Rules
+//!
+//! | Rule | Applies to | `code` |
+//! |------|------------|--------|
+//! | [`length`](Field::length) | strings (in characters), `Vec`s and slices | `length` |
+//! | [`range`](Field::range) | numbers and any other `PartialOrd` | `range` |
+//! | [`email`](Field::email) | strings | `email` |
+//! | [`matches`](Field::matches) | strings | `pattern` |
+//! | [`custom`](Field::custom) | anything | its own |
+//! | [`required`](Field::required) | `Option`s, via [`Validator::optional`] | `required` |
+//!
+//! Rules on a `None` optional field are skipped, and only the first
+//! failing rule of each field is reported. Nested types and lists are
+//! checked with [`Validator::nested`] and [`Validator::each`], which
+//! report paths like `address.city` and `items[2].quantity`.
+//!
+//! Services can check values that do not come from a request with
+//! [`validate`].
+
+mod extract;
+
+pub use extract::{ValidatedJson, ValidatedPath, ValidatedQuery};
+pub use regex::Regex;
+
+use crate::error::SwordError;
+use crate::openapi::ApiSchema;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::OnceLock;
+
+/// A type with validation rules.
+pub trait Validate {
+    /// Checks each field with `v`.
+    fn validate(&self, v: &mut Validator);
+}
+
+/// Checks the rules of `value`.
+pub fn validate<T: Validate + ?Sized>(value: &T) -> Result<(), ValidationErrors> {
+    let mut v = Validator::default();
+    value.validate(&mut v);
+    v.finish()
+}
+
+/// Collects the failing fields of a [`Validate`] implementation.
+#[derive(Debug, Default)]
+pub struct Validator {
+    prefix: String,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Starts the rules of the field `name`.
+    #[must_use]
+    pub fn field<'a, T: ?Sized>(&'a mut self, name: &str, value: &'a T) -> Field<'a, T> {
+        let path = self.path(name);
+        Field::new(self, path, Some(value))
+    }
+
+    /// Starts the rules of the optional field `name`. They are skipped
+    /// when it is `None`, unless it is [`required`](Field::required).
+    #[must_use]
+    pub fn optional<'a, T>(&'a mut self, name: &str, value: &'a Option<T>) -> Field<'a, T> {
+        let path = self.path(name);
+        Field::new(self, path, value.as_ref())
+    }
+
+    /// Checks the rules of the nested value `name`.
+    pub fn nested<T: Validate + ?Sized>(&mut self, name: &str, value: &T) {
+        let len = self.prefix.len();
+        self.prefix.push_str(name);
+        self.prefix.push('.');
+        value.validate(self);
+        self.prefix.truncate(len);
+    }
+
+    /// Checks the rules of each value of the list `name`.
+    pub fn each<T: Validate>(&mut self, name: &str, values: &[T]) {
+        let len = self.prefix.len();
+        for (i, value) in values.iter().enumerate() {
+            self.prefix.push_str(&format!("{}[{}].", name, i));
+            value.validate(self);
+            self.prefix.truncate(len);
+        }
+    }
+
+    /// Reports `field` as invalid, for rules spanning several fields.
+    pub fn error(
+        &mut self,
+        field: &str,
+        code: impl Into<Cow<'static, str>>,
+        message: impl Into<String>,
+    ) {
+        let field = self.path(field);
+        self.errors.push(FieldError {
+            field,
+            code: code.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Whether a rule has failed so far.
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// The failing fields, if any.
+    pub fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(self.errors))
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+/// The rules of one field, started with [`Validator::field`].
+pub struct Field<'a, T: ?Sized> {
+    validator: &'a mut Validator,
+    path: String,
+    value: Option<&'a T>,
+    failed: bool,
+}
+
+impl<'a, T: ?Sized> Field<'a, T> {
+    fn new(validator: &'a mut Validator, path: String, value: Option<&'a T>) -> Self {
+        Self {
+            validator,
+            path,
+            value,
+            failed: false,
+        }
+    }
+
+    fn fail(&mut self, invalid: Invalid) {
+        self.validator.errors.push(FieldError {
+            field: std::mem::take(&mut self.path),
+            code: invalid.code,
+            message: invalid.message,
+        });
+        self.failed = true;
+    }
+
+    /// Checks the value with `rule`.
+    pub fn custom(mut self, rule: impl FnOnce(&T) -> Result<(), Invalid>) -> Self {
+        if let (false, Some(value)) = (self.failed, self.value) {
+            if let Err(invalid) = rule(value) {
+                self.fail(invalid);
+            }
+        }
+        self
+    }
+
+    /// Requires an [optional](Validator::optional) field to be present.
+    pub fn required(mut self) -> Self {
+        if !self.failed && self.value.is_none() {
+            self.fail(Invalid::new("required", "is required"));
+        }
+        self
+    }
+
+    /// Requires the [length](Length) to be within `range`, e.g. `1..=100`.
+    pub fn length(self, range: impl RangeBounds<usize>) -> Self
+    where
+        T: Length,
+    {
+        self.custom(|value| {
+            if range.contains(&value.length()) {
+                return Ok(());
+            }
+            let min = match range.start_bound() {
+                Bound::Included(&min) => min,
+                Bound::Excluded(&min) => min + 1,
+                Bound::Unbounded => 0,
+            };
+            let max = match range.end_bound() {
+                Bound::Included(&max) => Some(max),
+                Bound::Excluded(&max) => Some(max.saturating_sub(1)),
+                Bound::Unbounded => None,
+            };
+            let unit = T::UNIT;
+            let message = match max {
+                None if min == 1 => "must not be empty".to_string(),
+                None => format!("must be at least {} {}", min, unit),
+                Some(max) if min == max => format!("must be exactly {} {}", max, unit),
+                Some(max) if min == 0 => format!("must be at most {} {}", max, unit),
+                Some(max) => format!("must be at least {} and at most {} {}", min, max, unit),
+            };
+            Err(Invalid::new("length", message))
+        })
+    }
+
+    /// Requires the value to be within `range`, e.g. `1..=5`.
+    pub fn range<R>(self, range: R) -> Self
+    where
+        T: PartialOrd + fmt::Display + Sized,
+        R: RangeBounds<T>,
+    {
+        self.custom(|value| {
+            if range.contains(value) {
+                return Ok(());
+            }
+            let min = match range.start_bound() {
+                Bound::Included(min) => Some(format!("at least {}", min)),
+                Bound::Excluded(min) => Some(format!("greater than {}", min)),
+                Bound::Unbounded => None,
+            };
+            let max = match range.end_bound() {
+                Bound::Included(max) => Some(format!("at most {}", max)),
+                Bound::Excluded(max) => Some(format!("less than {}", max)),
+                Bound::Unbounded => None,
+            };
+            let message = match (min, max) {
+                (Some(min), Some(max)) => format!("must be {} and {}", min, max),
+                (Some(bound), None) | (None, Some(bound)) => format!("must be {}", bound),
+                (None, None) => unreachable!("an unbounded range contains every value"),
+            };
+            Err(Invalid::new("range", message))
+        })
+    }
+
+    /// Requires a valid email address, as defined by the
+    /// [HTML standard](https://html.spec.whatwg.org/multipage/input.html#valid-e-mail-address).
+    pub fn email(self) -> Self
+    where
+        T: AsRef<str>,
+    {
+        static EMAIL: OnceLock<Regex> = OnceLock::new();
+        let email = EMAIL.get_or_init(|| {
+            Regex::new(
+                r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$",
+            )
+            .expect("the email pattern is valid")
+        });
+        self.custom(|value| {
+            let value = value.as_ref();
+            if value.len() <= 254 && email.is_match(value) {
+                Ok(())
+            } else {
+                Err(Invalid::new("email", "must be a valid email address"))
+            }
+        })
+    }
+
+    /// Requires the value to match `pattern`. Anchor it with `^...$` to
+    /// match the whole value.
+    pub fn matches(self, pattern: &Regex) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.custom(|value| {
+            if pattern.is_match(value.as_ref()) {
+                Ok(())
+            } else {
+                Err(Invalid::new("pattern", "has an invalid format"))
+            }
+        })
+    }
+}
+
+/// Values with a length, for [`Field::length`].
+pub trait Length {
+    /// What the length counts, e.g. `characters`.
+    const UNIT: &'static str;
+
+    /// The length of the value.
+    fn length(&self) -> usize;
+}
+
+impl Length for str {
+    const UNIT: &'static str = "characters";
+
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl Length for String {
+    const UNIT: &'static str = "characters";
+
+    fn length(&self) -> usize {
+        self.as_str().length()
+    }
+}
+
+impl<T> Length for [T] {
+    const UNIT: &'static str = "items";
+
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for Vec<T> {
+    const UNIT: &'static str = "items";
+
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+/// The failure of a [custom](Field::custom) rule.
+#[derive(Debug, Clone)]
+pub struct Invalid {
+    code: Cow<'static, str>,
+    message: String,
+}
+
+impl Invalid {
+    /// A failure with a machine-readable `code`, e.g. `username_taken`,
+    /// and a `message` for the client.
+    pub fn new(code: impl Into<Cow<'static, str>>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An invalid field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ApiSchema)]
+pub struct FieldError {
+    /// Path of the field, e.g. `items[2].quantity`.
+    pub field: String,
+    /// Machine-readable code of the failed rule, e.g. `length`.
+    pub code: Cow<'static, str>,
+    /// Explanation for the client, e.g. `must not be empty`.
+    pub message: String,
+}
+
+/// The invalid fields of a value.
+///
+/// Responds with `422 Unprocessable Entity` and code `validation_failed`,
+/// listing the fields in `errors`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    /// The invalid fields, in the order their rules ran.
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+}
+
+impl From<FieldError> for ValidationErrors {
+    fn from(error: FieldError) -> Self {
+        Self(vec![error])
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid fields: ")?;
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for SwordError {
+    fn from(errors: ValidationErrors) -> Self {
+        SwordError::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed")
+            .with_detail("The request has invalid fields")
+            .with_extension("errors", &errors.0)
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        SwordError::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Line {
+        sku: String,
+        quantity: u32,
+    }
+
+    impl Validate for Line {
+        fn validate(&self, v: &mut Validator) {
+            v.field("sku", &self.sku)
+                .matches(&Regex::new("^[A-Z]{3}-[0-9]+$").unwrap());
+            v.field("quantity", &self.quantity).range(1..=99);
+        }
+    }
+
+    struct Address {
+        city: String,
+    }
+
+    impl Validate for Address {
+        fn validate(&self, v: &mut Validator) {
+            v.field("city", &self.city).length(1..);
+        }
+    }
+
+    struct Order {
+        email: String,
+        note: Option<String>,
+        coupon: Option<String>,
+        address: Address,
+        lines: Vec<Line>,
+    }
+
+    impl Validate for Order {
+        fn validate(&self, v: &mut Validator) {
+            v.field("email", &self.email).length(..=254).email();
+            v.optional("note", &self.note).length(1..);
+            v.optional("coupon", &self.coupon)
+                .required()
+                .custom(|_| Ok(()));
+            v.nested("address", &self.address);
+            v.field("lines", &self.lines).length(1..=3);
+            v.each("lines", &self.lines);
+        }
+    }
+
+    fn order() -> Order {
+        Order {
+            email: "a@example.com".to_string(),
+            note: None,
+            coupon: Some("SPRING".to_string()),
+            address: Address {
+                city: "Lisbon".to_string(),
+            },
+            lines: vec![Line {
+                sku: "ABC-1".to_string(),
+                quantity: 2,
+            }],
+        }
+    }
+
+    /// The `(field, code, message)` of each failure of `rules`.
+    fn failures(rules: impl FnOnce(&mut Validator)) -> Vec<(String, String, String)> {
+        let mut v = Validator::default();
+        rules(&mut v);
+        match v.finish() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|e| (e.field.clone(), e.code.to_string(), e.message.clone()))
+                .collect(),
+        }
+    }
+
+    /// The message of the single failure of `rules`, if any.
+    fn message(rules: impl FnOnce(&mut Validator)) -> Option<String> {
+        let failures = failures(rules);
+        assert!(failures.len() <= 1, "{failures:?}");
+        failures.into_iter().next().map(|(_, _, message)| message)
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(validate(&order()), Ok(()));
+    }
+
+    #[test]
+    fn test_length() {
+        let name = "Zoë".to_string();
+        let tags = vec!["a", "b", "c"];
+        assert_eq!(
+            message(|v| {
+                v.field("name", &name).length(3..=3);
+            }),
+            None
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("name", &name).length(..=3);
+            }),
+            None,
+            "counts characters, not bytes"
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("name", "").length(1..);
+            })
+            .as_deref(),
+            Some("must not be empty")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("name", &name).length(4..);
+            })
+            .as_deref(),
+            Some("must be at least 4 characters")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("name", &name).length(..=2);
+            })
+            .as_deref(),
+            Some("must be at most 2 characters")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("name", &name).length(5..=10);
+            })
+            .as_deref(),
+            Some("must be at least 5 and at most 10 characters")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("tags", &tags).length(2..=2);
+            })
+            .as_deref(),
+            Some("must be exactly 2 items")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("tags", tags.as_slice()).length(..3);
+            })
+            .as_deref(),
+            Some("must be at most 2 items")
+        );
+    }
+
+    #[test]
+    fn test_length_excluded_bounds() {
+        let tags = vec!["a", "b", "c"];
+        let above = (Bound::Excluded(3), Bound::Unbounded);
+        assert_eq!(
+            message(|v| {
+                v.field("tags", &tags).length(above);
+            })
+            .as_deref(),
+            Some("must be at least 4 items")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("tags", &tags).length(0..3);
+            })
+            .as_deref(),
+            Some("must be at most 2 items")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("tags", &tags)
+                    .length((Bound::Excluded(0), Bound::Unbounded));
+            })
+            .as_deref(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            message(|v| {
+                v.field("n", &5).range(1..=5);
+            }),
+            None
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &0).range(1..=99);
+            })
+            .as_deref(),
+            Some("must be at least 1 and at most 99")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &0).range(1..);
+            })
+            .as_deref(),
+            Some("must be at least 1")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &6).range(..=5);
+            })
+            .as_deref(),
+            Some("must be at most 5")
+        );
+    }
+
+    #[test]
+    fn test_range_excluded_bounds() {
+        assert_eq!(
+            message(|v| {
+                v.field("n", &10).range(0..10);
+            })
+            .as_deref(),
+            Some("must be at least 0 and less than 10")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &0.0)
+                    .range((Bound::Excluded(0.0), Bound::Unbounded));
+            })
+            .as_deref(),
+            Some("must be greater than 0")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &1)
+                    .range((Bound::Excluded(1), Bound::Excluded(3)));
+            })
+            .as_deref(),
+            Some("must be greater than 1 and less than 3")
+        );
+        assert_eq!(
+            message(|v| {
+                v.field("n", &2).range(..3);
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_email() {
+        for valid in [
+            "a@example.com",
+            "first.last+tag@sub.example.org",
+            "a@localhost",
+        ] {
+            assert_eq!(
+                message(|v| {
+                    v.field("email", valid).email();
+                }),
+                None,
+                "{valid}"
+            );
+        }
+        let too_long = format!("{}@example.com", "a".repeat(250));
+        for invalid in [
+            "not an email",
+            "a@",
+            "@example.com",
+            "a@-example.com",
+            &too_long,
+        ] {
+            assert_eq!(
+                message(|v| {
+                    v.field("email", invalid).email();
+                })
+                .as_deref(),
+                Some("must be a valid email address"),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let sku = Regex::new("^[A-Z]{3}-[0-9]+$").unwrap();
+        assert_eq!(
+            message(|v| {
+                v.field("sku", "ABC-1").matches(&sku);
+            }),
+            None
+        );
+        assert_eq!(
+            failures(|v| {
+                v.field("sku", "abc").matches(&sku);
+            }),
+            [(
+                "sku".to_string(),
+                "pattern".to_string(),
+                "has an invalid format".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_custom() {
+        let taken = |name: &&str| {
+            if *name == "admin" {
+                Err(Invalid::new("username_taken", "is already taken"))
+            } else {
+                Ok(())
+            }
+        };
+        assert_eq!(
+            failures(|v| {
+                v.field("username", &"ada").custom(taken);
+            }),
+            []
+        );
+        assert_eq!(
+            failures(|v| {
+                v.field("username", &"admin").custom(taken);
+            }),
+            [(
+                "username".to_string(),
+                "username_taken".to_string(),
+                "is already taken".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_optional_and_required() {
+        let none: Option<String> = None;
+        let empty = Some(String::new());
+        assert_eq!(
+            failures(|v| {
+                v.optional("note", &none).length(1..);
+            }),
+            []
+        );
+        assert_eq!(
+            message(|v| {
+                v.optional("note", &empty).length(1..);
+            })
+            .as_deref(),
+            Some("must not be empty")
+        );
+        assert_eq!(
+            failures(|v| {
+                v.optional("coupon", &none).required().length(1..);
+            }),
+            [(
+                "coupon".to_string(),
+                "required".to_string(),
+                "is required".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_first_failure_per_field() {
+        let email = format!("{}@", "a".repeat(300));
+        assert_eq!(
+            failures(|v| {
+                v.field("email", &email).length(..=254).email();
+            }),
+            [(
+                "email".to_string(),
+                "length".to_string(),
+                "must be at most 254 characters".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let mut order = order();
+        order.address.city.clear();
+        order.lines.push(Line {
+            sku: "abc".to_string(),
+            quantity: 100,
+        });
+        let errors = validate(&order).unwrap_err();
+        let fields: Vec<(&str, &str)> = errors
+            .errors()
+            .iter()
+            .map(|e| (e.field.as_str(), e.code.as_ref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("address.city", "length"),
+                ("lines[1].sku", "pattern"),
+                ("lines[1].quantity", "range"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_spanning_fields() {
+        struct Period {
+            start: u32,
+            end: u32,
+        }
+
+        impl Validate for Period {
+            fn validate(&self, v: &mut Validator) {
+                if self.start > self.end {
+                    v.error("end", "before_start", "must not be before start");
+                }
+            }
+        }
+
+        struct Booking {
+            period: Period,
+        }
+
+        impl Validate for Booking {
+            fn validate(&self, v: &mut Validator) {
+                v.nested("period", &self.period);
+            }
+        }
+
+        let booking = Booking {
+            period: Period { start: 2, end: 1 },
+        };
+        let errors = validate(&booking).unwrap_err();
+        assert_eq!(errors.errors()[0].field, "period.end");
+        assert_eq!(errors.errors()[0].code, "before_start");
+    }
+
+    #[test]
+    fn test_errors_response() {
+        let errors = ValidationErrors::from(FieldError {
+            field: "name".to_string(),
+            code: "length".into(),
+            message: "must not be empty".to_string(),
+        });
+        assert_eq!(errors.to_string(), "invalid fields: name must not be empty");
+        let error = SwordError::from(errors);
+        assert_eq!(
+            (error.status(), error.code()),
+            (StatusCode::UNPROCESSABLE_ENTITY, "validation_failed")
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Layers generated from the tables of an existing Postgres database.
+
+use super::scaffold::{
+    field_name, pascal, singularize, Column, ColumnType, Names, Relation, RelationKind, Resource,
+    MODELS_DIR,
+};
+use super::Changes;
+use sqlx::postgres::PgConnection;
+use sqlx::{Connection, Row};
+use std::path::Path;
+
+/// Adds the layers of each of `tables`, read from the database's current
+/// schema.
+pub fn generate(
+    changes: &mut Changes,
+    project: &Path,
+    database_url: Option<String>,
+    tables: &[String],
+) -> anyhow::Result<()> {
+    let database_url = database_url_for(project, database_url)?;
+    let mut names: Vec<&str> = Vec::new();
+    for table in tables.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !names.contains(&table) {
+            names.push(table);
+        }
+    }
+    if names.is_empty() {
+        anyhow::bail!("Give at least one table, e.g. --tables orders,customers");
+    }
+
+    let schema = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(introspect(&database_url, &names))?;
+
+    let resources = schema
+        .tables
+        .iter()
+        .map(|table| schema.resource(table, changes))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for resource in resources {
+        resource.generate(changes)?;
+    }
+    Ok(())
+}
+
+/// `--database-url`, then `DATABASE_URL`, then `DATABASE_URL` in the
+/// project's `.env`.
+fn database_url_for(project: &Path, flag: Option<String>) -> anyhow::Result<String> {
+    let url = match flag.or_else(|| std::env::var("DATABASE_URL").ok()) {
+        Some(url) => url,
+        None => dotenvy::from_path_iter(project.join(".env"))
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .find(|(key, _)| key == "DATABASE_URL")
+            .map(|(_, url)| url)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Set DATABASE_URL, in the environment or .env, or pass --database-url"
+                )
+            })?,
+    };
+    if !url.starts_with("postgres://") && !url.starts_with("postgresql://") {
+        anyhow::bail!("generate from-db only supports Postgres databases");
+    }
+    Ok(url)
+}
+
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+}
+
+/// A single-column foreign key, by database names.
+struct ForeignKey {
+    from_table: String,
+    from_column: String,
+    to_table: String,
+    to_column: String,
+}
+
+struct Schema {
+    tables: Vec<Table>,
+    /// Foreign keys from or to any of `tables`.
+    foreign_keys: Vec<ForeignKey>,
+}
+
+const COLUMNS: &str = "
+    SELECT a.attname::text AS name,
+           t.typname::text AS type,
+           NOT a.attnotnull AS nullable,
+           pg_get_expr(d.adbin, d.adrelid) AS default_value,
+           a.attidentity <> '' OR a.attgenerated <> '' AS identity,
+           EXISTS (
+               SELECT 1 FROM pg_index i
+               WHERE i.indrelid = c.oid AND i.indisprimary AND a.attnum = ANY(i.indkey)
+           ) AS primary_key
+    FROM pg_attribute a
+    JOIN pg_class c ON c.oid = a.attrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    JOIN pg_type t ON t.oid = a.atttypid
+    LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+    WHERE n.nspname = current_schema() AND c.relname = $1 AND c.relkind IN ('r', 'p')
+      AND a.attnum > 0 AND NOT a.attisdropped
+    ORDER BY a.attnum";
+
+const FOREIGN_KEYS: &str = "
+    SELECT src.relname::text AS from_table, sa.attname::text AS from_column,
+           dst.relname::text AS to_table, da.attname::text AS to_column
+    FROM pg_constraint k
+    JOIN pg_class src ON src.oid = k.conrelid
+    JOIN pg_class dst ON dst.oid = k.confrelid
+    JOIN pg_namespace n ON n.oid = src.relnamespace
+    JOIN pg_attribute sa ON sa.attrelid = k.conrelid AND sa.attnum = k.conkey[1]
+    JOIN pg_attribute da ON da.attrelid = k.confrelid AND da.attnum = k.confkey[1]
+    WHERE k.contype = 'f' AND cardinality(k.conkey) = 1 AND n.nspname = current_schema()
+      AND (src.relname = ANY($1) OR dst.relname = ANY($1))
+    ORDER BY k.conname";
+
+async fn introspect(database_url: &str, tables: &[&str]) -> anyhow::Result<Schema> {
+    let mut conn = PgConnection::connect(database_url).await?;
+
+    let mut schema = Schema {
+        tables: Vec::new(),
+        foreign_keys: Vec::new(),
+    };
+    for &table in tables {
+        let rows = sqlx::query(COLUMNS)
+            .bind(table)
+            .fetch_all(&mut conn)
+            .await?;
+        if rows.is_empty() {
+            anyhow::bail!("Table '{}' not found in the current schema", table);
+        }
+        let columns = rows
+            .iter()
+            .map(|row| {
+                let name: String = row.try_get("name")?;
+                let ty: String = row.try_get("type")?;
+                let default: Option<String> = row.try_get("default_value")?;
+                let ty = column_type(&ty).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Column {}.{} has type '{}', which is not supported",
+                        table,
+                        name,
+                        ty
+                    )
+                })?;
+                let mut column = Column::new(&name, ty);
+                column.nullable = row.try_get("nullable")?;
+                column.primary_key = row.try_get("primary_key")?;
+                column.generated =
+                    row.try_get::<bool, _>("identity")? || is_generated(default.as_deref());
+                Ok(column)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        schema.tables.push(Table {
+            name: table.to_string(),
+            columns,
+        });
+    }
+
+    for row in sqlx::query(FOREIGN_KEYS)
+        .bind(tables)
+        .fetch_all(&mut conn)
+        .await?
+    {
+        schema.foreign_keys.push(ForeignKey {
+            from_table: row.try_get("from_table")?,
+            from_column: row.try_get("from_column")?,
+            to_table: row.try_get("to_table")?,
+            to_column: row.try_get("to_column")?,
+        });
+    }
+
+    conn.close().await?;
+    Ok(schema)
+}
+
+fn column_type(name: &str) -> Option<ColumnType> {
+    Some(match name {
+        "int2" => ColumnType::SmallInt,
+        "int4" => ColumnType::Int,
+        "int8" => ColumnType::BigInt,
+        "bool" => ColumnType::Bool,
+        "text" | "varchar" | "bpchar" | "citext" => ColumnType::String,
+        "float4" => ColumnType::Float,
+        "float8" => ColumnType::Double,
+        "numeric" => ColumnType::Decimal,
+        "timestamptz" => ColumnType::DateTimeUtc,
+        "timestamp" => ColumnType::DateTime,
+        "date" => ColumnType::Date,
+        "time" => ColumnType::Time,
+        "uuid" => ColumnType::Uuid,
+        "json" | "jsonb" => ColumnType::Json,
+        "bytea" => ColumnType::Bytes,
+        _ => return None,
+    })
+}
+
+/// Whether a column default produces a fresh value (a sequence, the current
+/// time or a random UUID), rather than a constant a client may override.
+fn is_generated(default: Option<&str>) -> bool {
+    let Some(default) = default else {
+        return false;
+    };
+    let default = default.to_ascii_lowercase();
+    [
+        "nextval(",
+        "now()",
+        "current_timestamp",
+        "current_date",
+        "gen_random_uuid()",
+        "uuid_generate_",
+    ]
+    .iter()
+    .any(|f| default.contains(f))
+}
+
+impl Schema {
+    fn generates(&self, table: &str) -> bool {
+        self.tables.iter().any(|t| t.name == table)
+    }
+
+    fn resource(&self, table: &Table, changes: &Changes) -> anyhow::Result<Resource> {
+        let names = Names::from_table(&table.name)?;
+        let mut relations: Vec<Relation> = Vec::new();
+        let mut add = |preferred: String, fallback: String, table: &str, kind| {
+            let variant = if relations.iter().any(|r| r.variant == preferred) {
+                fallback
+            } else {
+                preferred
+            };
+            relations.push(Relation {
+                variant,
+                table: table.to_string(),
+                kind,
+            });
+        };
+
+        for fk in self
+            .foreign_keys
+            .iter()
+            .filter(|k| k.from_table == table.name)
+        {
+            let model = Path::new(MODELS_DIR).join(format!("{}.rs", fk.to_table));
+            if !self.generates(&fk.to_table) && !changes.exists(&model) {
+                println!(
+                    "  skip   relation {}.{} -> {}: there is no {}",
+                    fk.from_table,
+                    fk.from_column,
+                    fk.to_table,
+                    model.display()
+                );
+                continue;
+            }
+            let field = field_name(&fk.from_column);
+            let preferred = match field.strip_suffix("_id") {
+                Some(stem) => pascal(stem),
+                None => pascal(&singularize(&fk.to_table)),
+            };
+            add(
+                preferred,
+                pascal(&field),
+                &fk.to_table,
+                RelationKind::BelongsTo {
+                    from: pascal(&field),
+                    to: pascal(&field_name(&fk.to_column)),
+                },
+            );
+        }
+
+        // `has_many` needs the other model to be `Related` to this one,
+        // which holds for the tables generated here with a single foreign
+        // key to it.
+        for fk in self.foreign_keys.iter().filter(|k| {
+            k.to_table == table.name && k.from_table != table.name && self.generates(&k.from_table)
+        }) {
+            let keys = self
+                .foreign_keys
+                .iter()
+                .filter(|k| k.from_table == fk.from_table && k.to_table == table.name)
+                .count();
+            if keys == 1 {
+                add(
+                    pascal(&fk.from_table),
+                    pascal(&format!("{}_{}", fk.from_table, fk.from_column)),
+                    &fk.from_table,
+                    RelationKind::HasMany,
+                );
+            }
+        }
+
+        Ok(Resource {
+            names,
+            columns: table.columns.clone(),
+            relations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_defaults() {
+        assert!(is_generated(Some("nextval('orders_id_seq'::regclass)")));
+        assert!(is_generated(Some("now()")));
+        assert!(is_generated(Some("CURRENT_TIMESTAMP")));
+        assert!(!is_generated(Some("'pending'::text")));
+        assert!(!is_generated(None));
+        assert_eq!(column_type("timestamptz"), Some(ColumnType::DateTimeUtc));
+        assert_eq!(column_type("_int4"), None);
+    }
+}
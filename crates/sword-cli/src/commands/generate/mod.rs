@@ -0,0 +1,162 @@
+mod from_db;
+mod resource;
+mod scaffold;
+
+use clap::Subcommand;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Subcommand)]
+pub enum GenerateAction {
+    /// Generate an entity with its model, migration, repository, service,
+    /// controller and routes
+    Resource {
+        /// Name of the resource, e.g. Post
+        name: String,
+
+        /// Fields as name:type, e.g. title:string body:text? author_id:references:users
+        fields: Vec<String>,
+    },
+    /// Generate the model, entity, repository, service, controller and
+    /// routes of existing Postgres tables
+    FromDb {
+        /// Tables to generate, e.g. orders,customers
+        #[arg(long, value_delimiter = ',', required = true)]
+        tables: Vec<String>,
+
+        /// Database to read (default: DATABASE_URL, from the environment or .env)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
+}
+
+pub fn execute(action: GenerateAction) -> anyhow::Result<()> {
+    let project = super::migrate::find_project(&std::env::current_dir()?)?;
+    let mut changes = Changes::new(&project);
+
+    match action {
+        GenerateAction::Resource { name, fields } => {
+            resource::generate(&mut changes, &name, &fields)?
+        }
+        GenerateAction::FromDb {
+            tables,
+            database_url,
+        } => from_db::generate(&mut changes, &project, database_url, &tables)?,
+    }
+    changes.write()
+}
+
+/// Files to create or update in a project, kept in memory until every one
+/// of them has been rendered.
+pub struct Changes {
+    project: PathBuf,
+    files: Vec<GeneratedFile>,
+}
+
+struct GeneratedFile {
+    /// Relative to the project root.
+    path: PathBuf,
+    content: String,
+    created: bool,
+}
+
+impl Changes {
+    fn new(project: &Path) -> Self {
+        Self {
+            project: project.to_path_buf(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Whether `path` exists in the project or is about to be created.
+    pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.project.join(path).exists() || self.files.iter().any(|f| f.path == path)
+    }
+
+    /// Adds a new file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file already exists.
+    pub fn create(&mut self, path: impl Into<PathBuf>, content: String) -> anyhow::Result<()> {
+        let path = path.into();
+        if self.exists(&path) {
+            anyhow::bail!("'{}' already exists", path.display());
+        }
+        self.files.push(GeneratedFile {
+            path,
+            content,
+            created: true,
+        });
+        Ok(())
+    }
+
+    /// Rewrites an existing file with `edit`, on top of earlier changes to
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or `edit` fails.
+    pub fn update(
+        &mut self,
+        path: &str,
+        edit: impl FnOnce(&str) -> anyhow::Result<String>,
+    ) -> anyhow::Result<()> {
+        if let Some(file) = self.files.iter_mut().find(|f| f.path == Path::new(path)) {
+            file.content = edit(&file.content)?;
+            return Ok(());
+        }
+        let source = fs::read_to_string(self.project.join(path))
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path, e))?;
+        self.files.push(GeneratedFile {
+            path: path.into(),
+            content: edit(&source)?,
+            created: false,
+        });
+        Ok(())
+    }
+
+    /// Writes every file and formats the new ones with rustfmt when it is
+    /// installed.
+    fn write(self) -> anyhow::Result<()> {
+        for file in &self.files {
+            let path = self.project.join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &file.content)?;
+            let verb = if file.created { "create" } else { "update" };
+            println!("  {} {}", verb, file.path.display());
+        }
+
+        let created: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|f| f.created)
+            .map(|f| self.project.join(&f.path))
+            .collect();
+        let _ = Command::new("rustfmt")
+            .args(["--edition", "2021"])
+            .args(&created)
+            .status();
+
+        Ok(())
+    }
+}
+
+/// Adds `pub mod <module>;` to a `mod.rs` file.
+fn add_module(source: &str, module: &str) -> String {
+    let decl = format!("pub mod {};", module);
+    if source.lines().any(|line| line.trim() == decl) {
+        return source.to_string();
+    }
+    let mut out = source.trim_end().to_string();
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&decl);
+    out.push('\n');
+    out
+}
@@ -0,0 +1,271 @@
+use super::scaffold::{
+    is_identifier, pascal, pluralize, words, Column, ColumnType, Names, Relation, RelationKind,
+    Resource, MODELS_DIR,
+};
+use super::Changes;
+use crate::commands::migrate::{self, MIGRATION_DIR};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const MIGRATION: &str = include_str!("../../../templates/resource/migration.rs");
+
+/// Adds a new resource, with the migration creating its table.
+pub fn generate(changes: &mut Changes, name: &str, specs: &[String]) -> anyhow::Result<()> {
+    let names = Names::new(name)?;
+    if specs.is_empty() {
+        anyhow::bail!("Give at least one field, e.g. title:string");
+    }
+    let fields = specs
+        .iter()
+        .map(|spec| Field::parse(spec))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut seen = BTreeSet::new();
+    for field in &fields {
+        if !seen.insert(field.name.as_str()) {
+            anyhow::bail!("Field '{}' is listed twice", field.name);
+        }
+        if let FieldType::References(table) = &field.ty {
+            let model = Path::new(MODELS_DIR).join(format!("{}.rs", table));
+            if *table != names.table && !changes.exists(&model) {
+                anyhow::bail!(
+                    "Field '{}' references '{}', but there is no {}",
+                    field.name,
+                    table,
+                    model.display()
+                );
+            }
+        }
+    }
+
+    let migration = migrate::module_name(&format!("create_{}", names.table));
+    changes.create(
+        format!("{}/{}.rs", MIGRATION_DIR, migration),
+        render_migration(&names.table, &fields),
+    )?;
+    changes.update(&format!("{}/mod.rs", MIGRATION_DIR), |source| {
+        migrate::register(source, &migration)
+    })?;
+
+    let mut id = Column::new("id", ColumnType::BigInt);
+    id.primary_key = true;
+    id.generated = true;
+    let mut created_at = Column::new("created_at", ColumnType::DateTimeUtc);
+    created_at.generated = true;
+    let columns = [id]
+        .into_iter()
+        .chain(fields.iter().map(Field::column))
+        .chain([created_at])
+        .collect();
+    let relations = fields
+        .iter()
+        .filter_map(|field| match &field.ty {
+            FieldType::References(table) => Some(Relation {
+                variant: field.relation_name(),
+                table: table.clone(),
+                kind: RelationKind::BelongsTo {
+                    from: pascal(&field.name),
+                    to: "Id".to_string(),
+                },
+            }),
+            _ => None,
+        })
+        .collect();
+
+    Resource {
+        names,
+        columns,
+        relations,
+    }
+    .generate(changes)
+}
+
+fn render_migration(table: &str, fields: &[Field]) -> String {
+    let block = |f: &dyn Fn(&Field) -> String| fields.iter().map(f).collect::<String>();
+    let iden = pascal(table);
+    let referenced: BTreeSet<&str> = fields
+        .iter()
+        .filter_map(|f| match &f.ty {
+            FieldType::References(target) if target != table => Some(target.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    MIGRATION
+        .replace("{{Table}}", &iden)
+        .replace("{{columns}}", &block(&|f| f.column_def(&iden)))
+        .replace("{{foreign_keys}}", &block(&|f| f.foreign_key(table)))
+        .replace(
+            "{{idens}}",
+            &block(&|f| format!("    {},\n", pascal(&f.name))),
+        )
+        .replace(
+            "{{referenced_idens}}",
+            &referenced
+                .into_iter()
+                .map(|target| {
+                    format!(
+                        "\n#[derive(DeriveIden)]\nenum {} {{\n    Table,\n    Id,\n}}\n",
+                        pascal(target)
+                    )
+                })
+                .collect::<String>(),
+        )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FieldType {
+    String,
+    Text,
+    Bool,
+    Int,
+    BigInt,
+    Float,
+    DateTime,
+    /// A `bigint` foreign key to the `id` of the given table.
+    References(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    name: String,
+    ty: FieldType,
+    optional: bool,
+}
+
+impl Field {
+    /// Parses `name:type`, `name:type?` (nullable) or
+    /// `name:references[:table]`.
+    fn parse(spec: &str) -> anyhow::Result<Self> {
+        let (spec, optional) = match spec.strip_suffix('?') {
+            Some(spec) => (spec, true),
+            None => (spec, false),
+        };
+        let parts: Vec<&str> = spec.split(':').collect();
+        let (name, ty) = match parts.as_slice() {
+            [name, ty, rest @ ..] => (*name, (*ty, rest)),
+            _ => anyhow::bail!("Invalid field '{}': expected name:type", spec),
+        };
+
+        if !is_identifier(name) {
+            anyhow::bail!("Invalid field name '{}': use snake_case", name);
+        }
+        if name == "id" || name == "created_at" {
+            anyhow::bail!("Field '{}' is added to every resource", name);
+        }
+
+        let ty = match ty {
+            ("string", []) => FieldType::String,
+            ("text", []) => FieldType::Text,
+            ("bool" | "boolean", []) => FieldType::Bool,
+            ("int" | "integer", []) => FieldType::Int,
+            ("bigint", []) => FieldType::BigInt,
+            ("float" | "double", []) => FieldType::Float,
+            ("datetime" | "timestamp", []) => FieldType::DateTime,
+            ("references", []) => {
+                let target = name.strip_suffix("_id").ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Field '{}': name the table, e.g. {}:references:users",
+                        name,
+                        name
+                    )
+                })?;
+                FieldType::References(pluralize(target))
+            }
+            ("references", [table]) if !table.is_empty() => {
+                FieldType::References(words(table).join("_"))
+            }
+            (ty, _) => anyhow::bail!(
+                "Unknown type '{}' for field '{}': expected string, text, bool, int, bigint, float, datetime or references",
+                ty,
+                name
+            ),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            ty,
+            optional,
+        })
+    }
+
+    /// The model column of this field.
+    fn column(&self) -> Column {
+        let ty = match self.ty {
+            FieldType::String | FieldType::Text => ColumnType::String,
+            FieldType::Bool => ColumnType::Bool,
+            FieldType::Int => ColumnType::Int,
+            FieldType::BigInt | FieldType::References(_) => ColumnType::BigInt,
+            FieldType::Float => ColumnType::Double,
+            FieldType::DateTime => ColumnType::DateTimeUtc,
+        };
+        let mut column = Column::new(&self.name, ty);
+        column.nullable = self.optional;
+        column
+    }
+
+    /// `.col(...)` line of the migration.
+    fn column_def(&self, table: &str) -> String {
+        let ty = match self.ty {
+            FieldType::String => "string",
+            FieldType::Text => "text",
+            FieldType::Bool => "boolean",
+            FieldType::Int => "integer",
+            FieldType::BigInt | FieldType::References(_) => "big_integer",
+            FieldType::Float => "double",
+            FieldType::DateTime => "timestamp_with_time_zone",
+        };
+        let null = if self.optional { "null" } else { "not_null" };
+        format!(
+            "                    .col(ColumnDef::new({}::{}).{}().{}())\n",
+            table,
+            pascal(&self.name),
+            ty,
+            null
+        )
+    }
+
+    /// `.foreign_key(...)` call of the migration, for references.
+    fn foreign_key(&self, table: &str) -> String {
+        let FieldType::References(target) = &self.ty else {
+            return String::new();
+        };
+        let on_delete = if self.optional { "SetNull" } else { "Cascade" };
+        format!(
+            "                    .foreign_key(\n                        ForeignKey::create()\n                            .name(\"fk_{table}_{name}\")\n                            .from({from}::Table, {from}::{column})\n                            .to({to}::Table, {to}::Id)\n                            .on_delete(ForeignKeyAction::{on_delete}),\n                    )\n",
+            table = table,
+            name = self.name,
+            from = pascal(table),
+            column = pascal(&self.name),
+            to = pascal(target),
+            on_delete = on_delete,
+        )
+    }
+
+    /// Variant of the model's `Relation` enum: `author_id` gives `Author`.
+    fn relation_name(&self) -> String {
+        pascal(self.name.strip_suffix("_id").unwrap_or(&self.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(
+            Field::parse("author_id:references:users").unwrap().ty,
+            FieldType::References("users".to_string())
+        );
+        assert_eq!(
+            Field::parse("category_id:references").unwrap().ty,
+            FieldType::References("categories".to_string())
+        );
+        let field = Field::parse("published_at:datetime?").unwrap();
+        assert!(field.optional);
+        assert_eq!(field.column().ty, ColumnType::DateTimeUtc);
+        assert!(Field::parse("type:string").is_err());
+        assert!(Field::parse("title:varchar").is_err());
+        assert!(Field::parse("id:bigint").is_err());
+    }
+}
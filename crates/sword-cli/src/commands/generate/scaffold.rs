@@ -0,0 +1,826 @@
+//! Rendering of the layers of a resource (entity, model, repository,
+//! service and controller) and their wiring into an existing project.
+
+use super::{add_module, Changes};
+use std::collections::BTreeSet;
+
+const ENTITY: &str = include_str!("../../../templates/resource/entity.rs");
+const MODEL: &str = include_str!("../../../templates/resource/model.rs");
+const REPOSITORY: &str = include_str!("../../../templates/resource/repository.rs");
+const SERVICE: &str = include_str!("../../../templates/resource/service.rs");
+const CONTROLLER: &str = include_str!("../../../templates/resource/controller.rs");
+
+pub const MODELS_DIR: &str = "src/infrastructure/database/models";
+const ROUTES: &str = "src/app/routes.rs";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// The spellings of a resource name.
+pub struct Names {
+    /// `blog_post`: modules and variables.
+    pub name: String,
+    /// `BlogPost`: types.
+    pub pascal: String,
+    /// `blog_posts`: collections and the controller module.
+    pub plural: String,
+    /// `blog_posts`: table and model module.
+    pub table: String,
+    /// `/blog-posts`: route prefix.
+    pub route: String,
+    /// `blog post`: messages.
+    pub label: String,
+}
+
+impl Names {
+    /// Names of a new resource, whose table is the plural of `name`.
+    pub fn new(name: &str) -> anyhow::Result<Self> {
+        let words = words(name);
+        if words.is_empty() || words[0].starts_with(|c: char| c.is_ascii_digit()) {
+            anyhow::bail!("Invalid resource name '{}'", name);
+        }
+        let name = words.join("_");
+        let table = pluralize(&name);
+        Ok(Self::with_table(name, table))
+    }
+
+    /// Names of the resource stored in an existing table.
+    pub fn from_table(table: &str) -> anyhow::Result<Self> {
+        if !is_identifier(table) {
+            anyhow::bail!("Table '{}' is not a valid Rust module name", table);
+        }
+        Ok(Self::with_table(singularize(table), table.to_string()))
+    }
+
+    fn with_table(name: String, table: String) -> Self {
+        let plural = if table == name {
+            pluralize(&name)
+        } else {
+            table.clone()
+        };
+        Self {
+            pascal: pascal(&name),
+            route: format!("/{}", plural.replace('_', "-")),
+            label: name.replace('_', " "),
+            plural,
+            table,
+            name,
+        }
+    }
+}
+
+/// Rust-side type of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    String,
+    Bool,
+    SmallInt,
+    Int,
+    BigInt,
+    Float,
+    Double,
+    Decimal,
+    DateTimeUtc,
+    DateTime,
+    Date,
+    Time,
+    Uuid,
+    Json,
+    Bytes,
+}
+
+impl ColumnType {
+    /// Type in the domain entity and request body.
+    fn domain(self) -> &'static str {
+        match self {
+            Self::String => "String",
+            Self::Bool => "bool",
+            Self::SmallInt => "i16",
+            Self::Int => "i32",
+            Self::BigInt => "i64",
+            Self::Float => "f32",
+            Self::Double => "f64",
+            Self::Decimal => "Decimal",
+            Self::DateTimeUtc => "DateTime<Utc>",
+            Self::DateTime => "NaiveDateTime",
+            Self::Date => "NaiveDate",
+            Self::Time => "NaiveTime",
+            Self::Uuid => "Uuid",
+            Self::Json => "serde_json::Value",
+            Self::Bytes => "Vec<u8>",
+        }
+    }
+
+    /// Type in the SeaORM model, as exported by its prelude.
+    fn model(self) -> &'static str {
+        match self {
+            Self::DateTimeUtc => "DateTimeUtc",
+            Self::DateTime => "DateTime",
+            Self::Date => "Date",
+            Self::Time => "Time",
+            Self::Json => "Json",
+            other => other.domain(),
+        }
+    }
+
+    /// Names to import for [`Self::domain`], grouped by crate.
+    fn imports(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Decimal => &[("sea_orm::prelude", "Decimal")],
+            Self::Uuid => &[("sea_orm::prelude", "Uuid")],
+            Self::DateTimeUtc => &[("chrono", "DateTime"), ("chrono", "Utc")],
+            Self::DateTime => &[("chrono", "NaiveDateTime")],
+            Self::Date => &[("chrono", "NaiveDate")],
+            Self::Time => &[("chrono", "NaiveTime")],
+            _ => &[],
+        }
+    }
+}
+
+/// A column of the resource's table.
+#[derive(Debug, Clone)]
+pub struct Column {
+    /// Name of the Rust field.
+    pub field: String,
+    /// Name in the database, when it differs from `field`.
+    pub column_name: Option<String>,
+    pub ty: ColumnType,
+    pub nullable: bool,
+    pub primary_key: bool,
+    /// Set by the database (e.g. a serial key or a `now()` default), so it
+    /// is left out of requests.
+    pub generated: bool,
+}
+
+impl Column {
+    /// A column whose field is named after `name`, renamed if `name` is not
+    /// a valid field name.
+    pub fn new(name: &str, ty: ColumnType) -> Self {
+        let field = field_name(name);
+        Self {
+            column_name: (field != name).then(|| name.to_string()),
+            field,
+            ty,
+            nullable: false,
+            primary_key: false,
+            generated: false,
+        }
+    }
+
+    /// Variant of the model's `Column` enum.
+    pub fn variant(&self) -> String {
+        pascal(&self.field)
+    }
+
+    fn wrap(&self, ty: &str) -> String {
+        if self.nullable {
+            format!("Option<{}>", ty)
+        } else {
+            ty.to_string()
+        }
+    }
+
+    fn serde_rename(&self) -> String {
+        match &self.column_name {
+            Some(name) => format!("    #[serde(rename = \"{}\")]\n", name),
+            None => String::new(),
+        }
+    }
+
+    /// Line of the request's `Validate` impl: required strings must not be
+    /// empty, and an `email` must be an email address.
+    fn rule(&self) -> String {
+        if self.ty != ColumnType::String || self.nullable {
+            return String::new();
+        }
+        let email = if self.field == "email" {
+            ".email()"
+        } else {
+            ""
+        };
+        format!(
+            "        v.field(\"{}\", &self.{}).length(1..){};\n",
+            self.column_name.as_deref().unwrap_or(&self.field),
+            self.field,
+            email
+        )
+    }
+
+    fn model_field(&self) -> String {
+        let mut attrs = Vec::new();
+        if self.primary_key {
+            attrs.push("primary_key".to_string());
+            let integer = matches!(
+                self.ty,
+                ColumnType::SmallInt | ColumnType::Int | ColumnType::BigInt
+            );
+            if !integer || !self.generated {
+                attrs.push("auto_increment = false".to_string());
+            }
+        }
+        if let Some(name) = &self.column_name {
+            attrs.push(format!("column_name = \"{}\"", name));
+        }
+        let attrs = if attrs.is_empty() {
+            String::new()
+        } else {
+            format!("    #[sea_orm({})]\n", attrs.join(", "))
+        };
+        format!(
+            "{}    pub {}: {},\n",
+            attrs,
+            self.field,
+            self.wrap(self.ty.model())
+        )
+    }
+
+    /// Type in the response body: timestamps are RFC 3339 strings.
+    fn response_type(&self) -> String {
+        match self.ty {
+            ColumnType::DateTimeUtc => self.wrap("String"),
+            ty => self.wrap(ty.domain()),
+        }
+    }
+
+    fn to_response(&self, var: &str) -> String {
+        match (self.ty, self.nullable) {
+            (ColumnType::DateTimeUtc, false) => format!("{}.{}.to_rfc3339()", var, self.field),
+            (ColumnType::DateTimeUtc, true) => {
+                format!("{}.{}.map(|t| t.to_rfc3339())", var, self.field)
+            }
+            _ => format!("{}.{}", var, self.field),
+        }
+    }
+}
+
+/// A variant of the model's `Relation` enum.
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub variant: String,
+    /// Model module of the related table.
+    pub table: String,
+    pub kind: RelationKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum RelationKind {
+    /// This table holds a foreign key: `from` and `to` are `Column`
+    /// variants of this model and of the related one.
+    BelongsTo { from: String, to: String },
+    /// The related table holds a foreign key to this one.
+    HasMany,
+}
+
+/// A resource to render: its names, columns and relations.
+pub struct Resource {
+    pub names: Names,
+    pub columns: Vec<Column>,
+    pub relations: Vec<Relation>,
+}
+
+impl Resource {
+    /// Adds the resource's layers to `changes`: its files, their `mod`
+    /// declarations and its routes.
+    pub fn generate(&self, changes: &mut Changes) -> anyhow::Result<()> {
+        let names = &self.names;
+        let controller = format!("{}_controller", names.plural);
+        let layers = [
+            ("src/domain/entities", names.name.clone(), ENTITY),
+            (MODELS_DIR, names.table.clone(), MODEL),
+            (
+                "src/domain/repositories",
+                format!("{}_repository", names.name),
+                REPOSITORY,
+            ),
+            (
+                "src/domain/services",
+                format!("{}_service", names.name),
+                SERVICE,
+            ),
+            ("src/app/controllers", controller, CONTROLLER),
+        ];
+        for (dir, module, template) in layers {
+            changes.create(format!("{}/{}.rs", dir, module), self.render(template)?)?;
+            changes.update(&format!("{}/mod.rs", dir), |source| {
+                Ok(add_module(source, &module))
+            })?;
+        }
+        changes.update(ROUTES, |source| wire_routes(source, names))
+    }
+
+    fn primary_key(&self) -> anyhow::Result<&Column> {
+        let mut keys = self.columns.iter().filter(|c| c.primary_key);
+        match (keys.next(), keys.next()) {
+            (Some(key), None) => Ok(key),
+            _ => anyhow::bail!(
+                "Table '{}' needs a single-column primary key",
+                self.names.table
+            ),
+        }
+    }
+
+    /// Columns set from a request. The primary key is only included when the
+    /// database does not generate it.
+    fn inputs(&self) -> impl Iterator<Item = &Column> {
+        self.columns.iter().filter(|c| !c.generated)
+    }
+
+    pub fn render(&self, template: &str) -> anyhow::Result<String> {
+        let names = &self.names;
+        let key = self.primary_key()?;
+        if !self.inputs().any(|c| !c.primary_key) {
+            anyhow::bail!(
+                "Table '{}' has no columns to update besides its primary key",
+                names.table
+            );
+        }
+        let all = |f: &dyn Fn(&Column) -> String| self.columns.iter().map(f).collect::<String>();
+        let inputs = |f: &dyn Fn(&Column) -> String| self.inputs().map(f).collect::<String>();
+        let rules = inputs(&Column::rule);
+        let mut label = names.label.clone();
+        label[..1].make_ascii_uppercase();
+
+        let controller_types = self
+            .inputs()
+            .map(|c| c.ty)
+            .chain(
+                self.columns
+                    .iter()
+                    .map(|c| c.ty)
+                    .filter(|ty| *ty != ColumnType::DateTimeUtc),
+            )
+            .chain([key.ty]);
+        let eq = if self
+            .columns
+            .iter()
+            .any(|c| matches!(c.ty, ColumnType::Float | ColumnType::Double))
+        {
+            ""
+        } else {
+            ", Eq"
+        };
+
+        Ok(template
+            .replace("{{Name}}", &names.pascal)
+            .replace("{{name}}", &names.name)
+            .replace("{{names}}", &names.plural)
+            .replace("{{table}}", &names.table)
+            .replace("{{label}}", &names.label)
+            .replace("{{Label}}", &label)
+            .replace("{{Id}}", key.ty.domain())
+            .replace("{{IdColumn}}", &key.variant())
+            .replace("{{eq}}", eq)
+            .replace(
+                "{{entity_imports}}",
+                &imports(self.columns.iter().map(|c| c.ty), "\n"),
+            )
+            .replace("{{id_imports}}", &imports([key.ty], ""))
+            .replace("{{controller_imports}}", &imports(controller_types, ""))
+            .replace(
+                "{{entity_fields}}",
+                &all(&|c| format!("    pub {}: {},\n", c.field, c.wrap(c.ty.domain()))),
+            )
+            .replace(
+                "{{input_fields}}",
+                &inputs(&|c| format!("    pub {}: {},\n", c.field, c.wrap(c.ty.domain()))),
+            )
+            .replace("{{model_fields}}", &all(&Column::model_field))
+            .replace("{{relation}}", &self.relation())
+            .replace(
+                "{{active_set}}",
+                &inputs(&|c| format!("            {0}: Set(input.{0}),\n", c.field)),
+            )
+            .replace(
+                "{{active_default}}",
+                if self.columns.iter().any(|c| c.generated) {
+                    "            ..Default::default()\n"
+                } else {
+                    ""
+                },
+            )
+            .replace(
+                "{{entity_from_model}}",
+                &all(&|c| format!("            {0}: model.{0},\n", c.field)),
+            )
+            .replace(
+                "{{request_fields}}",
+                &inputs(&|c| {
+                    format!(
+                        "{}    pub {}: {},\n",
+                        c.serde_rename(),
+                        c.field,
+                        c.wrap(c.ty.domain())
+                    )
+                }),
+            )
+            .replace("{{validator}}", if rules.is_empty() { "_v" } else { "v" })
+            .replace("{{validation_rules}}", &rules)
+            .replace(
+                "{{response_fields}}",
+                &all(&|c| {
+                    format!(
+                        "{}    pub {}: {},\n",
+                        c.serde_rename(),
+                        c.field,
+                        c.response_type()
+                    )
+                }),
+            )
+            .replace(
+                "{{new_from_request}}",
+                &inputs(&|c| format!("            {0}: request.{0},\n", c.field)),
+            )
+            .replace(
+                "{{response_from_entity}}",
+                &all(&|c| format!("            {}: {},\n", c.field, c.to_response(&names.name))),
+            ))
+    }
+
+    /// The model's `Relation` enum, with a `Related` impl for each other
+    /// table it is related to through exactly one variant.
+    fn relation(&self) -> String {
+        let derive = "#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]\n";
+        if self.relations.is_empty() {
+            return format!("{}pub enum Relation {{}}\n", derive);
+        }
+        let module = |table: &str| {
+            if table == self.names.table {
+                String::new()
+            } else {
+                format!("super::{}::", table)
+            }
+        };
+
+        let mut out = format!("{}pub enum Relation {{\n", derive);
+        for relation in &self.relations {
+            let target = module(&relation.table);
+            match &relation.kind {
+                RelationKind::BelongsTo { from, to } => out.push_str(&format!(
+                    "    #[sea_orm(\n        belongs_to = \"{0}Entity\",\n        from = \"Column::{1}\",\n        to = \"{0}Column::{2}\"\n    )]\n    {3},\n",
+                    target, from, to, relation.variant
+                )),
+                RelationKind::HasMany => out.push_str(&format!(
+                    "    #[sea_orm(has_many = \"{}Entity\")]\n    {},\n",
+                    target, relation.variant
+                )),
+            }
+        }
+        out.push_str("}\n");
+
+        for relation in &self.relations {
+            let count = self
+                .relations
+                .iter()
+                .filter(|r| r.table == relation.table)
+                .count();
+            if count == 1 && relation.table != self.names.table {
+                out.push_str(&format!(
+                    "\nimpl Related<super::{}::Entity> for Entity {{\n    fn to() -> RelationDef {{\n        Relation::{}.def()\n    }}\n}}\n",
+                    relation.table, relation.variant
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// `use` lines for `types`, followed by `trailer` when there are any.
+fn imports(types: impl IntoIterator<Item = ColumnType>, trailer: &str) -> String {
+    let mut names: Vec<(&str, BTreeSet<&str>)> = Vec::new();
+    for (krate, name) in types.into_iter().flat_map(ColumnType::imports) {
+        match names.iter_mut().find(|(k, _)| k == krate) {
+            Some((_, set)) => {
+                set.insert(name);
+            }
+            None => names.push((krate, BTreeSet::from([*name]))),
+        }
+    }
+    if names.is_empty() {
+        return String::new();
+    }
+    names.sort();
+    let mut out = String::new();
+    for (krate, set) in names {
+        let set: Vec<&str> = set.into_iter().collect();
+        if set.len() == 1 {
+            out.push_str(&format!("use {}::{};\n", krate, set[0]));
+        } else {
+            out.push_str(&format!("use {}::{{{}}};\n", krate, set.join(", ")));
+        }
+    }
+    out + trailer
+}
+
+/// Adds the resource's controller, service and routes to `routes.rs`.
+fn wire_routes(source: &str, names: &Names) -> anyhow::Result<String> {
+    let missing = |what: &str| {
+        anyhow::anyhow!(
+            "Cannot find {} in {}; add the {} routes by hand",
+            what,
+            ROUTES,
+            names.label
+        )
+    };
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let service = format!("{}_service", names.name);
+
+    // Imports
+    let mut imports = vec![
+        format!("use crate::app::controllers::{}_controller;", names.plural),
+        format!(
+            "use crate::domain::services::{}_service::{}Service;",
+            names.name, names.pascal
+        ),
+        format!(
+            "use crate::domain::repositories::{}_repository::{}Repository;",
+            names.name, names.pascal
+        ),
+    ];
+    let documented = source.contains("ApiRouter::new()");
+    let has_get = lines.iter().any(|l| {
+        (l.starts_with("use axum") || l.starts_with("use sword_ai::openapi")) && l.contains("get")
+    });
+    if !has_get {
+        imports.insert(0, "use axum::routing::get;".to_string());
+    }
+    if documented && !source.contains("StatusCode") {
+        imports.insert(0, "use axum::http::StatusCode;".to_string());
+    }
+    if !source.contains("use std::sync::Arc;") {
+        imports.insert(0, "use std::sync::Arc;".to_string());
+    }
+    let at = lines
+        .iter()
+        .rposition(|l| l.starts_with("use "))
+        .map_or(0, |i| i + 1);
+    lines.splice(at..at, imports);
+
+    // AppState field
+    let start = lines
+        .iter()
+        .position(|l| l.contains("pub struct AppState"))
+        .ok_or_else(|| missing("`pub struct AppState`"))?;
+    let end = (start..lines.len())
+        .find(|&i| lines[i].trim() == "}")
+        .ok_or_else(|| missing("the end of `AppState`"))?;
+    lines.insert(
+        end,
+        format!(
+            "    pub {}: Arc<{}Service<{}Repository>>,",
+            service, names.pascal, names.pascal
+        ),
+    );
+
+    // State construction
+    let start = lines
+        .iter()
+        .position(|l| l.trim_start().starts_with("let state = AppState {"))
+        .ok_or_else(|| missing("`let state = AppState { ... }`"))?;
+    let indent: String = lines[start]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let end = (start..lines.len())
+        .find(|&i| lines[i].trim() == "};")
+        .ok_or_else(|| missing("the end of `let state = AppState { ... }`"))?;
+    lines.insert(
+        end,
+        format!("{}    {}: Arc::new({}),", indent, service, service),
+    );
+    let at = if start > 0 && lines[start - 1].trim().is_empty() {
+        start - 1
+    } else {
+        start
+    };
+    lines.insert(
+        at,
+        format!(
+            "{}let {} = {}Service::new({}Repository::new());",
+            indent, service, names.pascal, names.pascal
+        ),
+    );
+
+    // Routes
+    if documented {
+        return wire_api_routes(lines, names)
+            .ok_or_else(|| missing("the end of `ApiRouter::new()`"));
+    }
+    let router = lines
+        .iter()
+        .position(|l| l.contains("Router::new()"))
+        .ok_or_else(|| missing("`Router::new()`"))?;
+    let at = (router..lines.len())
+        .find(|&i| {
+            let l = lines[i].trim_start();
+            l.starts_with(".layer(") || l.starts_with(".with_state(")
+        })
+        .ok_or_else(|| missing("`.layer(...)` or `.with_state(...)` after `Router::new()`"))?;
+    let indent: String = lines[at]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let controller = format!("{}_controller", names.plural);
+    let routes = [
+        format!("{}.route(", indent),
+        format!("{}    \"{}\",", indent, names.route),
+        format!(
+            "{}    get({c}::list_{p}).post({c}::create_{n}),",
+            indent,
+            c = controller,
+            p = names.plural,
+            n = names.name
+        ),
+        format!("{})", indent),
+        format!("{}.route(", indent),
+        format!("{}    \"{}/:id\",", indent, names.route),
+        format!("{}    get({}::get_{})", indent, controller, names.name),
+        format!(
+            "{}        .put({}::update_{})",
+            indent, controller, names.name
+        ),
+        format!(
+            "{}        .delete({}::delete_{}),",
+            indent, controller, names.name
+        ),
+        format!("{})", indent),
+    ];
+    lines.splice(at..at, routes);
+
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Appends the resource's routes to the `ApiRouter::new()` chain, which
+/// ends the function building it.
+fn wire_api_routes(mut lines: Vec<String>, names: &Names) -> Option<String> {
+    let router = lines.iter().position(|l| l.contains("ApiRouter::new()"))?;
+    let at = (router..lines.len()).find(|&i| lines[i].trim() == "}")?;
+    let indent: String = lines[router]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect::<String>()
+        + "    ";
+    let controller = format!("{}_controller", names.plural);
+    let routes = [
+        format!("{}.route(", indent),
+        format!("{}    \"{}\",", indent, names.route),
+        format!("{}    get({}::list_{})", indent, controller, names.plural),
+        format!(
+            "{}        .post_with({}::create_{}, |op| op.status(StatusCode::CREATED)),",
+            indent, controller, names.name
+        ),
+        format!("{})", indent),
+        format!("{}.route(", indent),
+        format!("{}    \"{}/:id\",", indent, names.route),
+        format!("{}    get({}::get_{})", indent, controller, names.name),
+        format!(
+            "{}        .put({}::update_{})",
+            indent, controller, names.name
+        ),
+        format!(
+            "{}        .delete_with({}::delete_{}, |op| op.status(StatusCode::NO_CONTENT)),",
+            indent, controller, names.name
+        ),
+        format!("{})", indent),
+    ];
+    lines.splice(at..at, routes);
+
+    Some(lines.join("\n") + "\n")
+}
+
+/// Name of the Rust field for the column `name`: snake_case, and not a
+/// keyword.
+pub fn field_name(name: &str) -> String {
+    let mut field = words(name).join("_");
+    if field.is_empty() || field.starts_with(|c: char| c.is_ascii_digit()) {
+        field.insert_str(0, "column_");
+    }
+    if KEYWORDS.contains(&field.as_str()) {
+        field.push('_');
+    }
+    field
+}
+
+/// Whether `name` is a lowercase identifier usable as a module name.
+pub fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// Splits `BlogPost`, `blog_post` or `blog-post` into lowercase words.
+pub fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_uppercase() && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c.is_ascii_alphanumeric() {
+            current.push(c.to_ascii_lowercase());
+        } else if !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+pub fn pascal(snake: &str) -> String {
+    snake
+        .split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut w = w.to_string();
+            w[..1].make_ascii_uppercase();
+            w
+        })
+        .collect()
+}
+
+/// English plural of the last word of a snake_case name.
+pub fn pluralize(name: &str) -> String {
+    if name.ends_with('s')
+        || name.ends_with('x')
+        || name.ends_with('z')
+        || name.ends_with("ch")
+        || name.ends_with("sh")
+    {
+        format!("{}es", name)
+    } else if name.ends_with('y') && !name[..name.len() - 1].ends_with(['a', 'e', 'i', 'o', 'u']) {
+        format!("{}ies", &name[..name.len() - 1])
+    } else {
+        format!("{}s", name)
+    }
+}
+
+/// English singular of the last word of a snake_case name; the inverse of
+/// [`pluralize`] for regular nouns.
+pub fn singularize(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if ["sses", "uses", "xes", "zes", "ches", "shes"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        name[..name.len() - 2].to_string()
+    } else if name.ends_with("ss") || name.ends_with("us") || name.ends_with("is") {
+        name.to_string()
+    } else {
+        name.strip_suffix('s').unwrap_or(name).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        let names = Names::new("BlogPost").unwrap();
+        assert_eq!(names.name, "blog_post");
+        assert_eq!(names.table, "blog_posts");
+        assert_eq!(names.route, "/blog-posts");
+
+        let names = Names::from_table("order_addresses").unwrap();
+        assert_eq!(names.name, "order_address");
+        assert_eq!(names.pascal, "OrderAddress");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(singularize("categories"), "category");
+        assert_eq!(singularize("statuses"), "status");
+    }
+
+    #[test]
+    fn test_wire_routes() {
+        let source = include_str!("../../../templates/base/src/app/routes.rs");
+        let names = Names::new("post").unwrap();
+
+        let wired = wire_routes(source, &names).unwrap();
+
+        assert!(wired.contains("use crate::app::controllers::posts_controller;\n"));
+        assert!(wired.contains("    pub post_service: Arc<PostService<PostRepository>>,\n}"));
+        assert!(wired.contains(
+            "    let post_service = PostService::new(PostRepository::new());\n\n    let state"
+        ));
+        assert!(wired.contains("        post_service: Arc::new(post_service),\n    };"));
+        assert!(wired.contains(
+            "        .route(\n            \"/posts/:id\",\n            get(posts_controller::get_post)\n"
+        ));
+        assert!(wired.contains(
+            ".delete_with(posts_controller::delete_post, |op| op.status(StatusCode::NO_CONTENT)),\n        )\n}\n\n/// The OpenAPI"
+        ));
+
+        let plain = "use axum::{routing::get, Router};\n\n#[derive(Clone)]\npub struct AppState {\n}\n\npub fn build_router() -> Router {\n    let state = AppState {\n    };\n\n    Router::new()\n        .with_state(state)\n}\n";
+        let wired = wire_routes(plain, &names).unwrap();
+        assert!(wired
+            .contains(".delete(posts_controller::delete_post),\n        )\n        .with_state("));
+        assert!(!wired.contains("StatusCode"));
+    }
+}
@@ -0,0 +1,221 @@
+use chrono::Utc;
+use clap::Subcommand;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Location of the migrations in a generated project.
+pub const MIGRATION_DIR: &str = "src/infrastructure/database/migration";
+
+#[derive(Subcommand)]
+pub enum MigrateAction {
+    /// Apply pending migrations
+    Up {
+        /// Number of migrations to apply (default: all)
+        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        n: Option<u32>,
+    },
+    /// Roll back applied migrations
+    Down {
+        /// Number of migrations to roll back
+        #[arg(default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        n: u32,
+    },
+    /// Show which migrations have been applied
+    Status,
+    /// Drop all tables, then apply all migrations
+    Fresh,
+    /// Roll back all applied migrations
+    Reset,
+    /// Create a new migration file
+    Generate {
+        /// Name of the migration, e.g. create_posts
+        name: String,
+    },
+}
+
+pub fn execute(action: MigrateAction) -> anyhow::Result<()> {
+    let project = find_project(&std::env::current_dir()?)?;
+
+    let args = match action {
+        MigrateAction::Generate { name } => return generate(&project, &name),
+        MigrateAction::Up { n } => vec!["up".to_string()]
+            .into_iter()
+            .chain(n.map(|n| n.to_string()))
+            .collect(),
+        MigrateAction::Down { n } => vec!["down".to_string(), n.to_string()],
+        MigrateAction::Status => vec!["status".to_string()],
+        MigrateAction::Fresh => vec!["fresh".to_string()],
+        MigrateAction::Reset => vec!["reset".to_string()],
+    };
+
+    // The project's binary runs the command against its own `Migrator`.
+    let status = Command::new("cargo")
+        .args(["run", "--quiet", "--", "migrate"])
+        .args(&args)
+        .current_dir(&project)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("migrate {} failed", args.join(" "));
+    }
+    Ok(())
+}
+
+/// Returns the nearest directory at or above `dir` that contains a Sword
+/// project.
+pub fn find_project(dir: &Path) -> anyhow::Result<PathBuf> {
+    dir.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file() && dir.join(MIGRATION_DIR).is_dir())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Not inside a Sword project (no {} directory found)",
+                MIGRATION_DIR
+            )
+        })
+}
+
+fn generate(project: &Path, name: &str) -> anyhow::Result<()> {
+    let module = module_name(&normalize_name(name)?);
+    let dir = project.join(MIGRATION_DIR);
+    let file = dir.join(format!("{}.rs", module));
+    if file.exists() {
+        anyhow::bail!("Migration '{}' already exists", file.display());
+    }
+
+    let mod_path = dir.join("mod.rs");
+    let registered = register(&fs::read_to_string(&mod_path)?, &module)?;
+    fs::write(&file, MIGRATION_TEMPLATE)?;
+    fs::write(&mod_path, registered)?;
+
+    println!("✓ Created {}", file.display());
+    println!("  Registered in {}", mod_path.display());
+    println!("  Fill in up() and down() before running `sword migrate up`");
+    Ok(())
+}
+
+/// Returns the timestamped module name `mYYYYMMDD_HHMMSS_<name>`.
+pub fn module_name(name: &str) -> String {
+    format!("m{}_{}", Utc::now().format("%Y%m%d_%H%M%S"), name)
+}
+
+/// Turns a migration name into a snake_case module suffix.
+fn normalize_name(name: &str) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let out = out.trim_end_matches('_').to_string();
+    if out.is_empty() {
+        anyhow::bail!("Migration name must contain letters or digits");
+    }
+    Ok(out)
+}
+
+/// Adds `module` to the `mod` declarations and to the `vec![...]` returned
+/// by `Migrator::migrations` in `mod.rs`.
+pub fn register(source: &str, module: &str) -> anyhow::Result<String> {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+
+    let decl = format!("mod {};", module);
+    let after = lines
+        .iter()
+        .rposition(|line| line.starts_with("mod m") || line.starts_with("pub mod m"))
+        .map_or(0, |i| i + 1);
+    lines.insert(after, decl);
+
+    let start = lines
+        .iter()
+        .position(|line| line.contains("vec!["))
+        .ok_or_else(|| anyhow::anyhow!("No `vec![...]` of migrations found in mod.rs"))?;
+    let end = (start..lines.len())
+        .find(|&i| lines[i].trim_end().ends_with(']'))
+        .ok_or_else(|| anyhow::anyhow!("Unterminated `vec![` in mod.rs"))?;
+
+    let indent: String = lines[start]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let body = lines[start..=end].join("\n");
+    let open = body.find("vec![").map(|i| i + "vec![".len()).unwrap_or(0);
+    let close = body.rfind(']').unwrap_or(body.len());
+    let mut entries: Vec<String> = body[open..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect();
+    entries.push(format!("Box::new({}::Migration)", module));
+
+    let mut replacement = vec![format!("{}vec![", indent)];
+    replacement.extend(
+        entries
+            .iter()
+            .map(|entry| format!("{}    {},", indent, entry)),
+    );
+    replacement.push(format!("{}]", indent));
+    lines.splice(start..=end, replacement);
+
+    Ok(lines.join("\n") + "\n")
+}
+
+const MIGRATION_TEMPLATE: &str = r#"use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Apply the migration here, e.g. with `manager.create_table(...)`.
+        // Until then it is recorded as applied without changing anything.
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Revert what `up` does, e.g. with `manager.drop_table(...)`.
+        Ok(())
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_migration() {
+        let source = "mod m20220101_000001_create_user;\n\
+                      \n\
+                      use sea_orm_migration::prelude::*;\n\
+                      \n\
+                      pub struct Migrator;\n\
+                      \n\
+                      #[async_trait::async_trait]\n\
+                      impl MigratorTrait for Migrator {\n    \
+                          fn migrations() -> Vec<Box<dyn MigrationTrait>> {\n        \
+                              vec![Box::new(m20220101_000001_create_user::Migration)]\n    \
+                          }\n\
+                      }\n";
+
+        let registered = register(source, "m20240301_120000_create_posts").unwrap();
+
+        assert!(registered.starts_with(
+            "mod m20220101_000001_create_user;\nmod m20240301_120000_create_posts;\n"
+        ));
+        assert!(registered.contains(
+            "        vec![\n            \
+             Box::new(m20220101_000001_create_user::Migration),\n            \
+             Box::new(m20240301_120000_create_posts::Migration),\n        \
+             ]\n"
+        ));
+        assert_eq!(normalize_name("AddPosts-index").unwrap(), "add_posts_index");
+    }
+}
@@ -1 +1,4 @@
+pub mod generate;
+pub mod migrate;
 pub mod new;
+pub mod openapi;
@@ -0,0 +1,39 @@
+use clap::Subcommand;
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::migrate::find_project;
+
+#[derive(Subcommand)]
+pub enum OpenApiAction {
+    /// Write the OpenAPI document, e.g. to diff it in CI
+    Export {
+        /// Output file, or `-` for stdout
+        #[arg(short, long, default_value = "openapi.json")]
+        output: PathBuf,
+    },
+}
+
+pub fn execute(action: OpenApiAction) -> anyhow::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let project = find_project(&cwd)?;
+
+    let OpenApiAction::Export { output } = action;
+    let to_stdout = output.as_os_str() == "-";
+    let output = if to_stdout { output } else { cwd.join(output) };
+
+    // The project's binary builds the document from its own routes, without
+    // connecting to the database.
+    let status = Command::new("cargo")
+        .args(["run", "--quiet", "--", "openapi", "export"])
+        .arg(&output)
+        .current_dir(&project)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("openapi export failed");
+    }
+    if !to_stdout {
+        println!("✓ Wrote {}", output.display());
+    }
+    Ok(())
+}
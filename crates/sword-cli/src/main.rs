@@ -33,6 +33,21 @@ enum Commands {
         #[arg(short, long)]
         port: Option<u16>,
     },
+    /// Run or create database migrations (inside a Sword project)
+    Migrate {
+        #[command(subcommand)]
+        action: commands::migrate::MigrateAction,
+    },
+    /// Generate code inside a Sword project
+    Generate {
+        #[command(subcommand)]
+        action: commands::generate::GenerateAction,
+    },
+    /// Export the project's OpenAPI document (inside a Sword project)
+    Openapi {
+        #[command(subcommand)]
+        action: commands::openapi::OpenApiAction,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -48,6 +63,15 @@ fn main() -> anyhow::Result<()> {
         } => {
             commands::new::execute(name, &out_dir, init, no_interactive, port)?;
         }
+        Commands::Migrate { action } => {
+            commands::migrate::execute(action)?;
+        }
+        Commands::Generate { action } => {
+            commands::generate::execute(action)?;
+        }
+        Commands::Openapi { action } => {
+            commands::openapi::execute(action)?;
+        }
     }
 
     Ok(())
@@ -4,17 +4,29 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use sword_ai::db::Tx;
+use sword_ai::openapi::ApiSchema;
+use sword_ai::pagination::{Page, Pagination};
+use sword_ai::validation::{Validate, ValidatedJson, Validator};
+use sword_ai::SwordError;
 
 use crate::app::routes::AppState;
+use crate::domain::entities::user::User;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ApiSchema)]
 pub struct CreateUserRequest {
     pub name: String,
     pub email: String,
 }
 
-#[derive(Debug, Serialize)]
+impl Validate for CreateUserRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("name", &self.name).length(1..=100);
+        v.field("email", &self.email).email();
+    }
+}
+
+#[derive(Debug, Serialize, ApiSchema)]
 pub struct UserResponse {
     pub id: i64,
     pub name: String,
@@ -22,49 +34,55 @@ pub struct UserResponse {
     pub created_at: String,
 }
 
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            created_at: user.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Runs in a transaction: it is committed when this returns `Ok` and rolled
+/// back on error. An invalid field is a `422 Unprocessable Entity` and a
+/// duplicate email a `409 Conflict`.
 pub async fn create_user(
     State(state): State<AppState>,
-    Json(payload): Json<CreateUserRequest>,
-) -> Result<(StatusCode, Json<UserResponse>), (StatusCode, String)> {
+    tx: Tx,
+    ValidatedJson(payload): ValidatedJson<CreateUserRequest>,
+) -> Result<(StatusCode, Json<UserResponse>), SwordError> {
     let user = state
         .user_service
-        .create_user(payload.name, payload.email)
-        .await
-        .map_err(|e| {
-            error!("Failed to create user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
-        })?;
-
-    let response = UserResponse {
-        id: user.id,
-        name: user.name,
-        email: user.email,
-        created_at: user.created_at.to_rfc3339(),
-    };
+        .create_user(&*tx, payload.name, payload.email)
+        .await?;
 
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok((StatusCode::CREATED, Json(user.into())))
 }
 
 pub async fn get_user(
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<UserResponse>, (StatusCode, String)> {
+) -> Result<Json<UserResponse>, SwordError> {
     let user = state
         .user_service
-        .get_user(id)
-        .await
-        .map_err(|e| {
-            error!("Failed to get user {}: {}", id, e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
-        })?
-        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+        .get_user(state.db.reader(), id)
+        .await?
+        .ok_or_else(|| SwordError::not_found("User not found"))?;
 
-    let response = UserResponse {
-        id: user.id,
-        name: user.name,
-        email: user.email,
-        created_at: user.created_at.to_rfc3339(),
-    };
+    Ok(Json(user.into()))
+}
+
+/// Lists users a page at a time: `GET /users?page=2&per_page=20`.
+pub async fn list_users(
+    State(state): State<AppState>,
+    pagination: Pagination,
+) -> Result<Page<UserResponse>, SwordError> {
+    let users = state
+        .user_service
+        .list_users(state.db.reader(), &pagination)
+        .await?;
 
-    Ok(Json(response))
+    Ok(users.map(Into::into))
 }
@@ -1,5 +1,7 @@
-use axum::{routing::get, routing::post, Router};
-use sword_ai::FrameworkContext;
+use axum::http::StatusCode;
+use axum::Router;
+use sword_ai::openapi::{get, ApiRouter, OpenApi};
+use sword_ai::{Db, FrameworkContext};
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
@@ -9,20 +11,40 @@ use crate::domain::repositories::user_repository::UserRepository;
 
 #[derive(Clone)]
 pub struct AppState {
+    pub db: Db,
     pub user_service: Arc<UserService<UserRepository>>,
 }
 
+/// The API routes, documented from their handlers.
+pub fn api() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .route(
+            "/users",
+            get(users_controller::list_users)
+                .post_with(users_controller::create_user, |op| op.status(StatusCode::CREATED)),
+        )
+        .route("/users/:id", get(users_controller::get_user))
+}
+
+/// The OpenAPI document of `api()`, served at `/openapi.json` and written
+/// by `sword openapi export`.
+pub fn openapi() -> OpenApi {
+    api()
+        .openapi()
+        .info(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
 pub fn build_router(ctx: &FrameworkContext) -> Router {
-    let user_repository = UserRepository::new(ctx.db.clone());
+    let user_repository = UserRepository::new();
     let user_service = UserService::new(user_repository);
 
     let state = AppState {
+        db: ctx.db.clone(),
         user_service: Arc::new(user_service),
     };
 
-    Router::new()
-        .route("/users", post(users_controller::create_user))
-        .route("/users/:id", get(users_controller::get_user))
+    api()
+        .into_router()
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
@@ -7,3 +7,10 @@ pub struct User {
     pub name: String,
     pub email: String,
 }
+
+/// Fields of a user to create.
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub name: String,
+    pub email: String,
+}
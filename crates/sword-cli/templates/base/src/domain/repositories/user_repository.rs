@@ -1,51 +1,23 @@
 use crate::domain::entities::user::User;
 use crate::infrastructure::database::models::users;
-use async_trait::async_trait;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use sword_ai::db::CrudRepository;
 
-#[async_trait]
-pub trait UserRepositoryTrait: Send + Sync {
-    async fn create(&self, name: String, email: String) -> anyhow::Result<User>;
-    async fn find_by_id(&self, id: i64) -> anyhow::Result<Option<User>>;
-}
+/// Create, find, list, update and delete come from `CrudRepository`, which
+/// maps rows with the `From` impls in `models/users.rs`. Add queries of
+/// your own to this trait.
+///
+/// Repository methods take the connection to run on, so the same code works
+/// with `ctx.db.reader()`, `ctx.db.writer()` or a request transaction (`&*tx`).
+pub trait UserRepositoryTrait: CrudRepository<users::Entity, User> {}
 
-pub struct UserRepository {
-    db: DatabaseConnection,
-}
+pub struct UserRepository;
 
 impl UserRepository {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new() -> Self {
+        Self
     }
 }
 
-#[async_trait]
-impl UserRepositoryTrait for UserRepository {
-    async fn create(&self, name: String, email: String) -> anyhow::Result<User> {
-        let user = users::ActiveModel {
-            name: Set(name),
-            email: Set(email),
-            ..Default::default()
-        };
-
-        let result = user.insert(&self.db).await?;
-
-        Ok(User {
-            id: result.id,
-            created_at: result.created_at,
-            name: result.name,
-            email: result.email,
-        })
-    }
-
-    async fn find_by_id(&self, id: i64) -> anyhow::Result<Option<User>> {
-        let user = users::Entity::find_by_id(id).one(&self.db).await?;
+impl CrudRepository<users::Entity, User> for UserRepository {}
 
-        Ok(user.map(|u| User {
-            id: u.id,
-            created_at: u.created_at,
-            name: u.name,
-            email: u.email,
-        }))
-    }
-}
+impl UserRepositoryTrait for UserRepository {}
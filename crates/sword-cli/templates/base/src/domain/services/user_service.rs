@@ -1,5 +1,8 @@
-use crate::domain::entities::user::User;
+use crate::domain::entities::user::{NewUser, User};
 use crate::domain::repositories::user_repository::UserRepositoryTrait;
+use sword_ai::db::{Executor, ListQuery};
+use sword_ai::pagination::{Page, Pagination};
+use sword_ai::SwordError;
 
 pub struct UserService<R: UserRepositoryTrait> {
     repository: R,
@@ -10,11 +13,24 @@ impl<R: UserRepositoryTrait> UserService<R> {
         Self { repository }
     }
 
-    pub async fn create_user(&self, name: String, email: String) -> anyhow::Result<User> {
-        self.repository.create(name, email).await
+    pub async fn create_user<C: Executor>(
+        &self,
+        db: &C,
+        name: String,
+        email: String,
+    ) -> Result<User, SwordError> {
+        Ok(self.repository.create(db, NewUser { name, email }).await?)
     }
 
-    pub async fn get_user(&self, id: i64) -> anyhow::Result<Option<User>> {
-        self.repository.find_by_id(id).await
+    pub async fn get_user<C: Executor>(&self, db: &C, id: i64) -> Result<Option<User>, SwordError> {
+        Ok(self.repository.find(db, id).await?)
+    }
+
+    pub async fn list_users<C: Executor>(
+        &self,
+        db: &C,
+        pagination: &Pagination,
+    ) -> Result<Page<User>, SwordError> {
+        Ok(self.repository.paginate(db, ListQuery::new(), pagination).await?)
     }
 }
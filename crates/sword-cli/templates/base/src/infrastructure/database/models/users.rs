@@ -1,4 +1,6 @@
+use crate::domain::entities::user::{NewUser, User};
 use sea_orm::entity::prelude::*;
+use sea_orm::Set;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "users")]
@@ -14,3 +16,24 @@ pub struct Model {
 pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for User {
+    fn from(model: Model) -> Self {
+        Self {
+            id: model.id,
+            created_at: model.created_at,
+            name: model.name,
+            email: model.email,
+        }
+    }
+}
+
+impl From<NewUser> for ActiveModel {
+    fn from(input: NewUser) -> Self {
+        Self {
+            name: Set(input.name),
+            email: Set(input.email),
+            ..Default::default()
+        }
+    }
+}
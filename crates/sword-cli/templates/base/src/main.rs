@@ -2,7 +2,9 @@ mod app;
 mod domain;
 mod infrastructure;
 
-use sword_ai::server;
+use sword_ai::db::MigrateCommand;
+use sword_ai::openapi::OpenApiCommand;
+use sword_ai::SwordApp;
 use infrastructure::database::migration::Migrator;
 
 #[tokio::main]
@@ -10,9 +12,19 @@ async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
     sword_ai::tracing::init_tracing();
 
-    server::run_with_migrator::<Migrator, _>(
-        |ctx| app::routes::build_router(ctx),
-        true,
-    )
-    .await
+    // `sword migrate ...` runs `cargo run -- migrate ...`
+    if let Some(command) = MigrateCommand::from_args(std::env::args())? {
+        return command.run::<Migrator>(std::io::stdout()).await;
+    }
+    // `sword openapi export` runs `cargo run -- openapi export <file>`
+    if let Some(command) = OpenApiCommand::from_args(std::env::args())? {
+        return command.run(&app::routes::openapi());
+    }
+
+    SwordApp::builder()
+        .migrator::<Migrator>()
+        .openapi(app::routes::openapi())
+        .router(app::routes::build_router)
+        .serve()
+        .await
 }
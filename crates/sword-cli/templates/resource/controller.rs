@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+{{controller_imports}}use serde::{Deserialize, Serialize};
+use sword_ai::db::Tx;
+use sword_ai::openapi::ApiSchema;
+use sword_ai::pagination::{Page, Pagination};
+use sword_ai::validation::{Validate, ValidatedJson, Validator};
+use sword_ai::SwordError;
+
+use crate::app::routes::AppState;
+use crate::domain::entities::{{name}}::{New{{Name}}, {{Name}}};
+
+#[derive(Debug, Deserialize, ApiSchema)]
+pub struct {{Name}}Request {
+{{request_fields}}}
+
+impl Validate for {{Name}}Request {
+    fn validate(&self, {{validator}}: &mut Validator) {
+{{validation_rules}}    }
+}
+
+#[derive(Debug, Serialize, ApiSchema)]
+pub struct {{Name}}Response {
+{{response_fields}}}
+
+impl From<{{Name}}Request> for New{{Name}} {
+    fn from(request: {{Name}}Request) -> Self {
+        Self {
+{{new_from_request}}        }
+    }
+}
+
+impl From<{{Name}}> for {{Name}}Response {
+    fn from({{name}}: {{Name}}) -> Self {
+        Self {
+{{response_from_entity}}        }
+    }
+}
+
+fn not_found() -> SwordError {
+    SwordError::not_found("{{Label}} not found")
+}
+
+/// Runs in a transaction: it is committed when this returns `Ok` and rolled
+/// back on error.
+pub async fn create_{{name}}(
+    State(state): State<AppState>,
+    tx: Tx,
+    ValidatedJson(payload): ValidatedJson<{{Name}}Request>,
+) -> Result<(StatusCode, Json<{{Name}}Response>), SwordError> {
+    let {{name}} = state
+        .{{name}}_service
+        .create_{{name}}(&*tx, payload.into())
+        .await?;
+
+    Ok((StatusCode::CREATED, Json({{name}}.into())))
+}
+
+pub async fn get_{{name}}(
+    State(state): State<AppState>,
+    Path(id): Path<{{Id}}>,
+) -> Result<Json<{{Name}}Response>, SwordError> {
+    let {{name}} = state
+        .{{name}}_service
+        .get_{{name}}(state.db.reader(), id)
+        .await?
+        .ok_or_else(not_found)?;
+
+    Ok(Json({{name}}.into()))
+}
+
+/// Returns one page at a time: `?page=2&per_page=20`.
+pub async fn list_{{names}}(
+    State(state): State<AppState>,
+    pagination: Pagination,
+) -> Result<Page<{{Name}}Response>, SwordError> {
+    let {{names}} = state
+        .{{name}}_service
+        .list_{{names}}(state.db.reader(), &pagination)
+        .await?;
+
+    Ok({{names}}.map(Into::into))
+}
+
+pub async fn update_{{name}}(
+    State(state): State<AppState>,
+    tx: Tx,
+    Path(id): Path<{{Id}}>,
+    ValidatedJson(payload): ValidatedJson<{{Name}}Request>,
+) -> Result<Json<{{Name}}Response>, SwordError> {
+    let {{name}} = state
+        .{{name}}_service
+        .update_{{name}}(&*tx, id, payload.into())
+        .await?
+        .ok_or_else(not_found)?;
+
+    Ok(Json({{name}}.into()))
+}
+
+pub async fn delete_{{name}}(
+    State(state): State<AppState>,
+    tx: Tx,
+    Path(id): Path<{{Id}}>,
+) -> Result<StatusCode, SwordError> {
+    let deleted = state
+        .{{name}}_service
+        .delete_{{name}}(&*tx, id)
+        .await?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
@@ -0,0 +1,8 @@
+{{entity_imports}}#[derive(Debug, Clone)]
+pub struct {{Name}} {
+{{entity_fields}}}
+
+/// Fields of a {{label}} to create or update.
+#[derive(Debug, Clone)]
+pub struct New{{Name}} {
+{{input_fields}}}
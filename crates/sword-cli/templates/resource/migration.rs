@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table({{Table}}::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new({{Table}}::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new({{Table}}::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+{{columns}}{{foreign_keys}}                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table({{Table}}::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum {{Table}} {
+    Table,
+    Id,
+    CreatedAt,
+{{idens}}}
+{{referenced_idens}}
@@ -0,0 +1,25 @@
+use crate::domain::entities::{{name}}::{New{{Name}}, {{Name}}};
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel{{eq}})]
+#[sea_orm(table_name = "{{table}}")]
+pub struct Model {
+{{model_fields}}}
+
+{{relation}}
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for {{Name}} {
+    fn from(model: Model) -> Self {
+        Self {
+{{entity_from_model}}        }
+    }
+}
+
+impl From<New{{Name}}> for ActiveModel {
+    fn from(input: New{{Name}}) -> Self {
+        Self {
+{{active_set}}{{active_default}}        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::domain::entities::{{name}}::{{Name}};
+use crate::infrastructure::database::models::{{table}};
+use sword_ai::db::CrudRepository;
+
+/// Create, find, list, update and delete come from `CrudRepository`, which
+/// maps rows with the `From` impls in `models/{{table}}.rs`. Add queries of
+/// your own to this trait.
+///
+/// Repository methods take the connection to run on, so the same code works
+/// with `ctx.db.reader()`, `ctx.db.writer()` or a request transaction (`&*tx`).
+pub trait {{Name}}RepositoryTrait: CrudRepository<{{table}}::Entity, {{Name}}> {}
+
+pub struct {{Name}}Repository;
+
+impl {{Name}}Repository {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl CrudRepository<{{table}}::Entity, {{Name}}> for {{Name}}Repository {}
+
+impl {{Name}}RepositoryTrait for {{Name}}Repository {}
@@ -0,0 +1,44 @@
+use crate::domain::entities::{{name}}::{New{{Name}}, {{Name}}};
+use crate::domain::repositories::{{name}}_repository::{{Name}}RepositoryTrait;
+use sword_ai::db::{Executor, ListQuery};
+use sword_ai::pagination::{Page, Pagination};
+use sword_ai::SwordError;
+{{id_imports}}
+pub struct {{Name}}Service<R: {{Name}}RepositoryTrait> {
+    repository: R,
+}
+
+impl<R: {{Name}}RepositoryTrait> {{Name}}Service<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    pub async fn create_{{name}}<C: Executor>(&self, db: &C, input: New{{Name}}) -> Result<{{Name}}, SwordError> {
+        Ok(self.repository.create(db, input).await?)
+    }
+
+    pub async fn get_{{name}}<C: Executor>(&self, db: &C, id: {{Id}}) -> Result<Option<{{Name}}>, SwordError> {
+        Ok(self.repository.find(db, id).await?)
+    }
+
+    pub async fn list_{{names}}<C: Executor>(
+        &self,
+        db: &C,
+        pagination: &Pagination,
+    ) -> Result<Page<{{Name}}>, SwordError> {
+        Ok(self.repository.paginate(db, ListQuery::new(), pagination).await?)
+    }
+
+    pub async fn update_{{name}}<C: Executor>(
+        &self,
+        db: &C,
+        id: {{Id}},
+        input: New{{Name}},
+    ) -> Result<Option<{{Name}}>, SwordError> {
+        Ok(self.repository.update(db, id, input).await?)
+    }
+
+    pub async fn delete_{{name}}<C: Executor>(&self, db: &C, id: {{Id}}) -> Result<bool, SwordError> {
+        Ok(self.repository.delete(db, id).await?)
+    }
+}